@@ -0,0 +1,260 @@
+//! A thread-safe mutable memory location, which uses native atomics where it can
+//!
+//! If `T` is a primitive integer, `bool`, `char`, or float, and has the same size and alignment
+//! as one of the native atomic integers, then [`AtomicCell`] uses that atomic integer directly,
+//! and never locks. Other types may contain padding bytes, which can't be read as an integer, so
+//! they always fall back to the [global lock set](crate::mutex::global), which picks a lock from
+//! the address of the cell. So unrelated cells may contend, but the cell itself is no larger than `T`.
+
+use core::any::TypeId;
+use core::fmt;
+use core::mem::{align_of, size_of, transmute_copy};
+use core::sync::atomic::{AtomicU16, AtomicU32, AtomicU8, Ordering};
+
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+
+use crate::mutex::global::{GlobalLock, Mutex};
+
+/// A thread-safe mutable memory location, see the [module level docs](self) for details
+#[repr(transparent)]
+pub struct AtomicCell<T> {
+    inner: Mutex<T>,
+}
+
+#[inline(always)]
+fn can_transmute<T: 'static, A>() -> bool {
+    is_primitive::<T>() && size_of::<T>() == size_of::<A>() && align_of::<T>() >= align_of::<A>()
+}
+
+// primitives have no padding bytes, and every bit pattern read back was written as a `T`
+#[inline(always)]
+fn is_primitive<T: 'static>() -> bool {
+    let id = TypeId::of::<T>();
+
+    [
+        TypeId::of::<u8>(),
+        TypeId::of::<u16>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<u64>(),
+        TypeId::of::<usize>(),
+        TypeId::of::<i8>(),
+        TypeId::of::<i16>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<i64>(),
+        TypeId::of::<isize>(),
+        TypeId::of::<bool>(),
+        TypeId::of::<char>(),
+        TypeId::of::<f32>(),
+        TypeId::of::<f64>(),
+    ]
+    .contains(&id)
+}
+
+// run `$atomic` with `$a` bound to the native atomic for `T`,
+// or `$fallback` if there is no native atomic for `T`
+macro_rules! atomic {
+    ($self:ident, $a:ident => $atomic:expr, $fallback:expr) => {
+        loop {
+            atomic!(@check $self, AtomicU8, $a => $atomic);
+            atomic!(@check $self, AtomicU16, $a => $atomic);
+            atomic!(@check $self, AtomicU32, $a => $atomic);
+            #[cfg(target_has_atomic = "64")]
+            atomic!(@check $self, AtomicU64, $a => $atomic);
+            break $fallback;
+        }
+    };
+    (@check $self:ident, $atomic:ident, $a:ident => $body:expr) => {
+        if can_transmute::<T, $atomic>() {
+            // SAFETY: `T` is a primitive with the same size as the atomic, and is at least as
+            // aligned, and the value is only accessed through the atomic
+            let $a = unsafe { &*($self.as_ptr() as *const $atomic) };
+            #[allow(unused_unsafe)]
+            break unsafe { $body };
+        }
+    };
+}
+
+impl<T> AtomicCell<T> {
+    /// Create a new atomic cell
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            inner: GlobalLock::mutex(value),
+        }
+    }
+
+    /// Get a raw pointer to the underlying value
+    #[inline]
+    pub fn as_ptr(&self) -> *mut T {
+        self as *const Self as *mut T
+    }
+
+    /// Get a mutable reference to the underlying value
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// Consume the cell, and get the underlying value
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: 'static> AtomicCell<T> {
+    /// Returns true if operations on this cell use native atomics, and never lock
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        let lock_free = can_transmute::<T, AtomicU8>()
+            || can_transmute::<T, AtomicU16>()
+            || can_transmute::<T, AtomicU32>();
+
+        #[cfg(target_has_atomic = "64")]
+        let lock_free = lock_free || can_transmute::<T, AtomicU64>();
+
+        lock_free
+    }
+
+    /// Store a value into the cell
+    #[inline]
+    pub fn store(&self, value: T) {
+        drop(self.swap(value))
+    }
+
+    /// Store a value into the cell, and return the previous value
+    #[inline]
+    pub fn swap(&self, value: T) -> T {
+        atomic!(
+            self,
+            a => {
+                let bits = transmute_copy(&value);
+                core::mem::forget(value);
+                transmute_copy(&a.swap(bits, Ordering::AcqRel))
+            },
+            core::mem::replace(&mut *self.inner.lock(), value)
+        )
+    }
+}
+
+impl<T: Copy + 'static> AtomicCell<T> {
+    /// Load a value from the cell
+    #[inline]
+    pub fn load(&self) -> T {
+        atomic!(
+            self,
+            a => transmute_copy(&a.load(Ordering::Acquire)),
+            *self.inner.lock()
+        )
+    }
+}
+
+impl<T: Copy + Eq + 'static> AtomicCell<T> {
+    /// Store `new` into the cell if the current value is equal to `current`
+    ///
+    /// On success this returns the previous value, which is equal to `current`,
+    /// otherwise this returns the current value
+    pub fn compare_exchange(&self, current: T, new: T) -> Result<T, T> {
+        atomic!(
+            self,
+            a => {
+                let new = transmute_copy(&new);
+                let mut bits = a.load(Ordering::Acquire);
+
+                loop {
+                    let value: T = transmute_copy(&bits);
+
+                    if value != current {
+                        break Err(value);
+                    }
+
+                    // values which are equal may have different bit patterns,
+                    // so retry with the bit pattern in the cell
+                    match a.compare_exchange_weak(bits, new, Ordering::AcqRel, Ordering::Acquire) {
+                        Ok(_) => break Ok(value),
+                        Err(x) => bits = x,
+                    }
+                }
+            },
+            {
+                let mut value = self.inner.lock();
+
+                if *value == current {
+                    Ok(core::mem::replace(&mut *value, new))
+                } else {
+                    Err(*value)
+                }
+            }
+        )
+    }
+}
+
+impl<T: Default> Default for AtomicCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for AtomicCell<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Copy + fmt::Debug + 'static> fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicCell")
+            .field("value", &self.load())
+            .finish()
+    }
+}
+
+#[test]
+fn test_atomic_cell() {
+    assert!(AtomicCell::<u32>::is_lock_free());
+    assert!(!AtomicCell::<[u8; 3]>::is_lock_free());
+    // may have padding
+    assert!(!AtomicCell::<(u8, u16)>::is_lock_free());
+    assert!(!AtomicCell::<[u8; 4]>::is_lock_free());
+    assert!(!AtomicCell::<[u64; 4]>::is_lock_free());
+
+    let cell = AtomicCell::new(0_u32);
+    let wide = AtomicCell::new([0_u64; 4]);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    let mut x = cell.load();
+                    while let Err(y) = cell.compare_exchange(x, x + 1) {
+                        x = y;
+                    }
+
+                    let mut x = wide.load();
+                    loop {
+                        let next = [x[0] + 1, x[1] + 2, x[2] + 3, x[3] + 4];
+                        match wide.compare_exchange(x, next) {
+                            Ok(_) => break,
+                            Err(y) => x = y,
+                        }
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(cell.load(), 4000);
+    assert_eq!(wide.load(), [4000, 8000, 12000, 16000]);
+
+    assert_eq!(cell.swap(1), 4000);
+    assert_eq!(
+        wide.compare_exchange([0; 4], [1; 4]),
+        Err([4000, 8000, 12000, 16000])
+    );
+    wide.store([1; 4]);
+    assert_eq!(wide.into_inner(), [1; 4]);
+}
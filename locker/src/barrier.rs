@@ -0,0 +1,196 @@
+//! A reusable barrier, which blocks a group of threads until all of them have reached it
+//!
+//! The barrier is built on a [`Mutex`] from this crate, so it works with any raw mutex,
+//! including the spin locks on `no_std`. The mutex only protects the count of threads that
+//! reached the barrier. Threads waiting for the rest of the group are parked with
+//! `parking_lot_core` until the last thread releases the round, or spin if the
+//! `parking_lot_core` feature is disabled.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::mutex::default::DefaultLock;
+use crate::mutex::{Mutex, RawMutex};
+use crate::Inhabitted;
+
+/// A barrier enables multiple threads to synchronize the beginning of some computation
+///
+/// The barrier can be reused, once all `n` threads have reached it, it is reset for the next round.
+pub struct Barrier<L: RawMutex = DefaultLock> {
+    num_threads: usize,
+    // the number of threads that reached the barrier in this round
+    count: Mutex<L, usize>,
+    // waiting threads are parked on the address of the generation
+    generation: AtomicUsize,
+}
+
+/// The result of [`Barrier::wait`]
+///
+/// Exactly one thread in each round is the leader
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns true if this thread is the leader of its round
+    ///
+    /// The leader is the last thread to reach the barrier
+    #[inline]
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl fmt::Debug for BarrierWaitResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BarrierWaitResult")
+            .field("is_leader", &self.is_leader())
+            .finish()
+    }
+}
+
+impl<L: RawMutex + crate::Init> Barrier<L> {
+    /// Creates a new barrier that can block `n` threads
+    ///
+    /// If `n` is 0 or 1, then `wait` never blocks, and every thread is a leader
+    #[inline]
+    pub const fn new(n: usize) -> Self {
+        Self {
+            num_threads: n,
+            count: Mutex::new(0),
+            generation: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<L: RawMutex> Barrier<L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// Blocks the current thread until `n` threads have called `wait` in this round
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut count = self.count.lock();
+        let generation = self.generation.load(Ordering::Relaxed);
+        *count += 1;
+
+        if *count >= self.num_threads {
+            // release every thread waiting in this round, and start the next round
+            *count = 0;
+            self.generation
+                .store(generation.wrapping_add(1), Ordering::Release);
+            drop(count);
+            self.unpark_all();
+            return BarrierWaitResult(true);
+        }
+
+        drop(count);
+        self.wait_slow(generation);
+
+        BarrierWaitResult(false)
+    }
+
+    #[cfg(feature = "parking_lot_core")]
+    #[cold]
+    fn wait_slow(&self, generation: usize) {
+        while self.generation.load(Ordering::Acquire) == generation {
+            let validate = || self.generation.load(Ordering::Relaxed) == generation;
+
+            // SAFETY:
+            //   * the key is the address of the generation, which is only used by this barrier
+            //   * `validate` does not panic or call into any function of `parking_lot`
+            //   * `before_sleep` and `timed_out` do nothing
+            unsafe {
+                parking_lot_core::park(
+                    self.key(),
+                    validate,
+                    || {},
+                    |_, _| {},
+                    parking_lot_core::DEFAULT_PARK_TOKEN,
+                    None,
+                );
+            }
+        }
+    }
+
+    #[cfg(not(feature = "parking_lot_core"))]
+    #[cold]
+    fn wait_slow(&self, generation: usize) {
+        let mut backoff = crate::relax::Backoff::new();
+
+        while self.generation.load(Ordering::Acquire) == generation {
+            backoff.snooze();
+        }
+    }
+}
+
+impl<L: RawMutex> Barrier<L> {
+    #[cfg(feature = "parking_lot_core")]
+    #[inline]
+    fn key(&self) -> usize {
+        &self.generation as *const AtomicUsize as usize
+    }
+
+    #[cfg(feature = "parking_lot_core")]
+    #[inline]
+    fn unpark_all(&self) {
+        // SAFETY: the key is the address of the generation, which is only used by this barrier
+        unsafe {
+            parking_lot_core::unpark_all(self.key(), parking_lot_core::DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    #[cfg(not(feature = "parking_lot_core"))]
+    #[inline]
+    fn unpark_all(&self) {}
+}
+
+impl<L: RawMutex> fmt::Debug for Barrier<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Barrier")
+            .field("num_threads", &self.num_threads)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+fn check_barrier<L: RawMutex + crate::Init + Sync>()
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    const THREADS: usize = 4;
+
+    let barrier = Barrier::<L>::new(THREADS);
+    let leaders = AtomicUsize::new(0);
+    let arrived = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|_| {
+                for round in 1..=3 {
+                    arrived.fetch_add(1, Ordering::Relaxed);
+
+                    if barrier.wait().is_leader() {
+                        leaders.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    // every thread reached the barrier before any of them left it
+                    assert!(arrived.load(Ordering::Relaxed) >= round * THREADS);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(leaders.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_barrier() {
+    check_barrier::<DefaultLock>();
+    check_barrier::<crate::mutex::spin::SpinLock>();
+}
@@ -8,3 +8,11 @@ pub use reentrant_panic::ReentrantPanic;
 
 mod debug_checked;
 pub use debug_checked::DebugChecked;
+
+mod cache_padded;
+pub use cache_padded::CachePadded;
+
+mod elision;
+pub use elision::Elidable;
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use elision::Elided;
@@ -43,6 +43,10 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLock for Fair<L> {
     unsafe fn exc_bump(&self) {
         self.0.exc_bump_fair()
     }
+
+    fn exc_lock_addr(&self) -> usize {
+        self.0.exc_lock_addr()
+    }
 }
 
 unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for Fair<L> {
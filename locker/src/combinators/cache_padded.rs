@@ -0,0 +1,270 @@
+use crate::exclusive_lock::{
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+    SplittableExclusiveLock,
+};
+use crate::share_lock::{
+    RawShareLock, RawShareLockCount, RawShareLockFair, RawShareLockRecursive, RawShareLockTimed,
+    RawShareLockUpgrade, RawShareLockUpgradeTimed,
+};
+use crate::{Init, RawLockInfo, RawLockQuery, RawTimedLock};
+
+use crate::mutex::RawMutex;
+use crate::remutex::RawReentrantMutex;
+use crate::rwlock::RawRwLock;
+
+/// Wraps a lock and aligns it to a cache line
+///
+/// This prevents false sharing between locks which are next to each other in memory,
+/// like in an array of mutexes. On `x86_64` and `aarch64` this is aligned to 128 bytes,
+/// because those cpus prefetch pairs of cache lines, otherwise this is aligned to 64 bytes.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CachePadded<L: ?Sized>(pub L);
+
+unsafe impl<L: RawMutex> RawMutex for CachePadded<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for CachePadded<L> {}
+unsafe impl<L: RawReentrantMutex> RawReentrantMutex for CachePadded<L> {}
+
+impl<L: Init> Init for CachePadded<L> {
+    const INIT: Self = Self(Init::INIT);
+}
+
+unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for CachePadded<L> {
+    type ExclusiveGuardTraits = <L as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <L as RawLockInfo>::ShareGuardTraits;
+}
+
+impl<L: RawTimedLock + ?Sized> RawTimedLock for CachePadded<L> {
+    type Instant = L::Instant;
+    type Duration = L::Duration;
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLock> RawExclusiveLock for CachePadded<L> {
+    #[inline]
+    fn exc_lock(&self) {
+        self.0.exc_lock()
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.0.exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.0.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        self.0.exc_bump()
+    }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.0.exc_lock_addr()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for CachePadded<L> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.0.exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.0.exc_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for CachePadded<L> {
+    #[inline]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.0.exc_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.0.exc_try_lock_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawExclusiveLockDowngrade> RawExclusiveLockDowngrade for CachePadded<L> {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        self.0.downgrade()
+    }
+}
+
+unsafe impl<L: ?Sized + SplittableExclusiveLock> SplittableExclusiveLock for CachePadded<L> {
+    #[inline]
+    unsafe fn exc_split(&self) {
+        self.0.exc_split()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLock> RawShareLock for CachePadded<L> {
+    #[inline]
+    fn shr_lock(&self) {
+        self.0.shr_lock()
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.0.shr_try_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        self.0.shr_split()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.0.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        self.0.shr_bump()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockFair> RawShareLockFair for CachePadded<L> {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        self.0.shr_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) {
+        self.0.shr_bump_fair()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockTimed> RawShareLockTimed for CachePadded<L> {
+    #[inline]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.0.shr_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.0.shr_try_lock_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgrade> RawShareLockUpgrade for CachePadded<L> {
+    #[inline]
+    unsafe fn upgrade(&self) {
+        self.0.upgrade()
+    }
+
+    #[inline]
+    unsafe fn try_upgrade(&self) -> bool {
+        self.0.try_upgrade()
+    }
+
+    #[inline]
+    fn shr_lock_upgradable(&self) {
+        self.0.shr_lock_upgradable()
+    }
+
+    #[inline]
+    fn shr_try_lock_upgradable(&self) -> bool {
+        self.0.shr_try_lock_upgradable()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock_upgradable(&self) {
+        self.0.shr_unlock_upgradable()
+    }
+
+    #[inline]
+    unsafe fn upgrade_upgradable(&self) {
+        self.0.upgrade_upgradable()
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_upgradable(&self) -> bool {
+        self.0.try_upgrade_upgradable()
+    }
+
+    #[inline]
+    unsafe fn downgrade_to_upgradable(&self) {
+        self.0.downgrade_to_upgradable()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockUpgradeTimed> RawShareLockUpgradeTimed for CachePadded<L> {
+    #[inline]
+    unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
+        self.0.try_upgrade_until(instant)
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool {
+        self.0.try_upgrade_for(duration)
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockCount> RawShareLockCount for CachePadded<L> {
+    #[inline]
+    fn shr_count(&self) -> usize {
+        self.0.shr_count()
+    }
+}
+
+unsafe impl<L: ?Sized + RawShareLockRecursive> RawShareLockRecursive for CachePadded<L> {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        self.0.shr_lock_recursive()
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        self.0.shr_try_lock_recursive()
+    }
+}
+
+impl<L: ?Sized + RawLockQuery> RawLockQuery for CachePadded<L> {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.0.is_locked()
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.0.is_locked_exclusive()
+    }
+}
+
+unsafe impl<L: ?Sized + super::Elidable> super::Elidable for CachePadded<L> {}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl<L: ?Sized + crate::condvar::Parkable> crate::condvar::Parkable for CachePadded<L> {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.0.park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.0.mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.0.mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.0.is_handoff(token)
+    }
+}
@@ -94,6 +94,10 @@ unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLock for DebugChecked<
         #[cfg(debug_assertions)]
         self.inner.exc_bump_fair()
     }
+
+    fn exc_lock_addr(&self) -> usize {
+        self.inner.exc_lock_addr()
+    }
 }
 
 unsafe impl<L: ?Sized + RawExclusiveLockFair> RawExclusiveLockFair for DebugChecked<L> {
@@ -0,0 +1,293 @@
+/// Locks which can be elided by [`Elided`]
+///
+/// # Safety
+///
+/// * [`is_locked`](crate::RawLockQuery::is_locked) must return true if there is any *exc lock* or *shr lock* on the lock
+/// * `is_locked` must only read from the lock state
+/// * locking or unlocking the lock must write to the state read by `is_locked`,
+///   this ensures that acquiring the lock aborts all elided critical sections
+pub unsafe trait Elidable: crate::RawLockQuery {}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))] {
+        use core::arch::asm;
+
+        use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair};
+        use crate::share_lock::{RawShareLock, RawShareLockFair};
+        use crate::{Init, RawLockInfo};
+
+        use crate::mutex::RawMutex;
+        use crate::rwlock::RawRwLock;
+
+        const XBEGIN_STARTED: u32 = !0;
+        const XABORT_EXPLICIT: u32 = 1 << 0;
+        const XABORT_RETRY: u32 = 1 << 1;
+
+        // the number of times to retry a transaction before falling back to the lock
+        const RETRIES: u32 = 3;
+
+        /// Wraps a lock and tries to elide it using hardware transactional memory (Intel RTM)
+        ///
+        /// Critical sections are first run as hardware transactions, without acquiring the lock.
+        /// The lock state is read at the start of the transaction, so if any thread
+        /// actually acquires the lock, or if two elided critical sections conflict, then the
+        /// transaction is aborted and retried a few times, before falling back to the lock.
+        ///
+        /// This allows non-conflicting critical sections to run in parallel, even if they
+        /// use the exclusive lock, and elided shared locks never write to the lock state.
+        ///
+        /// If the CPU doesn't support RTM, then this always uses the lock.
+        ///
+        /// Transactions are tied to the thread that started them, so the guards of
+        /// this lock are never `Send`. Anything that can't be run in a transaction, like
+        /// system calls, will abort the transaction.
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct Elided<L: ?Sized>(pub L);
+
+        #[inline]
+        fn has_rtm() -> bool {
+            std::is_x86_feature_detected!("rtm")
+        }
+
+        /// Begins a transaction, returns `XBEGIN_STARTED` if the transaction started
+        ///
+        /// If the transaction aborts, then the execution resumes from here
+        /// and this returns the abort status.
+        ///
+        /// # Safety
+        ///
+        /// the CPU must support RTM
+        #[inline]
+        unsafe fn xbegin() -> u32 {
+            let status: u32;
+            asm!("mov eax, -1", "xbegin 2f", "2:", out("eax") status, options(nostack));
+            status
+        }
+
+        /// # Safety
+        ///
+        /// must be in a transaction
+        #[inline]
+        unsafe fn xend() {
+            asm!("xend", options(nostack));
+        }
+
+        /// # Safety
+        ///
+        /// the CPU must support RTM
+        #[inline]
+        unsafe fn xtest() -> bool {
+            let in_transaction: u8;
+            asm!("xtest", "setnz {}", out(reg_byte) in_transaction, options(nostack, nomem));
+            in_transaction != 0
+        }
+
+        /// returns true if the current thread is in a transaction
+        #[inline]
+        fn in_transaction() -> bool {
+            has_rtm() && unsafe { xtest() }
+        }
+
+        impl<L: ?Sized + Elidable> Elided<L> {
+            /// Try to start an elided critical section
+            ///
+            /// If we are already in a transaction, then this either nests the transaction
+            /// or aborts the outermost transaction, so this never returns false
+            /// while in a transaction.
+            #[inline]
+            fn elide(&self) -> bool {
+                if !has_rtm() {
+                    return false;
+                }
+
+                for _ in 0..RETRIES {
+                    let status = unsafe { xbegin() };
+
+                    if status == XBEGIN_STARTED {
+                        // this reads the lock state into the transaction's read set,
+                        // so any thread acquiring the lock will abort the transaction
+                        if !self.0.is_locked() {
+                            return true;
+                        }
+
+                        // the lock is held, so we can't elide it
+                        unsafe { asm!("xabort 0xff", options(nostack)) }
+                    }
+
+                    if status & XABORT_EXPLICIT != 0 || status & XABORT_RETRY == 0 {
+                        break;
+                    }
+                }
+
+                false
+            }
+
+            /// returns true if the current critical section was elided
+            ///
+            /// If we hold the lock, then it is locked, otherwise we must have checked
+            /// that it is unlocked in a transaction, and it can't change without
+            /// aborting the transaction.
+            #[inline]
+            fn is_elided(&self) -> bool {
+                !self.0.is_locked() && in_transaction()
+            }
+        }
+
+        unsafe impl<L: RawMutex + Elidable> RawMutex for Elided<L> {}
+        unsafe impl<L: RawRwLock + Elidable> RawRwLock for Elided<L> {}
+
+        impl<L: Init> Init for Elided<L> {
+            const INIT: Self = Self(Init::INIT);
+        }
+
+        unsafe impl<L: RawLockInfo + ?Sized> RawLockInfo for Elided<L> {
+            type ExclusiveGuardTraits = (<L as RawLockInfo>::ExclusiveGuardTraits, crate::NoSend);
+            type ShareGuardTraits = (
+                <L as RawLockInfo>::ShareGuardTraits,
+                (crate::NoSend, crate::NoSync),
+            );
+        }
+
+        unsafe impl<L: ?Sized + RawExclusiveLock + Elidable> RawExclusiveLock for Elided<L> {
+            #[inline]
+            fn exc_lock(&self) {
+                if !self.elide() {
+                    self.0.exc_lock()
+                }
+            }
+
+            #[inline]
+            fn exc_try_lock(&self) -> bool {
+                self.elide() || self.0.exc_try_lock()
+            }
+
+            #[inline]
+            unsafe fn exc_unlock(&self) {
+                if self.is_elided() {
+                    xend()
+                } else {
+                    self.0.exc_unlock()
+                }
+            }
+
+            #[inline]
+            unsafe fn exc_bump(&self) {
+                if !self.is_elided() {
+                    self.0.exc_bump()
+                }
+            }
+
+            #[inline]
+            fn exc_lock_addr(&self) -> usize {
+                self.0.exc_lock_addr()
+            }
+        }
+
+        unsafe impl<L: ?Sized + RawExclusiveLockFair + Elidable> RawExclusiveLockFair for Elided<L> {
+            #[inline]
+            unsafe fn exc_unlock_fair(&self) {
+                if self.is_elided() {
+                    xend()
+                } else {
+                    self.0.exc_unlock_fair()
+                }
+            }
+
+            #[inline]
+            unsafe fn exc_bump_fair(&self) {
+                if !self.is_elided() {
+                    self.0.exc_bump_fair()
+                }
+            }
+        }
+
+        unsafe impl<L: ?Sized + RawExclusiveLockDowngrade + Elidable> RawExclusiveLockDowngrade
+            for Elided<L>
+        {
+            #[inline]
+            unsafe fn downgrade(&self) {
+                // an elided critical section doesn't distinguish between
+                // exclusive and shared access, so there is nothing to do
+                if !self.is_elided() {
+                    self.0.downgrade()
+                }
+            }
+        }
+
+        unsafe impl<L: ?Sized + RawShareLock + Elidable> RawShareLock for Elided<L> {
+            #[inline]
+            fn shr_lock(&self) {
+                if !self.elide() {
+                    self.0.shr_lock()
+                }
+            }
+
+            #[inline]
+            fn shr_try_lock(&self) -> bool {
+                self.elide() || self.0.shr_try_lock()
+            }
+
+            #[inline]
+            unsafe fn shr_split(&self) {
+                if self.is_elided() {
+                    // nest the transaction, so that each *shr lock* ends exactly one transaction
+                    let status = xbegin();
+                    debug_assert_eq!(status, XBEGIN_STARTED);
+                } else {
+                    self.0.shr_split()
+                }
+            }
+
+            #[inline]
+            unsafe fn shr_unlock(&self) {
+                if self.is_elided() {
+                    xend()
+                } else {
+                    self.0.shr_unlock()
+                }
+            }
+
+            #[inline]
+            unsafe fn shr_bump(&self) {
+                if !self.is_elided() {
+                    self.0.shr_bump()
+                }
+            }
+        }
+
+        unsafe impl<L: ?Sized + RawShareLockFair + Elidable> RawShareLockFair for Elided<L> {
+            #[inline]
+            unsafe fn shr_unlock_fair(&self) {
+                if self.is_elided() {
+                    xend()
+                } else {
+                    self.0.shr_unlock_fair()
+                }
+            }
+
+            #[inline]
+            unsafe fn shr_bump_fair(&self) {
+                if !self.is_elided() {
+                    self.0.shr_bump_fair()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "extra", any(target_arch = "x86", target_arch = "x86_64")))]
+#[test]
+fn test_elided_rwlock() {
+    let lock = crate::rwlock::RwLock::<Elided<crate::rwlock::spin::SpinLock>, _>::new(0);
+
+    *lock.write() += 1;
+
+    let a = lock.read();
+    let b = lock.read();
+    assert_eq!(*a + *b, 2);
+    drop(a);
+    drop(b);
+
+    // if the lock wasn't elided, then the inner lock must be unlocked properly
+    assert!(lock.try_write().is_some());
+}
@@ -92,6 +92,10 @@ unsafe impl<L: ?Sized + RawExclusiveLock, I: ThreadInfo> RawExclusiveLock for Re
         self.inner.exc_bump();
         self.owner.store(owner, Ordering::Release);
     }
+
+    fn exc_lock_addr(&self) -> usize {
+        self.inner.exc_lock_addr()
+    }
 }
 
 unsafe impl<L: ?Sized + RawExclusiveLockFair, I: ThreadInfo> RawExclusiveLockFair
@@ -3,18 +3,65 @@ use crate::share_lock::{RawShareLock, ShareGuard};
 
 use crate::RawLockInfo;
 
+use core::ops::Deref;
+
 use std::time::{Duration, Instant};
 
 pub mod raw;
 
+/// A condition variable which works with the guards of any [`Parkable`] lock
+///
+/// Threads block with [`wait`](Self::wait) (or the timed variants) on a guard, which
+/// releases the lock while the thread is parked, and re-acquires it before returning.
+/// They are woken up with [`notify_one`](Self::notify_one) or [`notify_all`](Self::notify_all).
 pub struct Condvar {
     raw: raw::Condvar,
 }
 
+/// A lock whose guards can be used with a [`Condvar`]
+///
+/// This is implemented for the parking mutex backends, like [`DefaultLock`](crate::mutex::default::DefaultLock),
+/// [`AdaptiveLock`](crate::mutex::adaptive::AdaptiveLock), and [`TaggedLock`](crate::mutex::tagged::TaggedLock)
+///
+/// By default [`Condvar::notify_all`] wakes up every waiting thread. Locks which park their waiting
+/// threads with `parking_lot_core` can override the provided methods, so that `notify_all` wakes up
+/// one thread and moves the rest directly into the lock's queue, instead of waking all of them
+/// just for them to block on the lock again.
+///
 /// # Safety
 ///
-/// `exc_unlock` cannot call `parking_lot_core::park`, or panic
-pub unsafe trait Parkable {}
+/// * `exc_unlock` cannot call `parking_lot_core::park`, or panic
+/// * If `park_key` returns `Some(key)`, then
+///   * threads blocked on the lock must be parked on `key`
+///   * unlocking the lock must unpark a thread if the parked bit is set
+///   * `mark_parked_if_locked` and `mark_parked` cannot call into `parking_lot_core`, or panic
+///   * `is_handoff` must return true only if a thread unparked with `token` owns the lock
+pub unsafe trait Parkable {
+    /// The key that threads blocked on this lock are parked on, or `None` if threads
+    /// waiting on a [`Condvar`] can't be requeued onto this lock
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        None
+    }
+
+    /// Sets the parked bit if the lock is locked, and returns true if it was locked
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        false
+    }
+
+    /// Sets the parked bit
+    #[inline]
+    fn mark_parked(&self) {}
+
+    /// Returns true if a thread unparked from this lock's queue with `token` was handed the lock
+    /// without unlocking it
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        let _ = token;
+        false
+    }
+}
 
 /// A type indicating whether a timed wait on a condition variable returned
 /// due to a time out or not.
@@ -76,6 +123,76 @@ impl Condvar {
     ) -> WaitTimeoutResult {
         guard.wait_for(self, duration)
     }
+
+    /// Waits on this condition variable for a notification, timing out after `duration`
+    ///
+    /// This is the same as [`wait_for`](Self::wait_for)
+    #[inline]
+    pub fn wait_timeout<W: Wait + ?Sized>(
+        &self,
+        guard: &mut W,
+        duration: Duration,
+    ) -> WaitTimeoutResult {
+        guard.wait_for(self, duration)
+    }
+
+    /// Blocks the current thread until `condition` returns false
+    ///
+    /// `condition` is checked before the first wait, and after every wake up,
+    /// so spurious wake ups are handled
+    pub fn wait_while<W, F>(&self, guard: &mut W, mut condition: F)
+    where
+        W: Wait + Deref + ?Sized,
+        F: FnMut(&W::Target) -> bool,
+    {
+        while condition(&**guard) {
+            guard.wait(self);
+        }
+    }
+
+    /// Blocks the current thread until `condition` returns false, or the `instant` is reached
+    ///
+    /// The result is timed out only if `condition` still returns true after the `instant`
+    pub fn wait_while_until<W, F>(
+        &self,
+        guard: &mut W,
+        instant: Instant,
+        mut condition: F,
+    ) -> WaitTimeoutResult
+    where
+        W: Wait + Deref + ?Sized,
+        F: FnMut(&W::Target) -> bool,
+    {
+        while condition(&**guard) {
+            if guard.wait_until(self, instant).timed_out() {
+                return WaitTimeoutResult(condition(&**guard));
+            }
+        }
+
+        WaitTimeoutResult(false)
+    }
+
+    /// Blocks the current thread until `condition` returns false, or `duration` has passed
+    ///
+    /// The result is timed out only if `condition` still returns true after `duration`
+    pub fn wait_timeout_while<W, F>(
+        &self,
+        guard: &mut W,
+        duration: Duration,
+        condition: F,
+    ) -> WaitTimeoutResult
+    where
+        W: Wait + Deref + ?Sized,
+        F: FnMut(&W::Target) -> bool,
+    {
+        match Instant::now().checked_add(duration) {
+            Some(instant) => self.wait_while_until(guard, instant, condition),
+            None => {
+                self.wait_while(guard, condition);
+                WaitTimeoutResult(false)
+            }
+        }
+    }
 }
 
 pub trait Wait {
@@ -122,3 +239,122 @@ impl<L: RawLockInfo + RawShareLock + Parkable, T: ?Sized> Wait for ShareGuard<'_
         unsafe { cv.raw.shr_wait_for(ShareGuard::raw_mut(self), duration) }
     }
 }
+
+#[test]
+fn test_condvar() {
+    let mutex = crate::mutex::tagged::TaggedLock::mutex(false);
+    let condvar = Condvar::new();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            *mutex.lock() = true;
+            condvar.notify_one();
+        });
+
+        let mut guard = mutex.lock();
+        while !*guard {
+            condvar.wait(&mut guard);
+        }
+    })
+    .unwrap();
+
+    let mut guard = mutex.lock();
+    assert!(condvar
+        .wait_for(&mut guard, Duration::from_millis(1))
+        .timed_out());
+    assert_eq!(condvar.notify_all(), 0);
+}
+
+#[test]
+fn test_wait_while() {
+    let mutex = crate::mutex::tagged::TaggedLock::mutex(0);
+    let condvar = Condvar::new();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            for _ in 0..3 {
+                *mutex.lock() += 1;
+                condvar.notify_all();
+            }
+        });
+
+        let mut guard = mutex.lock();
+        condvar.wait_while(&mut guard, |&value| value < 3);
+        assert_eq!(*guard, 3);
+    })
+    .unwrap();
+
+    let mut guard = mutex.lock();
+    let result = condvar.wait_timeout_while(&mut guard, Duration::from_millis(1), |&v| v < 4);
+    assert!(result.timed_out());
+    let result = condvar.wait_timeout_while(&mut guard, Duration::from_millis(1), |&v| v < 3);
+    assert!(!result.timed_out());
+    assert!(condvar
+        .wait_timeout(&mut guard, Duration::from_millis(1))
+        .timed_out());
+}
+
+#[test]
+fn test_notify_all_requeue() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    const THREADS: usize = 4;
+
+    let mutex = crate::mutex::adaptive::AdaptiveLock::mutex(false);
+    let other = crate::mutex::tagged::TaggedLock::mutex(());
+    let condvar = Condvar::new();
+    let waiting = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|_| {
+                let mut guard = mutex.lock();
+                waiting.fetch_add(1, Ordering::Relaxed);
+                while !*guard {
+                    condvar.wait(&mut guard);
+                }
+            });
+        }
+
+        while waiting.load(Ordering::Relaxed) != THREADS {
+            std::thread::yield_now();
+        }
+
+        // the mutex is locked, so every waiting thread is moved onto the mutex's queue,
+        // and then handed the mutex directly by the fair unlocks
+        let mut guard = mutex.lock();
+        *guard = true;
+        assert_eq!(condvar.notify_all(), THREADS);
+        ExclusiveGuard::unlock_fair(guard);
+    })
+    .unwrap();
+
+    // threads waiting with different locks are all woken up
+    let waiting = AtomicUsize::new(0);
+    *mutex.lock() = false;
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            let mut guard = mutex.lock();
+            waiting.fetch_add(1, Ordering::Relaxed);
+            while !*guard {
+                condvar.wait(&mut guard);
+            }
+        });
+
+        s.spawn(|_| {
+            let mut guard = other.lock();
+            waiting.fetch_add(1, Ordering::Relaxed);
+            condvar.wait(&mut guard);
+        });
+
+        while waiting.load(Ordering::Relaxed) != 2 {
+            std::thread::yield_now();
+        }
+
+        let _other = other.lock();
+        *mutex.lock() = true;
+        assert_eq!(condvar.notify_all(), 2);
+    })
+    .unwrap();
+}
@@ -1,31 +1,50 @@
-use parking_lot_core::{self, UnparkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use parking_lot_core::{
+    self, FilterOp, ParkResult, RequeueOp, UnparkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN,
+};
 
 use super::{Parkable, WaitTimeoutResult};
 use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLock};
 use crate::share_lock::{RawShareGuard, RawShareLock};
 use crate::RawLockInfo;
 
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 pub struct Condvar {
     is_parked: AtomicBool,
+    // the park key of the lock that every waiting thread uses, or 0 if the waiting
+    // threads can't be requeued, because they use different locks, or locks which
+    // don't support requeuing
+    requeue_key: AtomicUsize,
+    // the lock that every waiting thread uses, this is only accessed while the queue
+    // for this condvar is locked by `parking_lot_core`, and is only valid while
+    // `requeue_key` is not 0
+    requeue_lock: UnsafeCell<*const dyn Parkable>,
 }
 
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
 impl crate::Init for Condvar {
-    const INIT: Self = Self {
-        is_parked: AtomicBool::new(false),
-    };
+    const INIT: Self = Self::new();
 }
 
 impl Condvar {
     pub const fn new() -> Self {
         Self {
             is_parked: AtomicBool::new(false),
+            requeue_key: AtomicUsize::new(0),
+            requeue_lock: UnsafeCell::new(&NoRequeue as *const dyn Parkable),
         }
     }
 }
 
+// a placeholder for `requeue_lock` when there are no waiting threads
+struct NoRequeue;
+
+unsafe impl Parkable for NoRequeue {}
+
 impl Condvar {
     #[inline]
     pub fn notify_one(&self) -> bool {
@@ -47,6 +66,7 @@ impl Condvar {
                 // Clear our state if there are no more waiting threads
                 if !result.have_more_threads {
                     self.is_parked.store(false, Ordering::Relaxed);
+                    self.requeue_key.store(0, Ordering::Relaxed);
                 }
 
                 DEFAULT_UNPARK_TOKEN
@@ -71,38 +91,155 @@ impl Condvar {
 
     #[cold]
     fn notify_all_slow(&self) -> usize {
-        unsafe {
-            // Unpark one thread and requeue the rest onto the mutex
-            let key = self as *const _ as usize;
-            let unpark_count = parking_lot_core::unpark_all(key, DEFAULT_UNPARK_TOKEN);
-            self.is_parked.store(false, Ordering::Relaxed);
-            unpark_count
+        let addr = self as *const _ as usize;
+
+        loop {
+            let requeue_key = self.requeue_key.load(Ordering::Relaxed);
+
+            if requeue_key == 0 {
+                return self.unpark_all();
+            }
+
+            let requeue_lock = Cell::new(None);
+
+            let validate = || {
+                // the waiting threads changed since `requeue_key` was loaded
+                if !self.is_parked.load(Ordering::Relaxed)
+                    || self.requeue_key.load(Ordering::Relaxed) != requeue_key
+                {
+                    return RequeueOp::Abort;
+                }
+
+                // SAFETY: the queue for this condvar is locked, and there are waiting threads
+                // which use `requeue_lock`, so it's still alive
+                let lock = unsafe { &**self.requeue_lock.get() };
+                requeue_lock.set(Some(lock));
+
+                // all of the waiting threads are about to leave the queue
+                self.is_parked.store(false, Ordering::Relaxed);
+                self.requeue_key.store(0, Ordering::Relaxed);
+
+                // If the lock is locked, then the waiting threads would immediately block on it,
+                // so put all of them in the lock's queue. Otherwise wake up one thread to take
+                // the lock, and put the rest in the lock's queue.
+                if lock.mark_parked_if_locked() {
+                    RequeueOp::RequeueAll
+                } else {
+                    RequeueOp::UnparkOneRequeueRest
+                }
+            };
+
+            let callback = |op, result: UnparkResult| {
+                // the lock's parked bit must be set so that unlocking it wakes the requeued threads
+                if op == RequeueOp::UnparkOneRequeueRest && result.requeued_threads != 0 {
+                    if let Some(lock) = requeue_lock.get() {
+                        lock.mark_parked();
+                    }
+                }
+
+                DEFAULT_UNPARK_TOKEN
+            };
+
+            // SAFETY:
+            //   * `addr` is an address we control, and `requeue_key` is the key of a lock which
+            //     supports requeuing.
+            //   * `validate`/`callback` does not panic or call into any function of `parking_lot`.
+            let result =
+                unsafe { parking_lot_core::unpark_requeue(addr, requeue_key, validate, callback) };
+
+            if requeue_lock.get().is_some() {
+                return result.unparked_threads + result.requeued_threads;
+            }
+
+            if !self.is_parked.load(Ordering::Relaxed) {
+                return 0;
+            }
         }
     }
 
+    fn unpark_all(&self) -> usize {
+        let addr = self as *const _ as usize;
+        let callback = |_| {
+            self.is_parked.store(false, Ordering::Relaxed);
+            self.requeue_key.store(0, Ordering::Relaxed);
+            DEFAULT_UNPARK_TOKEN
+        };
+
+        // SAFETY:
+        //   * `addr` is an address we control.
+        //   * the filter and `callback` does not panic or call into any function of `parking_lot`.
+        let result =
+            unsafe { parking_lot_core::unpark_filter(addr, |_| FilterOp::Unpark, callback) };
+
+        result.unparked_threads
+    }
+
+    /// # Safety
+    ///
+    /// `parkable` must be the lock that `lock` and `unlock` act on, if it is given
     #[cold]
     #[inline(never)]
     unsafe fn wait(
         &self,
         timeout: Option<Instant>,
+        parkable: Option<&dyn Parkable>,
         lock: impl FnOnce(),
         unlock: impl FnOnce(),
     ) -> WaitTimeoutResult {
         let result;
         {
             let addr = self as *const _ as usize;
-            let validate = || self.is_parked.load(Ordering::Relaxed);
-            let timed_out = |_, was_last_thread| {
+            let key = parkable
+                .and_then(|parkable| parkable.park_key())
+                .unwrap_or(0);
+
+            let validate = || {
+                // the queue for this condvar is locked, so `requeue_lock` can be accessed
+                let parkable = parkable.filter(|_| key != 0).map(|parkable| {
+                    // the lock outlives this thread's wait, and `requeue_lock` is only
+                    // accessed while a thread using the same lock is waiting
+                    core::mem::transmute::<*const (dyn Parkable + '_), *const dyn Parkable>(
+                        parkable,
+                    )
+                });
+
+                if !self.is_parked.load(Ordering::Relaxed) {
+                    if let Some(parkable) = parkable {
+                        *self.requeue_lock.get() = parkable;
+                    }
+
+                    self.requeue_key.store(key, Ordering::Relaxed);
+                    self.is_parked.store(true, Ordering::Relaxed);
+                } else {
+                    let same_lock = match parkable {
+                        Some(parkable) => core::ptr::eq(
+                            *self.requeue_lock.get() as *const u8,
+                            parkable as *const u8,
+                        ),
+                        None => false,
+                    };
+
+                    if !same_lock || self.requeue_key.load(Ordering::Relaxed) != key {
+                        // the waiting threads use different locks, so they can't be requeued
+                        self.requeue_key.store(0, Ordering::Relaxed);
+                    }
+                }
+
+                true
+            };
+
+            let timed_out = |key, was_last_thread| {
                 // If we were the last thread on the queue then we need to
                 // clear our state. This is normally done by the
                 // notify_{one,all} functions when not timing out.
-                if was_last_thread {
+                // If this thread was requeued onto the lock, then the condvar's queue was
+                // already cleared by `notify_all`.
+                if was_last_thread && key == addr {
                     self.is_parked.store(false, Ordering::Relaxed);
+                    self.requeue_key.store(0, Ordering::Relaxed);
                 }
             };
 
-            self.is_parked.store(true, Ordering::Relaxed);
-
             result = parking_lot_core::park(
                 addr,
                 validate,
@@ -113,7 +250,11 @@ impl Condvar {
             );
         }
 
-        lock();
+        match (result, parkable) {
+            // this thread was requeued onto the lock, and the lock was handed off to this thread
+            (ParkResult::Unparked(token), Some(parkable)) if parkable.is_handoff(token) => (),
+            _ => lock(),
+        }
 
         WaitTimeoutResult(!result.is_unparked())
     }
@@ -121,12 +262,19 @@ impl Condvar {
 
 impl Condvar {
     #[inline]
-    fn exc_wait_until_internal(
+    fn exc_wait_until_internal<L: RawExclusiveLock + Parkable>(
         &self,
-        lock: &dyn RawExclusiveLock,
+        lock: &L,
         timeout: Option<Instant>,
     ) -> WaitTimeoutResult {
-        unsafe { self.wait(timeout, || lock.exc_lock(), || lock.exc_unlock()) }
+        unsafe {
+            self.wait(
+                timeout,
+                Some(lock),
+                || lock.exc_lock(),
+                || lock.exc_unlock(),
+            )
+        }
     }
 
     #[inline]
@@ -163,7 +311,7 @@ impl Condvar {
         lock: &dyn RawShareLock,
         timeout: Option<Instant>,
     ) -> WaitTimeoutResult {
-        unsafe { self.wait(timeout, || lock.shr_lock(), || lock.shr_unlock()) }
+        unsafe { self.wait(timeout, None, || lock.shr_lock(), || lock.shr_unlock()) }
     }
 
     #[inline]
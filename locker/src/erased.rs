@@ -0,0 +1,84 @@
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveGuard, RawExclusiveLock};
+use crate::share_lock::{
+    RawShareGuard, RawShareLock, RawShareLockUpgrade, RawUpgradableGuard, ShareGuard,
+    UpgradableGuard,
+};
+use crate::RawLockInfo;
+use core::fmt;
+use std::boxed::Box;
+
+trait Erased {}
+
+impl<T: ?Sized> Erased for T {}
+
+/// A type-erased guard, which releases the lock it holds when dropped
+///
+/// Any guard can be converted into an `ErasedGuard`, so guards over locks with different
+/// backends or protected data can be stored together, for example in a `Vec<ErasedGuard>`.
+/// The data can't be accessed through an `ErasedGuard`, it only holds on to the lock.
+#[must_use = "if unused the `ErasedGuard` will immediately unlock"]
+pub struct ErasedGuard<'a> {
+    _guard: Box<dyn Erased + 'a>,
+}
+
+impl ErasedGuard<'_> {
+    /// Releases the lock held by this guard
+    ///
+    /// This is the same as dropping the guard
+    pub fn unlock(self) {}
+}
+
+impl fmt::Debug for ErasedGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ErasedGuard")
+            .field(&crate::guard::Placeholder(".."))
+            .finish()
+    }
+}
+
+macro_rules! erase {
+    ($(impl[$($generics:tt)*] $type:ty;)*) => {$(
+        impl<'a, $($generics)*> From<$type> for ErasedGuard<'a> {
+            fn from(guard: $type) -> Self {
+                Self {
+                    _guard: Box::new(guard),
+                }
+            }
+        }
+    )*};
+}
+
+erase! {
+    impl[L: RawExclusiveLock + RawLockInfo + 'a] RawExclusiveGuard<'a, L>;
+    impl[L: RawShareLock + RawLockInfo + 'a] RawShareGuard<'a, L>;
+    impl[L: RawShareLockUpgrade + RawLockInfo + 'a] RawUpgradableGuard<'a, L>;
+    impl[L: RawExclusiveLock + RawLockInfo + 'a, T: ?Sized + 'a, St: 'a] ExclusiveGuard<'a, L, T, St>;
+    impl[L: RawShareLock + RawLockInfo + 'a, T: ?Sized + 'a, St: 'a] ShareGuard<'a, L, T, St>;
+    impl[L: RawShareLockUpgrade + RawLockInfo + 'a, T: ?Sized + 'a] UpgradableGuard<'a, L, T>;
+}
+
+#[test]
+fn test_erased_guard() {
+    use crate::mutex::spin::SpinLock;
+    use crate::rwlock::spin::SpinLock as RwSpinLock;
+
+    let a = SpinLock::mutex(0);
+    let b = RwSpinLock::rwlock(String::new());
+    let c = SpinLock::mutex((0, 1));
+
+    let guards: Vec<ErasedGuard<'_>> = vec![
+        a.lock().into(),
+        b.read().into(),
+        b.upgradable_read().into(),
+        ExclusiveGuard::map(c.lock(), |(x, _)| x).into(),
+    ];
+
+    assert!(a.try_lock().is_none());
+    assert!(b.try_write().is_none());
+    assert!(c.try_lock().is_none());
+    drop(guards);
+
+    assert!(a.try_lock().is_some());
+    assert!(b.try_write().is_some());
+    assert!(c.try_lock().is_some());
+}
@@ -0,0 +1,128 @@
+//! A manual-reset event
+//!
+//! Once an [`Event`] is [`set`](Event::set), every current and future waiter is released,
+//! until the event is [`reset`](Event::reset). Waiting threads are parked with an [`EventCount`],
+//! keyed on the address of the event.
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::event_count::EventCount;
+
+/// A manual-reset event
+#[derive(Default)]
+pub struct Event {
+    is_set: AtomicBool,
+    event: EventCount,
+}
+
+impl Event {
+    /// Create a new event, which is not set
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_state(false)
+    }
+
+    /// Create a new event, which is set if `is_set` is true
+    #[inline]
+    pub const fn with_state(is_set: bool) -> Self {
+        Self {
+            is_set: AtomicBool::new(is_set),
+            event: EventCount::new(),
+        }
+    }
+
+    /// Returns true if the event is set
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.is_set.load(Ordering::Acquire)
+    }
+
+    /// Set the event, releasing all current and future waiters until the event is reset
+    #[inline]
+    pub fn set(&self) {
+        if !self.is_set.swap(true, Ordering::Release) {
+            self.event.notify_all();
+        }
+    }
+
+    /// Reset the event, so that future waiters block until it is set again
+    #[inline]
+    pub fn reset(&self) {
+        self.is_set.store(false, Ordering::Relaxed);
+    }
+
+    /// Blocks the current thread until the event is set
+    #[inline]
+    pub fn wait(&self) {
+        if !self.is_set() {
+            self.wait_slow(None);
+        }
+    }
+
+    /// Blocks the current thread until the event is set, or the `timeout` has passed
+    ///
+    /// returns true if the event was set
+    #[inline]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.is_set() || self.wait_slow(Instant::now().checked_add(timeout))
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait_slow(&self, timeout: Option<Instant>) -> bool {
+        loop {
+            let key = self.event.prepare_wait();
+
+            if self.is_set() {
+                self.event.cancel_wait(key);
+                return true;
+            }
+
+            match timeout {
+                None => self.event.commit_wait(key),
+                Some(timeout) => {
+                    if !self.event.commit_wait_until(key, timeout) {
+                        return self.is_set();
+                    }
+                }
+            }
+
+            if self.is_set() {
+                return true;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
+
+#[test]
+fn test_event() {
+    let event = Event::new();
+    assert!(!event.wait_timeout(Duration::from_millis(1)));
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| event.wait());
+        }
+
+        event.set();
+    })
+    .unwrap();
+
+    // the event stays set until it is reset
+    event.wait();
+    assert!(event.wait_timeout(Duration::from_millis(1)));
+
+    event.reset();
+    assert!(!event.is_set());
+    assert!(!event.wait_timeout(Duration::from_millis(1)));
+}
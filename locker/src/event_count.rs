@@ -0,0 +1,184 @@
+//! An event count, which allows threads to block until some condition changes
+//!
+//! This is the recommended way to turn a spin based [`RawExclusiveLock`](crate::exclusive_lock::RawExclusiveLock)
+//! into a blocking one, without depending on `parking_lot_core` directly.
+//!
+//! To lock:
+//! 1. try to acquire the lock, if that succeeds then we are done
+//! 2. call [`EventCount::prepare_wait`]
+//! 3. try to acquire the lock again, if that succeeds then call
+//!    [`EventCount::cancel_wait`] and we are done
+//! 4. call [`EventCount::commit_wait`] and go back to 1
+//!
+//! To unlock, release the lock, and then call [`EventCount::notify_one`].
+//!
+//! If the lock is released between 2 and 4, then `commit_wait` will return
+//! immediately, so no wakeups are lost.
+
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use parking_lot_core::{DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+
+/// An event count
+///
+/// Waiting threads are parked in `parking_lot_core`, keyed on the address of the event count,
+/// so the event count must not share it's address with anything else that calls into
+/// `parking_lot_core` (like a lock from this crate).
+#[derive(Debug, Default)]
+pub struct EventCount {
+    epoch: AtomicUsize,
+    waiters: AtomicUsize,
+}
+
+/// A ticket from [`EventCount::prepare_wait`]
+///
+/// This must be passed to either [`EventCount::cancel_wait`] or [`EventCount::commit_wait`]
+#[must_use = "a `Key` must be passed to either `cancel_wait` or `commit_wait`"]
+#[derive(Debug)]
+pub struct Key {
+    epoch: usize,
+}
+
+impl EventCount {
+    /// Create a new event count
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            epoch: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Prepare to wait for a notification
+    ///
+    /// Any notification after this call will cause the corresponding
+    /// [`commit_wait`](EventCount::commit_wait) to return immediately.
+    #[inline]
+    pub fn prepare_wait(&self) -> Key {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+
+        Key {
+            epoch: self.epoch.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Cancel a wait, because the condition became true after
+    /// [`prepare_wait`](EventCount::prepare_wait)
+    #[inline]
+    pub fn cancel_wait(&self, key: Key) {
+        let _ = key;
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Block the current thread until a notification, unless there was
+    /// a notification since the key was created
+    #[inline]
+    pub fn commit_wait(&self, key: Key) {
+        self.park(key, None);
+    }
+
+    /// Block the current thread until a notification or the timeout, unless
+    /// there was a notification since the key was created
+    ///
+    /// returns false if the timeout was reached
+    #[inline]
+    pub fn commit_wait_until(&self, key: Key, timeout: Instant) -> bool {
+        self.park(key, Some(timeout))
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn park(&self, key: Key, timeout: Option<Instant>) -> bool {
+        let validate = || self.epoch.load(Ordering::SeqCst) == key.epoch;
+        let before_sleep = || {};
+        let timed_out = |_key, _was_last| {};
+
+        // SAFETY:
+        //   * `key` is the address of the event count, which is only used to park and unpark
+        //     on notifications (assuming the user upholds the `EventCount`'s documentation)
+        //   * `validate`, `before_sleep`, and `timed_out` do not call `park`, nor do they panic.
+        let result = unsafe {
+            parking_lot_core::park(
+                self.key(),
+                validate,
+                before_sleep,
+                timed_out,
+                DEFAULT_PARK_TOKEN,
+                timeout,
+            )
+        };
+
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+
+        !matches!(result, parking_lot_core::ParkResult::TimedOut)
+    }
+
+    #[inline]
+    fn notify(&self) -> bool {
+        // make sure that any change to the condition is visible to
+        // threads which prepared to wait before we incremented the epoch
+        fence(Ordering::SeqCst);
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        self.waiters.load(Ordering::SeqCst) != 0
+    }
+
+    /// Wake up one blocked thread, and all threads that have prepared to wait
+    /// but have not yet blocked
+    #[inline]
+    pub fn notify_one(&self) {
+        if self.notify() {
+            let callback = |_result| DEFAULT_UNPARK_TOKEN;
+
+            // SAFETY: the key is the address of the event count
+            unsafe {
+                parking_lot_core::unpark_one(self.key(), callback);
+            }
+        }
+    }
+
+    /// Wake up all blocked threads, and all threads that have prepared to wait
+    /// but have not yet blocked
+    #[inline]
+    pub fn notify_all(&self) {
+        if self.notify() {
+            // SAFETY: the key is the address of the event count
+            unsafe {
+                parking_lot_core::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_notify() {
+    use core::sync::atomic::AtomicBool;
+
+    let event = EventCount::new();
+    let flag = AtomicBool::new(false);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                while !flag.load(Ordering::Acquire) {
+                    let key = event.prepare_wait();
+
+                    if flag.load(Ordering::Acquire) {
+                        event.cancel_wait(key);
+                    } else {
+                        event.commit_wait(key);
+                    }
+                }
+            });
+        }
+
+        flag.store(true, Ordering::Release);
+        event.notify_all();
+    })
+    .unwrap();
+}
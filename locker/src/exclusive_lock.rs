@@ -2,11 +2,19 @@
 //!
 //! See [`RawExclusiveLock`] for details
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod arc;
 mod guard;
+mod lazy;
+mod owned;
 mod raw;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use arc::ArcExclusiveGuard;
 pub use guard::{ExclusiveGuard, MappedExclusiveGuard};
-pub use raw::{RawExclusiveGuard, _RawExclusiveGuard};
+pub use lazy::LazyGuard;
+pub use owned::{_OwnedRawExclusiveGuard, OwnedExclusiveGuard, OwnedRawExclusiveGuard};
+pub use raw::{_RawExclusiveGuard, ExclusiveUnlocked, RawExclusiveGuard};
 
 #[cfg(doc)]
 use crate::RawLockInfo;
@@ -84,6 +92,16 @@ pub unsafe trait RawExclusiveLock {
         self.exc_unlock();
         self.exc_lock();
     }
+
+    /// The address of the lock that is actually acquired by `exc_lock`
+    ///
+    /// Locks which forward to a shared lock (like the global locks) return the address
+    /// of the shared lock, so that two locks with the same address will contend.
+    /// This is used to order locks in [`lock_all`](crate::multi_lock::lock_all)
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
 }
 
 /// Additional methods for `RawExclusiveLock` which support locking with timeouts.
@@ -208,6 +226,10 @@ macro_rules! trait_impls {
             unsafe fn exc_bump(&self) {
                 L::exc_bump(self)
             }
+
+            fn exc_lock_addr(&self) -> usize {
+                L::exc_lock_addr(self)
+            }
         }
 
         unsafe impl<$L: ?Sized + RawExclusiveLockTimed> RawExclusiveLockTimed for $type {
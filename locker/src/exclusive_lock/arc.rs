@@ -0,0 +1,89 @@
+use super::RawExclusiveLock;
+use crate::mutex::Mutex;
+use crate::RawLockInfo;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// An RAII structure used to release the exclusive access of a lock when dropped.
+///
+/// Unlike [`ExclusiveGuard`](crate::exclusive_lock::ExclusiveGuard) this guard keeps the lock
+/// alive by holding on to an `Arc`, so it has no lifetime parameter, and can be stored in
+/// structs or sent to other threads.
+///
+/// This is created by [`Mutex::lock_arc`] and
+/// [`RwLock::write_arc`](crate::rwlock::RwLock::write_arc)
+#[must_use = "if unused the `ArcExclusiveGuard` will immediately unlock"]
+pub struct ArcExclusiveGuard<L: RawExclusiveLock + RawLockInfo, T: ?Sized> {
+    mutex: Arc<Mutex<L, T>>,
+    _traits: L::ExclusiveGuardTraits,
+}
+
+unsafe impl<L: RawExclusiveLock + RawLockInfo + Send + Sync, T: ?Sized + Send> Send
+    for ArcExclusiveGuard<L, T>
+where
+    L::ExclusiveGuardTraits: Send,
+{
+}
+// the mutex can be reached through `ArcExclusiveGuard::mutex`, and locked on another thread
+unsafe impl<L: RawExclusiveLock + RawLockInfo + Sync, T: ?Sized + Send + Sync> Sync
+    for ArcExclusiveGuard<L, T>
+where
+    L::ExclusiveGuardTraits: Sync,
+{
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized> ArcExclusiveGuard<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Create a new `ArcExclusiveGuard`
+    ///
+    /// # Safety
+    ///
+    /// An *exc lock* must owned for the given `mutex`
+    pub unsafe fn from_raw(mutex: Arc<Mutex<L, T>>) -> Self {
+        Self {
+            mutex,
+            _traits: crate::Inhabitted::INIT,
+        }
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized> ArcExclusiveGuard<L, T> {
+    /// The mutex that this guard locks
+    ///
+    /// This is an associated function that needs to be used as `ArcExclusiveGuard::mutex(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn mutex(g: &Self) -> &Arc<Mutex<L, T>> {
+        &g.mutex
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized> Drop for ArcExclusiveGuard<L, T> {
+    fn drop(&mut self) {
+        unsafe { self.mutex.raw().inner().exc_unlock() }
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized> Deref for ArcExclusiveGuard<L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized> DerefMut for ArcExclusiveGuard<L, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + fmt::Debug> fmt::Debug
+    for ArcExclusiveGuard<L, T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -1,14 +1,15 @@
 use super::{
-    RawExclusiveGuard, RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair,
-    SplittableExclusiveLock,
+    ExclusiveUnlocked, RawExclusiveGuard, RawExclusiveLock, RawExclusiveLockDowngrade,
+    RawExclusiveLockFair, SplittableExclusiveLock,
 };
 use crate::RawLockInfo;
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 
 pub use crate::guard::{Mapped, Pure, TryMapError};
 
-/// An RAII exclusive guard guard returned by `ExclusiveGuard::map`,
+/// An RAII exclusive guard returned by `ExclusiveGuard::map`,
 /// which can point to a subfield of the protected data.
 ///
 /// The main difference between `MappedExclusiveGuard` and `ExclusiveGuard`
@@ -17,6 +18,10 @@ pub use crate::guard::{Mapped, Pure, TryMapError};
 pub type MappedExclusiveGuard<'a, L, T> = ExclusiveGuard<'a, L, T, Mapped>;
 
 /// RAII structure used to release the exclusive access of a lock when dropped.
+///
+/// If the lock can be unlocked from any thread, then this guard is `Send`, so it can be acquired
+/// on one thread and moved to another thread to be released there. Some locks only opt into this
+/// when the `send_guard` feature is enabled, see [`SendGuard`](crate::marker::SendGuard).
 #[must_use = "if unused the `ExclusiveGuard` will immediately unlock"]
 pub struct ExclusiveGuard<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St = Pure> {
     raw: RawExclusiveGuard<'a, L>,
@@ -40,6 +45,9 @@ where
 impl<L: RawExclusiveLockFair + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'_, L, T, St> {
     /// Unlocks the guard using a fair unlocking protocol
     /// [read more](RawExclusiveLockFair#method.exc_unlock_fair)
+    ///
+    /// This is also available on mapped guards, so projecting into the data doesn't
+    /// lose fair handoff.
     pub fn unlock_fair(g: Self) {
         g.raw.unlock_fair();
     }
@@ -58,11 +66,24 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T>
     pub fn unlocked<R>(g: &mut Self, f: impl FnOnce() -> R) -> R {
         g.raw.unlocked(f)
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The function gets a handle which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because &mut guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_with<R>(g: &mut Self, f: impl FnOnce(&ExclusiveUnlocked<'_, L>) -> R) -> R {
+        g.raw.unlocked_with(f)
+    }
 }
 
 impl<'a, L: RawExclusiveLockFair + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L, T> {
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawExclusiveLockFair#method.exc_bump_fair)
+    ///
+    /// This is not available on mapped guards, because another thread could invalidate
+    /// the projected data while the lock is yielded. Use [`unlock_fair`](Self::unlock_fair)
+    /// and re-acquire the lock instead.
     pub fn bump_fair(g: &mut Self) {
         g.raw.bump_fair();
     }
@@ -75,6 +96,19 @@ impl<'a, L: RawExclusiveLockFair + RawLockInfo, T: ?Sized> ExclusiveGuard<'a, L,
     pub fn unlocked_fair<R>(g: &mut Self, f: impl FnOnce() -> R) -> R {
         g.raw.unlocked_fair(f)
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The lock is unlocked a fair unlock protocol, and the function gets a handle
+    /// which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because `&mut` guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_fair_with<R>(
+        g: &mut Self,
+        f: impl FnOnce(&ExclusiveUnlocked<'_, L>) -> R,
+    ) -> R {
+        g.raw.unlocked_fair_with(f)
+    }
 }
 
 impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L, T, St> {
@@ -161,13 +195,40 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
         (g.raw, g.value)
     }
 
+    /// Forget the guard, and leave the *exc lock* held forever
+    ///
+    /// This is like [`RefMut::leak`](core::cell::RefMut::leak), the guarded value can
+    /// be written to for as long as the lock is borrowed, but the lock can never be
+    /// acquired again.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::leak(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn leak(g: Self) -> &'a mut T {
+        let (raw, value) = Self::into_raw_parts(g);
+        raw.leak();
+        unsafe { &mut *value }
+    }
+
+    /// Forget the guard without releasing the *exc lock*, and return a pointer to the guarded value
+    ///
+    /// The guard can be reconstructed with [`ExclusiveGuard::from_raw`] to release the lock, this
+    /// is useful for passing the guard through FFI or type-erased storage.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::into_raw(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn into_raw(g: Self) -> *mut T {
+        let (raw, value) = Self::into_raw_parts(g);
+        raw.into_inner();
+        value
+    }
+
     /// Make a new `MappedExclusiveGuard` for a component of the locked data.
     ///
     /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
     ///
     /// This is an associated function that needs to be used as `ExclusiveGuard::map(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn map<F, U: ?Sized>(
+    pub fn map<U: ?Sized>(
         g: Self,
         f: impl FnOnce(&mut T) -> &mut U,
     ) -> MappedExclusiveGuard<'a, L, U> {
@@ -177,13 +238,26 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
     }
 
     /// Attempts to make a new `MappedExclusiveGuard` for a component of the locked data.
-    /// The original guard is return if the closure returns `Err` as well as the error.
-    ///
-    /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
+    /// The original guard is returned if the closure returns `None`.
     ///
     /// This is an associated function that needs to be used as `ExclusiveGuard::try_map(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn try_map<E, U: ?Sized>(
+    pub fn try_map<U: ?Sized>(
+        g: Self,
+        f: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Result<MappedExclusiveGuard<'a, L, U>, Self> {
+        match f(unsafe { &mut *g.value }) {
+            None => Err(g),
+            Some(value) => Ok(unsafe { ExclusiveGuard::from_raw_parts(g.raw, value) }),
+        }
+    }
+
+    /// Attempts to make a new `MappedExclusiveGuard` for a component of the locked data.
+    /// The original guard is return if the closure returns `Err` as well as the error.
+    ///
+    /// This is an associated function that needs to be used as `ExclusiveGuard::try_map_result(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn try_map_result<E, U: ?Sized>(
         g: Self,
         f: impl FnOnce(&mut T) -> Result<&mut U, E>,
     ) -> Result<MappedExclusiveGuard<'a, L, U>, TryMapError<E, Self>> {
@@ -194,6 +268,23 @@ impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L,
     }
 }
 
+impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L, T, St>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Reconstruct a guard from a pointer returned by [`ExclusiveGuard::into_raw`]
+    ///
+    /// # Safety
+    ///
+    /// * a *exc lock* must be owned for the given `lock`, and it will be released when
+    ///   the guard is dropped
+    /// * `value` must have been returned by `ExclusiveGuard::into_raw` on a guard of the same
+    ///   kind (mapped or not) that locked `lock`
+    pub unsafe fn from_raw(lock: &'a L, value: *mut T) -> Self {
+        Self::from_raw_parts(RawExclusiveGuard::from_raw(lock), value)
+    }
+}
+
 impl<'a, L: SplittableExclusiveLock + RawLockInfo, T: ?Sized, St> ExclusiveGuard<'a, L, T, St> {
     /// Make a two new `MappedExclusiveGuard`s for a component of the locked data.
     ///
@@ -263,6 +354,42 @@ where
     }
 }
 
+impl<'a, L: RawExclusiveLockDowngrade + RawLockInfo, T: ?Sized> MappedExclusiveGuard<'a, L, T>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Atomically downgrades a *exc lock* into a *shr lock* without allowing any new
+    /// *exc locks* in the meantime.
+    ///
+    /// # Safety
+    ///
+    /// Once the lock is downgraded, other threads can acquire *shr locks* and access the
+    /// protected data. The projected value must remain valid while they do so, for example
+    /// it must not be reachable only through interior mutability (like the contents of a `Box`
+    /// behind a `std::sync::Mutex`), which another thread could replace.
+    pub unsafe fn downgrade_mapped(g: Self) -> crate::share_lock::MappedShareGuard<'a, L, T> {
+        crate::share_lock::ShareGuard::from_raw_parts(g.raw.downgrade(), g.value)
+    }
+}
+
+impl<'a, L: crate::share_lock::RawShareLockUpgrade + RawLockInfo, T: ?Sized>
+    ExclusiveGuard<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Atomically downgrades a *exc lock* into an *upg lock* without allowing any new
+    /// *exc locks* or *upg locks* in the meantime.
+    pub fn downgrade_to_upgradable(g: Self) -> crate::share_lock::UpgradableGuard<'a, L, T> {
+        unsafe {
+            crate::share_lock::UpgradableGuard::from_raw_parts(
+                g.raw.downgrade_to_upgradable(),
+                g.value,
+            )
+        }
+    }
+}
+
 impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> Deref for ExclusiveGuard<'_, L, T, St> {
     type Target = T;
 
@@ -276,3 +403,57 @@ impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized, St> DerefMut for ExclusiveGua
         unsafe { &mut *self.value }
     }
 }
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + fmt::Debug, St> fmt::Debug
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + fmt::Display, St> fmt::Display
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + PartialEq, St> PartialEq
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + Eq, St> Eq for ExclusiveGuard<'_, L, T, St> {}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + PartialOrd, St> PartialOrd
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + Ord, St> Ord for ExclusiveGuard<'_, L, T, St> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo, T: ?Sized + core::hash::Hash, St> core::hash::Hash
+    for ExclusiveGuard<'_, L, T, St>
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        T::hash(self, state)
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<'a, L: RawExclusiveLock + RawLockInfo, T: ?Sized + core::marker::Unsize<U>, U: ?Sized, St>
+    core::ops::CoerceUnsized<ExclusiveGuard<'a, L, U, St>> for ExclusiveGuard<'a, L, T, St>
+{
+}
@@ -0,0 +1,105 @@
+use crate::mutex::{Mutex, RawMutex};
+use crate::Inhabitted;
+use core::cell::Cell;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// An RAII structure that only acquires its mutex the first time the data is accessed,
+/// and releases the exclusive access of the mutex when dropped if it was acquired.
+///
+/// This is useful when the protected data is only accessed conditionally, because the
+/// mutex isn't contended if the data is never touched.
+///
+/// This is created by [`Mutex::lock_lazy`]
+#[must_use = "if unused the `LazyGuard` will never lock"]
+pub struct LazyGuard<'a, L: RawMutex, T: ?Sized> {
+    mutex: &'a Mutex<L, T>,
+    locked: Cell<bool>,
+    _traits: L::ExclusiveGuardTraits,
+}
+
+unsafe impl<'a, L: RawMutex + Sync, T: ?Sized + Send> Send for LazyGuard<'a, L, T> where
+    L::ExclusiveGuardTraits: Send
+{
+}
+
+impl<'a, L: RawMutex, T: ?Sized> LazyGuard<'a, L, T>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// Create a new `LazyGuard`, which doesn't lock the `mutex` until the data is accessed
+    pub fn new(mutex: &'a Mutex<L, T>) -> Self {
+        Self {
+            mutex,
+            locked: Cell::new(false),
+            _traits: Inhabitted::INIT,
+        }
+    }
+}
+
+impl<'a, L: RawMutex, T: ?Sized> LazyGuard<'a, L, T> {
+    /// The mutex that this guard locks
+    ///
+    /// This is an associated function that needs to be used as `LazyGuard::mutex(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn mutex(g: &Self) -> &'a Mutex<L, T> {
+        g.mutex
+    }
+
+    /// Returns true if the data was accessed, and so the mutex is locked by this guard
+    ///
+    /// This is an associated function that needs to be used as `LazyGuard::is_locked(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn is_locked(g: &Self) -> bool {
+        g.locked.get()
+    }
+
+    /// Acquires the mutex if it isn't already locked by this guard
+    ///
+    /// This is an associated function that needs to be used as `LazyGuard::force(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    pub fn force(g: &Self) {
+        if !g.locked.get() {
+            g.mutex.raw().inner().exc_lock();
+            g.locked.set(true);
+        }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> Drop for LazyGuard<'_, L, T> {
+    fn drop(&mut self) {
+        if self.locked.get() {
+            unsafe { self.mutex.raw().inner().exc_unlock() }
+        }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> Deref for LazyGuard<'_, L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Self::force(self);
+        unsafe { &*self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> DerefMut for LazyGuard<'_, L, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Self::force(self);
+        unsafe { &mut *self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized + fmt::Debug> fmt::Debug for LazyGuard<'_, L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.locked.get() {
+            fmt::Debug::fmt(&**self, f)
+        } else {
+            fmt::Debug::fmt(&crate::guard::Placeholder("<unlocked>"), f)
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use super::{RawExclusiveLock, RawExclusiveLockFair};
+use crate::mutex::{Mutex, RawMutex};
+use crate::{Inhabitted, RawLockInfo, StableDeref};
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+/// A RAII implementation of a scoped exclusive lock, which owns a pointer to it's lock
+///
+/// This is like [`RawExclusiveGuard`](crate::exclusive_lock::RawExclusiveGuard), but it holds
+/// on to the pointer `P` instead of borrowing the lock. So if `P` is an owning pointer, like
+/// `Arc<L>` or `Box<L>`, then this guard is `'static`.
+pub type OwnedRawExclusiveGuard<P> =
+    _OwnedRawExclusiveGuard<P, <<P as Deref>::Target as RawLockInfo>::ExclusiveGuardTraits>;
+
+#[doc(hidden)]
+#[must_use = "if unused the `OwnedRawExclusiveGuard` will immediately unlock"]
+pub struct _OwnedRawExclusiveGuard<P: StableDeref, Tr>
+where
+    P::Target: RawExclusiveLock,
+{
+    lock: P,
+    _traits: Tr,
+}
+
+impl<P: StableDeref, Tr> Drop for _OwnedRawExclusiveGuard<P, Tr>
+where
+    P::Target: RawExclusiveLock,
+{
+    fn drop(&mut self) {
+        unsafe { self.lock.exc_unlock() }
+    }
+}
+
+impl<P: StableDeref> OwnedRawExclusiveGuard<P>
+where
+    P::Target: RawExclusiveLock + RawLockInfo,
+    <P::Target as RawLockInfo>::ExclusiveGuardTraits: Inhabitted,
+{
+    /// # Safety
+    ///
+    /// An *exc lock* must owned for the given `lock`
+    pub unsafe fn from_raw(lock: P) -> Self {
+        Self {
+            lock,
+            _traits: Inhabitted::INIT,
+        }
+    }
+
+    /// Create a new `OwnedRawExclusiveGuard`
+    ///
+    /// blocks until lock is acquired
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is cannot be acquired
+    pub fn new(lock: P) -> Self {
+        lock.exc_lock();
+        unsafe { Self::from_raw(lock) }
+    }
+
+    /// Try to create a new `OwnedRawExclusiveGuard`, and give back the pointer on failure
+    ///
+    /// This function is non-blocking and may not panic
+    pub fn try_new(lock: P) -> Result<Self, P> {
+        if lock.exc_try_lock() {
+            Ok(unsafe { Self::from_raw(lock) })
+        } else {
+            Err(lock)
+        }
+    }
+}
+
+impl<P: StableDeref> OwnedRawExclusiveGuard<P>
+where
+    P::Target: RawExclusiveLock + RawLockInfo,
+{
+    /// Temporarily yields the lock to another thread if there is one.
+    pub fn bump(&mut self) {
+        unsafe { self.lock.exc_bump() }
+    }
+
+    /// The inner lock
+    pub fn inner(&self) -> &P::Target {
+        &self.lock
+    }
+
+    /// Consume the guard without releasing the lock
+    pub fn into_inner(self) -> P {
+        let this = ManuallyDrop::new(self);
+        unsafe { core::ptr::read(&this.lock) }
+    }
+}
+
+impl<P: StableDeref> OwnedRawExclusiveGuard<P>
+where
+    P::Target: RawExclusiveLockFair + RawLockInfo,
+{
+    /// Unlocks the guard using a fair unlocking protocol
+    /// [read more](RawExclusiveLockFair#method.exc_unlock_fair)
+    pub fn unlock_fair(self) {
+        let lock = self.into_inner();
+        unsafe { lock.exc_unlock_fair() }
+    }
+
+    /// Temporarily yields the lock to a waiting thread if there is one.
+    /// [read more](RawExclusiveLockFair#method.exc_bump_fair)
+    pub fn bump_fair(&mut self) {
+        unsafe { self.lock.exc_bump_fair() }
+    }
+}
+
+/// An RAII structure used to release the exclusive access of a mutex when dropped,
+/// which owns a pointer to it's mutex
+///
+/// This is like [`ArcExclusiveGuard`](crate::exclusive_lock::ArcExclusiveGuard), but
+/// it works with any [`StableDeref`] pointer to the mutex, like `Box` or `Rc`.
+///
+/// This is created by [`Mutex::lock_owned`]
+#[must_use = "if unused the `OwnedExclusiveGuard` will immediately unlock"]
+pub struct OwnedExclusiveGuard<P, L, T: ?Sized>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    mutex: P,
+    _traits: L::ExclusiveGuardTraits,
+}
+
+unsafe impl<P, L, T: ?Sized + Send> Send for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>> + Send,
+    L: RawMutex,
+    L::ExclusiveGuardTraits: Send,
+{
+}
+unsafe impl<P, L, T: ?Sized + Sync> Sync for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>> + Sync,
+    L: RawMutex,
+    L::ExclusiveGuardTraits: Sync,
+{
+}
+
+impl<P, L, T: ?Sized> OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// Create a new `OwnedExclusiveGuard`
+    ///
+    /// # Safety
+    ///
+    /// An *exc lock* must owned for the given `mutex`
+    pub unsafe fn from_raw(mutex: P) -> Self {
+        Self {
+            mutex,
+            _traits: Inhabitted::INIT,
+        }
+    }
+}
+
+impl<P, L, T: ?Sized> OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    /// The pointer to the mutex that this guard locks
+    ///
+    /// This is an associated function that needs to be used as `OwnedExclusiveGuard::mutex(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn mutex(g: &Self) -> &P {
+        &g.mutex
+    }
+}
+
+impl<P, L, T: ?Sized> Drop for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    fn drop(&mut self) {
+        unsafe { self.mutex.raw().inner().exc_unlock() }
+    }
+}
+
+impl<P, L, T: ?Sized> Deref for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<P, L, T: ?Sized> DerefMut for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.as_mut_ptr() }
+    }
+}
+
+impl<P, L, T: ?Sized + fmt::Debug> fmt::Debug for OwnedExclusiveGuard<P, L, T>
+where
+    P: StableDeref<Target = Mutex<L, T>>,
+    L: RawMutex,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
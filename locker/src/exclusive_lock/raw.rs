@@ -1,7 +1,67 @@
 use super::{
-    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, SplittableExclusiveLock,
+    RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair, RawExclusiveLockTimed,
+    SplittableExclusiveLock,
 };
 use crate::{Inhabitted, RawLockInfo};
+use core::cell::Cell;
+
+/// A handle to a lock which was temporarily unlocked by
+/// [`RawExclusiveGuard::unlocked_with`](crate::exclusive_lock::RawExclusiveGuard#method.unlocked_with)
+///
+/// The lock is re-acquired once the closure returns, unless it was already re-acquired with
+/// [`relock`](Self::relock) or [`try_relock`](Self::try_relock)
+pub struct ExclusiveUnlocked<'a, L: RawExclusiveLock + ?Sized> {
+    lock: &'a L,
+    locked: Cell<bool>,
+}
+
+impl<L: RawExclusiveLock + ?Sized> Drop for ExclusiveUnlocked<'_, L> {
+    fn drop(&mut self) {
+        if !self.locked.get() {
+            self.lock.exc_lock()
+        }
+    }
+}
+
+impl<'a, L: RawExclusiveLock + ?Sized> ExclusiveUnlocked<'a, L> {
+    fn new(lock: &'a L) -> Self {
+        Self {
+            lock,
+            locked: Cell::new(false),
+        }
+    }
+
+    /// The inner lock
+    pub fn inner(&self) -> &'a L {
+        self.lock
+    }
+
+    /// Returns true if the lock was already re-acquired
+    pub fn is_relocked(&self) -> bool {
+        self.locked.get()
+    }
+
+    /// Re-acquire the lock early, blocking until it is acquired
+    ///
+    /// Does nothing if the lock was already re-acquired
+    pub fn relock(&self) {
+        if !self.locked.get() {
+            self.lock.exc_lock();
+            self.locked.set(true);
+        }
+    }
+
+    /// Try to re-acquire the lock early, without blocking
+    ///
+    /// returns true if the lock is held after this call
+    pub fn try_relock(&self) -> bool {
+        if !self.locked.get() && self.lock.exc_try_lock() {
+            self.locked.set(true);
+        }
+
+        self.locked.get()
+    }
+}
 
 /// A RAII implementation of a scoped exclusive lock
 ///
@@ -72,6 +132,33 @@ where
     }
 }
 
+impl<'a, L: RawExclusiveLockTimed + RawLockInfo + ?Sized> RawExclusiveGuard<'a, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// Try to create a new `RawExclusiveGuard`, blocking until the lock is acquired or the `instant` is reached
+    ///
+    /// This function may not panic
+    pub fn try_new_until(lock: &'a L, instant: L::Instant) -> Option<Self> {
+        if lock.exc_try_lock_until(instant) {
+            Some(unsafe { Self::from_raw(lock) })
+        } else {
+            None
+        }
+    }
+
+    /// Try to create a new `RawExclusiveGuard`, blocking until the lock is acquired or the `duration` has passed
+    ///
+    /// This function may not panic
+    pub fn try_new_for(lock: &'a L, duration: L::Duration) -> Option<Self> {
+        if lock.exc_try_lock_for(duration) {
+            Some(unsafe { Self::from_raw(lock) })
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawExclusiveLock#method.exc_bump)
@@ -92,6 +179,18 @@ impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
         f()
     }
 
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The function gets a handle which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because &mut guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_with<R>(&mut self, f: impl FnOnce(&ExclusiveUnlocked<'_, L>) -> R) -> R {
+        unsafe {
+            self.lock.exc_unlock();
+        }
+        f(&ExclusiveUnlocked::new(self.lock))
+    }
+
     /// The inner lock
     pub fn inner(&self) -> &L {
         self.lock
@@ -101,6 +200,14 @@ impl<'a, L: RawExclusiveLock + RawLockInfo> RawExclusiveGuard<'a, L> {
     pub fn into_inner(self) -> &'a L {
         core::mem::ManuallyDrop::new(self).lock
     }
+
+    /// Forget the guard, and leave the *exc lock* held forever
+    ///
+    /// This is like [`RefMut::leak`](core::cell::RefMut::leak), no other lock can be acquired
+    /// after this
+    pub fn leak(self) -> &'a L {
+        self.into_inner()
+    }
 }
 
 impl<L: RawExclusiveLockFair + RawLockInfo> RawExclusiveGuard<'_, L> {
@@ -133,6 +240,19 @@ impl<L: RawExclusiveLockFair + RawLockInfo> RawExclusiveGuard<'_, L> {
         defer!(self.lock.exc_lock());
         f()
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The lock is unlocked a fair unlock protocol, and the function gets a handle
+    /// which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because `&mut` guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_fair_with<R>(&mut self, f: impl FnOnce(&ExclusiveUnlocked<'_, L>) -> R) -> R {
+        unsafe {
+            self.lock.exc_unlock_fair();
+        }
+        f(&ExclusiveUnlocked::new(self.lock))
+    }
 }
 
 impl<'a, L: RawExclusiveLockDowngrade + RawLockInfo> RawExclusiveGuard<'a, L>
@@ -162,6 +282,22 @@ where
     }
 }
 
+impl<'a, L: crate::share_lock::RawShareLockUpgrade + RawLockInfo> RawExclusiveGuard<'a, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    /// Atomically downgrades a write lock into an upgradable read lock without allowing
+    /// any writers or upgradable readers to take the lock in the meantime.
+    pub fn downgrade_to_upgradable(self) -> crate::share_lock::RawUpgradableGuard<'a, L> {
+        let lock = self.into_inner();
+        unsafe {
+            lock.downgrade_to_upgradable();
+            crate::share_lock::RawUpgradableGuard::from_raw(lock)
+        }
+    }
+}
+
 impl<L: SplittableExclusiveLock + RawLockInfo> Clone for RawExclusiveGuard<'_, L> {
     fn clone(&self) -> Self {
         unsafe {
@@ -4,7 +4,16 @@ pub enum Pure {}
 /// Represents an mapped guard
 pub enum Mapped {}
 
-/// The error return type of `try_map` and `try_split_map`
+/// The error return type of `try_map_result` and `try_split_map`
 ///
 /// Contains the error and the old guard in that order
 pub struct TryMapError<E, G>(pub E, pub G);
+
+/// Formats as the given string, this is used to fill in values that can't be accessed in `Debug` impls
+pub(crate) struct Placeholder(pub &'static str);
+
+impl core::fmt::Debug for Placeholder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.0)
+    }
+}
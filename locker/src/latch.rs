@@ -0,0 +1,179 @@
+//! A single use latch, which blocks threads until a counter reaches zero
+//!
+//! The latch is built on a raw exclusive lock, which is held while the count is positive, and
+//! released by the last [`Latch::count_down`]. Waiting threads block on the lock, so the latch
+//! waits in the same way the lock does. A [spin lock](crate::mutex::spin::SpinLock) gives a
+//! spinning latch, and the [default lock](crate::mutex::default::DefaultLock) gives a parking
+//! latch if `parking_lot_core` is enabled.
+//!
+//! Because a lock must not be moved while it is locked, the lock is acquired by the first call to
+//! `count_down` or `wait`, instead of when the latch is created.
+
+use core::fmt;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::mutex::default::DefaultLock;
+use crate::relax::Backoff;
+use crate::RawLockInfo;
+
+const UNARMED: u8 = 0;
+const ARMING: u8 = 1;
+const ARMED: u8 = 2;
+
+/// A latch which opens once it has been counted down `count` times
+///
+/// The count is fixed when the latch is created, and once the latch is open it stays open.
+pub struct Latch<L = DefaultLock> {
+    count: AtomicUsize,
+    armed: AtomicU8,
+    lock: L,
+}
+
+/// A latch which spins while waiting
+pub type SpinLatch = Latch<crate::mutex::spin::SpinLock>;
+
+impl<L: crate::Init> Latch<L> {
+    /// Create a new latch, which opens after `count` calls to `count_down`
+    ///
+    /// If `count` is 0, then the latch is already open
+    #[inline]
+    pub const fn new(count: usize) -> Self {
+        Self {
+            count: AtomicUsize::new(count),
+            armed: AtomicU8::new(UNARMED),
+            lock: crate::Init::INIT,
+        }
+    }
+}
+
+impl<L: RawExclusiveLock + RawLockInfo> Latch<L>
+where
+    L::ExclusiveGuardTraits: Send,
+{
+    // acquire the lock, which is held until the count reaches zero
+    #[inline]
+    fn arm(&self) {
+        if self.armed.load(Ordering::Acquire) != ARMED {
+            self.arm_slow()
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn arm_slow(&self) {
+        match self
+            .armed
+            .compare_exchange(UNARMED, ARMING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                self.lock.exc_lock();
+                self.armed.store(ARMED, Ordering::Release);
+            }
+            Err(_) => {
+                let mut backoff = Backoff::new();
+
+                while self.armed.load(Ordering::Acquire) != ARMED {
+                    backoff.snooze();
+                }
+            }
+        }
+    }
+
+    /// The number of times `count_down` must be called before the latch opens
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Decrement the count, and open the latch if it reaches zero
+    ///
+    /// If the latch is already open, then this does nothing
+    pub fn count_down(&self) {
+        if self.count.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        self.arm();
+
+        let count = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |count| {
+                count.checked_sub(1)
+            });
+
+        if count == Ok(1) {
+            // SAFETY: the lock was acquired in place by `arm`, and the count reaches zero once
+            unsafe { self.lock.exc_unlock() }
+        }
+    }
+
+    /// Returns true if the latch is open, without blocking
+    #[inline]
+    pub fn try_wait(&self) -> bool {
+        self.count.load(Ordering::Acquire) == 0
+    }
+
+    /// Blocks the current thread until the latch is open
+    pub fn wait(&self) {
+        if self.try_wait() {
+            return;
+        }
+
+        self.arm();
+
+        // the lock is released once the count reaches zero
+        self.lock.exc_lock();
+        unsafe { self.lock.exc_unlock() }
+    }
+}
+
+impl<L> fmt::Debug for Latch<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Latch")
+            .field("count", &self.count.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+fn check_latch<L: RawExclusiveLock + RawLockInfo + crate::Init + Sync>()
+where
+    L::ExclusiveGuardTraits: Send,
+{
+    const THREADS: usize = 4;
+
+    let latch = Latch::<L>::new(THREADS);
+    let done = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|_| {
+                latch.wait();
+                assert_eq!(done.load(Ordering::Relaxed), THREADS);
+            });
+        }
+
+        for _ in 0..THREADS {
+            assert!(!latch.try_wait());
+            done.fetch_add(1, Ordering::Relaxed);
+            latch.count_down();
+        }
+    })
+    .unwrap();
+
+    assert!(latch.try_wait());
+    latch.count_down();
+    latch.wait();
+    assert_eq!(latch.count(), 0);
+}
+
+#[test]
+fn test_latch() {
+    check_latch::<DefaultLock>();
+    check_latch::<crate::mutex::spin::SpinLock>();
+
+    let latch = SpinLatch::new(0);
+    assert!(latch.try_wait());
+    latch.wait();
+}
@@ -8,7 +8,9 @@
         const_mut_refs,
         const_raw_ptr_deref,
         const_loop,
-        const_generics
+        const_generics,
+        coerce_unsized,
+        unsize
     )
 )]
 
@@ -50,6 +52,8 @@ pub trait Init: Sized {
 /// You can use `NoSend` to remove the `Send` bounds, and `NoSync` to remove the `Sync` bound.
 /// To remove both, you can use `(NoSend, NoSync)`
 /// If it is should be impossible to create the guard, then use `core::convert::Infallible`
+/// * if the lock can be unlocked from any thread, use `SendGuard` instead of `NoSend`, then the
+///   `send_guard` feature will make the guards `Send`
 pub unsafe trait RawLockInfo {
     /// A type that will remove auto-trait implementations for the `*ExclusiveGuard` types
     type ExclusiveGuardTraits: marker::Marker;
@@ -73,26 +77,86 @@ pub trait RawTimedLock: RawLockInfo {
     type Duration;
 }
 
+/// Non-blocking queries of the current state of a lock
+///
+/// These only return a snapshot of the lock state, which may be out of date as soon as
+/// they return, so they should only be used for assertions, debug logging and heuristics.
+pub trait RawLockQuery {
+    /// Returns true if there is any *exc lock* or *shr lock* on the lock
+    fn is_locked(&self) -> bool;
+
+    /// Returns true if there is an *exc lock* on the lock
+    fn is_locked_exclusive(&self) -> bool;
+}
+
+/// A pointer whose target doesn't move when the pointer is moved
+///
+/// This is used by the owned guards, which hold on to a pointer to their lock instead of
+/// borrowing it.
+///
+/// # Safety
+///
+/// `deref` must always return the same address, even after `Self` is moved, and that address
+/// must be valid until `Self` is dropped
+pub unsafe trait StableDeref: core::ops::Deref {}
+
+unsafe impl<T: ?Sized> StableDeref for &T {}
+unsafe impl<T: ?Sized> StableDeref for &mut T {}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+unsafe impl<T: ?Sized> StableDeref for std::boxed::Box<T> {}
+#[cfg(any(feature = "std", feature = "alloc"))]
+unsafe impl<T: ?Sized> StableDeref for std::rc::Rc<T> {}
+#[cfg(any(feature = "std", feature = "alloc"))]
+unsafe impl<T: ?Sized> StableDeref for std::sync::Arc<T> {}
+
+#[cfg(feature = "extra")]
+pub mod atomic_cell;
+#[cfg(feature = "extra")]
+pub mod barrier;
 pub mod combinators;
 mod defer;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod erased;
 pub mod exclusive_lock;
+#[cfg(feature = "extra")]
+pub mod latch;
+#[cfg(feature = "std")]
+pub mod lock_pool;
+pub mod multi_lock;
 pub mod mutex;
 #[allow(missing_docs)]
 pub mod once;
+#[cfg(feature = "std")]
+pub mod poison;
+#[cfg(all(feature = "extra", any(feature = "std", feature = "alloc")))]
+pub mod read_mostly;
+pub mod relax;
 pub mod remutex;
 pub mod rwlock;
+pub mod seqlock;
+pub mod stamped;
 pub mod share_lock;
 mod spin_wait;
+pub mod transaction;
 
 #[allow(missing_docs)]
 #[cfg(feature = "parking_lot_core")]
 pub mod condvar; // 25
+#[cfg(feature = "parking_lot_core")]
+pub mod event;
+#[cfg(feature = "parking_lot_core")]
+pub mod event_count;
+#[cfg(feature = "parking_lot_core")]
+pub mod semaphore;
 mod guard;
 pub mod marker;
 #[allow(missing_docs)]
 #[cfg(feature = "parking_lot_core")]
 pub mod waiter; // 25
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use erased::ErasedGuard;
 pub use guard::{Mapped, Pure, TryMapError};
 use marker::*;
 
@@ -107,6 +171,16 @@ macro_rules! trait_impls {
             type Instant = L::Instant;
             type Duration = L::Duration;
         }
+
+        impl<$L: ?Sized + RawLockQuery> RawLockQuery for $type {
+            fn is_locked(&self) -> bool {
+                L::is_locked(self)
+            }
+
+            fn is_locked_exclusive(&self) -> bool {
+                L::is_locked_exclusive(self)
+            }
+        }
     )*};
 }
 
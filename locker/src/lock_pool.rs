@@ -0,0 +1,274 @@
+//! A fixed pool of locks, which keys are assigned to by hashing
+//!
+//! This is like the [global lock set](crate::mutex::global), but keys are hashed instead of
+//! addresses, and the pool is an ordinary value, so its size, lock type, and hasher can be
+//! picked by the user. Different keys may be assigned to the same lock, so a thread must not
+//! lock a key while it is holding the lock of another key from the same pool. Use
+//! [`LockPool::lock_many`] to lock multiple keys at once, which locks them in index order
+//! so it never deadlocks.
+//!
+//! Each lock can protect a value, which [`LockPool::lock`] returns an
+//! [`ExclusiveGuard`] to. By default this is `()`, so the pool only provides mutual exclusion.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::hash::{BuildHasher, BuildHasherDefault, Hash};
+use core::marker::PhantomData;
+use std::collections::hash_map::DefaultHasher;
+
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveGuard};
+use crate::mutex::raw::Mutex;
+use crate::mutex::RawMutex;
+use crate::Inhabitted;
+
+/// A fixed pool of `N` locks, see the [module level docs](self) for details
+///
+/// Each lock protects a value of type `T`, which is shared by all of the keys assigned to it.
+pub struct LockPool<L, const N: usize, T = (), S = BuildHasherDefault<DefaultHasher>> {
+    locks: [Mutex<L>; N],
+    values: [UnsafeCell<T>; N],
+    hasher: PhantomData<fn() -> S>,
+}
+
+unsafe impl<L: Send, const N: usize, T: Send, S> Send for LockPool<L, N, T, S> {}
+unsafe impl<L: Sync, const N: usize, T: Send, S> Sync for LockPool<L, N, T, S> {}
+
+/// An RAII guard which holds the locks of several keys of a [`LockPool`],
+/// created by [`LockPool::lock_many`]
+///
+/// The locks are released when the guard is dropped
+#[must_use = "if unused the `LockPoolGuard` will immediately unlock"]
+pub struct LockPoolGuard<'a, L: RawMutex, const N: usize, T, S> {
+    pool: &'a LockPool<L, N, T, S>,
+    locked: [bool; N],
+    _traits: L::ExclusiveGuardTraits,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const UNIT: UnsafeCell<()> = UnsafeCell::new(());
+
+impl<L: RawMutex + crate::Init, const N: usize, S> LockPool<L, N, (), S> {
+    /// Create a new lock pool
+    pub const fn new() -> Self {
+        assert!(N != 0, "A lock pool must have at least one lock");
+
+        Self {
+            locks: [<Mutex<L> as crate::Init>::INIT; N],
+            values: [UNIT; N],
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<L: RawMutex + crate::Init, const N: usize, S> Default for LockPool<L, N, (), S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: RawMutex + crate::Init, const N: usize, T, S> LockPool<L, N, T, S> {
+    /// Create a new lock pool, where the `i`-th lock protects `values[i]`
+    pub fn from_values(values: [T; N]) -> Self {
+        assert!(N != 0, "A lock pool must have at least one lock");
+
+        Self {
+            locks: [<Mutex<L> as crate::Init>::INIT; N],
+            values: values.map(UnsafeCell::new),
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<L, const N: usize, T, S> LockPool<L, N, T, S> {
+    /// Get mutable references to the values protected by each lock
+    #[inline]
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.values.iter_mut().map(UnsafeCell::get_mut)
+    }
+
+    /// Consume the pool, and get the values protected by each lock
+    #[inline]
+    pub fn into_values(self) -> [T; N] {
+        self.values.map(UnsafeCell::into_inner)
+    }
+}
+
+impl<L: RawMutex, const N: usize, T, S: BuildHasher + Default> LockPool<L, N, T, S> {
+    /// The index of the lock that `key` is assigned to
+    #[inline]
+    pub fn index<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        (S::default().hash_one(key) % N as u64) as usize
+    }
+
+    /// Get the raw mutex that `key` is assigned to
+    #[inline]
+    pub fn get<K: Hash + ?Sized>(&self, key: &K) -> &Mutex<L> {
+        &self.locks[self.index(key)]
+    }
+
+    /// Checks if two keys will contend
+    #[inline]
+    pub fn will_contend<K: Hash + ?Sized, J: Hash + ?Sized>(&self, a: &K, b: &J) -> bool {
+        self.index(a) == self.index(b)
+    }
+}
+
+impl<L: RawMutex, const N: usize, T, S: BuildHasher + Default> LockPool<L, N, T, S>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    #[inline]
+    fn wrap<'a>(&'a self, raw: RawExclusiveGuard<'a, L>, index: usize) -> ExclusiveGuard<'a, L, T> {
+        // SAFETY: the guard holds the lock which protects this value
+        unsafe { ExclusiveGuard::from_raw_parts(raw, self.values[index].get()) }
+    }
+
+    /// Acquire the lock of `key`, blocking the current thread until it is able to do so
+    ///
+    /// The current thread must not hold the lock of any other key from this pool,
+    /// because it may be the same lock
+    #[inline]
+    pub fn lock<K: Hash + ?Sized>(&self, key: &K) -> ExclusiveGuard<'_, L, T> {
+        let index = self.index(key);
+        self.wrap(self.locks[index].lock(), index)
+    }
+
+    /// Attempt to acquire the lock of `key`, without blocking
+    #[inline]
+    pub fn try_lock<K: Hash + ?Sized>(&self, key: &K) -> Option<ExclusiveGuard<'_, L, T>> {
+        let index = self.index(key);
+        Some(self.wrap(self.locks[index].try_lock()?, index))
+    }
+
+    /// Acquire the locks of all of the `keys`, in index order
+    ///
+    /// Keys which are assigned to the same lock only lock it once
+    pub fn lock_many<K: Hash, I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+    ) -> LockPoolGuard<'_, L, N, T, S> {
+        let mut guard = LockPoolGuard {
+            pool: self,
+            locked: [false; N],
+            _traits: Inhabitted::INIT,
+        };
+
+        let mut wanted = [false; N];
+
+        for key in keys {
+            wanted[self.index(&key)] = true;
+        }
+
+        for (index, _) in wanted.iter().enumerate().filter(|(_, &wanted)| wanted) {
+            self.locks[index].inner().exc_lock();
+            guard.locked[index] = true;
+        }
+
+        guard
+    }
+}
+
+impl<L: RawMutex, const N: usize, T, S: BuildHasher + Default> LockPoolGuard<'_, L, N, T, S> {
+    /// Returns true if this guard holds the lock of `key`
+    #[inline]
+    pub fn contains<K: Hash + ?Sized>(&self, key: &K) -> bool {
+        self.locked[self.pool.index(key)]
+    }
+
+    /// Get the value protected by the lock of `key`, if this guard holds it
+    #[inline]
+    pub fn get<K: Hash + ?Sized>(&self, key: &K) -> Option<&T> {
+        let index = self.pool.index(key);
+
+        if self.locked[index] {
+            // SAFETY: this guard holds the lock which protects this value
+            Some(unsafe { &*self.pool.values[index].get() })
+        } else {
+            None
+        }
+    }
+
+    /// Get the value protected by the lock of `key`, if this guard holds it
+    #[inline]
+    pub fn get_mut<K: Hash + ?Sized>(&mut self, key: &K) -> Option<&mut T> {
+        let index = self.pool.index(key);
+
+        if self.locked[index] {
+            // SAFETY: this guard holds the lock which protects this value, and is borrowed uniquely
+            Some(unsafe { &mut *self.pool.values[index].get() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<L: RawMutex, const N: usize, T, S> Drop for LockPoolGuard<'_, L, N, T, S> {
+    fn drop(&mut self) {
+        for (index, &locked) in self.locked.iter().enumerate().rev() {
+            if locked {
+                // SAFETY: this guard acquired the lock in `lock_many`
+                unsafe { self.pool.locks[index].inner().exc_unlock() }
+            }
+        }
+    }
+}
+
+impl<L, const N: usize, T, S> fmt::Debug for LockPool<L, N, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockPool").field("locks", &N).finish()
+    }
+}
+
+impl<L: RawMutex, const N: usize, T, S> fmt::Debug for LockPoolGuard<'_, L, N, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let locked = self.locked.iter().filter(|&&locked| locked).count();
+        f.debug_struct("LockPoolGuard")
+            .field("locked", &locked)
+            .finish()
+    }
+}
+
+#[test]
+fn test_lock_pool() {
+    use crate::mutex::default::DefaultLock;
+
+    static POOL: LockPool<DefaultLock, 13> = LockPool::new();
+
+    let guard = POOL.lock("a");
+    assert!(POOL.try_lock("a").is_none());
+    drop(guard);
+
+    let count = core::sync::atomic::AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for i in 0..4 {
+            let count = &count;
+            s.spawn(move |_| {
+                for j in 0..100 {
+                    // lock the keys in a different order on each thread
+                    let keys = [i * j, j, 100 - j];
+                    let guard = POOL.lock_many(keys.iter().cycle().skip(i).take(3));
+
+                    for key in keys.iter() {
+                        assert!(guard.contains(key));
+                        assert!(POOL.try_lock(key).is_none());
+                    }
+
+                    count.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(count.into_inner(), 400);
+    assert!(POOL.will_contend(&"a", &"a"));
+
+    let pool = LockPool::<DefaultLock, 4, usize>::from_values([0; 4]);
+
+    *pool.lock("a") += 1;
+    *pool.lock_many(["b"]).get_mut("b").unwrap() += 2;
+    assert_eq!(pool.lock_many(["a"]).get("a"), Some(&1));
+    assert_eq!(pool.into_values().iter().sum::<usize>(), 3);
+}
@@ -53,6 +53,20 @@ impl Inhabitted for NoSend {
     const INIT: Self = Self(core::marker::PhantomData);
 }
 
+/// A [`Marker`](crate::Marker) for locks that can be unlocked from any thread
+///
+/// This is [`NoSend`](crate::NoSend) by default, and `()` when the `send_guard` feature
+/// is enabled, so that guards can be acquired on one thread and released on another.
+#[cfg(not(feature = "send_guard"))]
+pub type SendGuard = NoSend;
+
+/// A [`Marker`](crate::Marker) for locks that can be unlocked from any thread
+///
+/// This is [`NoSend`](crate::NoSend) by default, and `()` when the `send_guard` feature
+/// is enabled, so that guards can be acquired on one thread and released on another.
+#[cfg(feature = "send_guard")]
+pub type SendGuard = ();
+
 impl Marker for NoSync {}
 impl Inhabitted for NoSync {
     const INIT: Self = Self(core::marker::PhantomData);
@@ -0,0 +1,182 @@
+//! Lock multiple locks at once without deadlocking
+//!
+//! If two threads lock the same pair of locks in opposite orders, they can deadlock.
+//! [`lock_both`] and [`lock_all`] avoid this by always acquiring the locks in the order of
+//! their addresses, so every thread agrees on the order no matter how the locks were passed in.
+//! The guards are still returned in the order that the locks were passed in.
+//!
+//! The address of a lock is the address of the raw lock that it actually acquires, see
+//! [`RawExclusiveLock::exc_lock_addr`]. So locks which share an underlying lock, like the
+//! [global locks](crate::mutex::global), are ordered by the shared lock. Such locks can't be held
+//! at the same time, so they are treated as the same lock.
+
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::{Mutex, RawMutex};
+use crate::rwlock::{RawRwLock, RwLock};
+
+/// A lock which can be acquired by [`lock_both`] and [`lock_all`]
+pub trait Lockable {
+    /// The guard returned after acquiring the lock
+    type Guard;
+
+    /// The address that is used to order the locks
+    ///
+    /// Locks which share an underlying lock must have the same address
+    fn address(&self) -> usize;
+
+    /// Acquire the lock, blocking the current thread until it is able to do so
+    fn acquire(&self) -> Self::Guard;
+}
+
+impl<'a, L: RawMutex, T: ?Sized> Lockable for &'a Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    type Guard = ExclusiveGuard<'a, L, T>;
+
+    #[inline]
+    fn address(&self) -> usize {
+        self.raw().inner().exc_lock_addr()
+    }
+
+    #[inline]
+    fn acquire(&self) -> Self::Guard {
+        self.lock()
+    }
+}
+
+/// Acquires an *exc lock*, like [`RwLock::write`]
+impl<'a, L: RawRwLock, T: ?Sized> Lockable for &'a RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    type Guard = ExclusiveGuard<'a, L, T>;
+
+    #[inline]
+    fn address(&self) -> usize {
+        self.raw().inner().exc_lock_addr()
+    }
+
+    #[inline]
+    fn acquire(&self) -> Self::Guard {
+        self.write()
+    }
+}
+
+/// A tuple of [`Lockable`]s which can be acquired by [`lock_all`]
+pub trait LockAll {
+    /// A tuple of the guards of each lock
+    type Guards;
+
+    /// Acquire all of the locks in address order
+    fn lock_all(self) -> Self::Guards;
+}
+
+#[cold]
+#[inline(never)]
+pub(crate) fn same_lock() -> ! {
+    panic!("tried to acquire the same lock more than once")
+}
+
+macro_rules! lock_all {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Lockable),+> LockAll for ($($name,)+) {
+            type Guards = ($($name::Guard,)+);
+
+            fn lock_all(self) -> Self::Guards {
+                let mut order = [$((self.$index.address(), $index)),+];
+                order.sort_unstable();
+
+                if order.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                    same_lock()
+                }
+
+                let mut guards = ($(None::<$name::Guard>,)+);
+
+                for &(_, index) in order.iter() {
+                    match index {
+                        $($index => guards.$index = Some(self.$index.acquire()),)+
+                        _ => unreachable!(),
+                    }
+                }
+
+                ($(match guards.$index {
+                    Some(guard) => guard,
+                    None => unreachable!(),
+                },)+)
+            }
+        }
+    };
+}
+
+lock_all!(A 0);
+lock_all!(A 0, B 1);
+lock_all!(A 0, B 1, C 2);
+lock_all!(A 0, B 1, C 2, D 3);
+lock_all!(A 0, B 1, C 2, D 3, E 4);
+lock_all!(A 0, B 1, C 2, D 3, E 4, F 5);
+lock_all!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+lock_all!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Acquire two locks in address order, and return their guards in argument order
+///
+/// # Panic
+///
+/// This function panics if both arguments are the same lock,
+/// or share an underlying lock
+pub fn lock_both<A: Lockable, B: Lockable>(a: A, b: B) -> (A::Guard, B::Guard) {
+    (a, b).lock_all()
+}
+
+/// Acquire a tuple of locks in address order, and return their guards in argument order
+///
+/// Tuples of up to 8 locks are supported
+///
+/// # Panic
+///
+/// This function panics if the same lock is passed in more than once,
+/// or if two of the locks share an underlying lock
+pub fn lock_all<A: LockAll>(locks: A) -> A::Guards {
+    locks.lock_all()
+}
+
+#[test]
+fn test_lock_all() {
+    use crate::mutex::spin::SpinLock;
+    use crate::rwlock::spin::SpinLock as RwSpinLock;
+
+    let a = SpinLock::mutex(0);
+    let b = SpinLock::mutex(1);
+    let c = RwSpinLock::rwlock(2);
+
+    let (mut x, mut y) = lock_both(&b, &a);
+    assert!(a.try_lock().is_none());
+    assert!(b.try_lock().is_none());
+    *x += 10;
+    *y += 10;
+    drop((x, y));
+
+    let (x, y, z) = lock_all((&c, &a, &b));
+    assert_eq!((*x, *y, *z), (2, 10, 11));
+    assert!(c.try_read().is_none());
+    drop((x, y, z));
+
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guards = lock_both(&a, &a);
+    }))
+    .is_err());
+
+    // there are more mutexes than global locks, so some of them must share a lock
+    use crate::mutex::global::GlobalLock;
+    let globals: [_; 64] = core::array::from_fn(GlobalLock::mutex);
+    let (a, b) = (0..64)
+        .flat_map(|i| (i + 1..64).map(move |j| (i, j)))
+        .find(|&(i, j)| GlobalLock::will_mutex_contend(&globals[i], &globals[j]))
+        .unwrap();
+
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guards = lock_both(&globals[a], &globals[b]);
+    }))
+    .is_err());
+}
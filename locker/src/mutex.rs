@@ -2,7 +2,9 @@
 
 use core::cell::UnsafeCell;
 
-use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLock, RawExclusiveLockTimed};
+use crate::exclusive_lock::{
+    ExclusiveGuard, RawExclusiveLock, RawExclusiveLockFair, RawExclusiveLockTimed,
+};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "extra")] {
@@ -16,6 +18,21 @@ cfg_if::cfg_if! {
         pub mod tagged_default;
         pub mod splittable_spin;
         pub mod splittable_default;
+        pub mod ticket;
+        pub mod one_shot;
+        pub mod cohort;
+        #[cfg(feature = "std")]
+        pub mod flat_combining;
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        pub mod clh;
+        #[cfg(all(feature = "libc", target_os = "linux"))]
+        pub mod futex;
+        #[cfg(all(feature = "libc", feature = "std", target_os = "linux"))]
+        pub mod pi_futex;
+        #[cfg(all(unix, feature = "libc", any(feature = "std", feature = "alloc")))]
+        pub mod pthread;
+        #[cfg(all(feature = "libc", target_os = "linux"))]
+        pub mod shared;
 
         #[cfg(feature = "parking_lot_core")]
         pub mod adaptive;
@@ -23,6 +40,10 @@ cfg_if::cfg_if! {
         pub mod tagged;
         #[cfg(feature = "parking_lot_core")]
         pub mod splittable;
+        #[cfg(feature = "parking_lot_core")]
+        pub mod hybrid;
+        #[cfg(all(feature = "parking_lot_core", feature = "libc", target_os = "linux"))]
+        pub mod owner_aware;
     }
 }
 
@@ -38,7 +59,7 @@ pub unsafe trait RawMutex: crate::RawLockInfo + RawExclusiveLock {}
 /// A mutual exclusion primitive useful for protecting shared data
 ///
 /// This mutex will block threads waiting for the lock to become available.
-/// The mutex can also be statically initialized or created via a `new` constructor (via `nightly` feature flag)
+/// The mutex can also be statically initialized or created via the `const` `new` constructor
 /// or with the `from_raw_parts` (even on `stable`, but `unsafe`).
 /// Each mutex has a type parameter which represents the data that it is protecting.
 /// The data can only be accessed through the RAII guards returned from `lock` and
@@ -56,8 +77,45 @@ impl<L: RawMutex + crate::Init, T: Default> Default for Mutex<L, T> {
     }
 }
 
-unsafe impl<L: Send + RawMutex, T: Send> Send for Mutex<L, T> {}
-unsafe impl<L: Sync + RawMutex, T: Send> Sync for Mutex<L, T> {}
+impl<L: RawMutex, T: ?Sized + core::fmt::Debug> core::fmt::Debug for Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// This uses `try_lock`, so it never blocks, and prints `<locked>` if the mutex is locked
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut f = f.debug_struct("Mutex");
+
+        match self.try_lock() {
+            Some(guard) => f.field("data", &&*guard),
+            None => f.field("data", &crate::guard::Placeholder("<locked>")),
+        }
+        .finish()
+    }
+}
+
+/// This locks the mutex while serializing the value
+#[cfg(feature = "serde")]
+impl<L: RawMutex, T: ?Sized + serde::Serialize> serde::Serialize for Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lock().serialize(serializer)
+    }
+}
+
+/// This creates a new unlocked mutex
+#[cfg(feature = "serde")]
+impl<'de, L: RawMutex + crate::Init, T: serde::Deserialize<'de>> serde::Deserialize<'de>
+    for Mutex<L, T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+unsafe impl<L: Send + RawMutex, T: ?Sized + Send> Send for Mutex<L, T> {}
+unsafe impl<L: Sync + RawMutex, T: ?Sized + Send> Sync for Mutex<L, T> {}
 
 impl<L, T> Mutex<L, T> {
     /// Create a new mutex with the given raw mutex
@@ -94,6 +152,14 @@ impl<L, T: ?Sized> Mutex<L, T> {
         self.value.get()
     }
 
+    /// Get a raw pointer to the value, without locking the mutex
+    ///
+    /// This is the same as [`as_mut_ptr`](Self::as_mut_ptr). It is up to the caller to
+    /// make sure that the appropriate lock is held while the value is accessed through this pointer.
+    pub fn data_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             /// the underlying raw mutex
@@ -137,21 +203,25 @@ impl<L, T: ?Sized> Mutex<L, T> {
     }
 }
 
+impl<L: crate::rwlock::RawRwLock, T: ?Sized + Sync> Mutex<L, T> {
+    /// View this mutex as a rwlock
+    ///
+    /// This is only possible if `T: Sync`, because the rwlock can share `T` between threads,
+    /// and if the lock supports *shr lock*s. The readers of the rwlock will wait for the
+    /// mutex to be unlocked, and the mutex will wait for all readers.
+    pub fn as_rwlock(&self) -> &crate::rwlock::RwLock<L, T> {
+        // SAFETY: `Mutex` and `RwLock` are both `repr(C)` with the same fields,
+        // and `raw::Mutex` and `raw::RwLock` are both `repr(transparent)` over `L`.
+        // `T: Sync`, so `RwLock` doesn't need stronger `Sync` bounds than `Mutex`
+        unsafe { &*(self as *const Self as *const crate::rwlock::RwLock<L, T>) }
+    }
+}
+
 impl<L: RawMutex + crate::Init, T> Mutex<L, T> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            /// Creates a new mutex in an unlocked state ready for use.
-            #[inline]
-            pub const fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        } else {
-            /// Creates a new mutex in an unlocked state ready for use.
-            #[inline]
-            pub fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        }
+    /// Creates a new mutex in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self::from_raw_parts(crate::Init::INIT, value)
     }
 }
 
@@ -196,6 +266,352 @@ where
     pub fn try_lock(&self) -> Option<ExclusiveGuard<'_, L, T>> {
         Some(self.wrap(self.raw.try_lock()?))
     }
+
+    /// Acquires the mutex, and runs the given function on the locked value
+    ///
+    /// The mutex is unlocked once the function returns, so the lock can't be held
+    /// for longer than the function call.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Acquires a mutex through any [`StableDeref`](crate::StableDeref) pointer, blocking the
+    /// current thread until it is able to do so.
+    ///
+    /// This is like [`lock`](Self::lock), but the returned guard holds on to the pointer
+    /// instead of borrowing the mutex, so the guard is `'static` if the pointer is.
+    ///
+    /// This is an associated function that needs to be used as `Mutex::lock_owned(...)`.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn lock_owned<P>(this: P) -> crate::exclusive_lock::OwnedExclusiveGuard<P, L, T>
+    where
+        P: crate::StableDeref<Target = Self>,
+    {
+        this.raw.inner().exc_lock();
+        unsafe { crate::exclusive_lock::OwnedExclusiveGuard::from_raw(this) }
+    }
+
+    /// Attempts to acquire this lock through any [`StableDeref`](crate::StableDeref) pointer.
+    ///
+    /// This is like [`try_lock`](Self::try_lock), but the returned guard holds on to the pointer
+    /// instead of borrowing the mutex. If the lock could not be acquired, then the pointer is
+    /// given back.
+    ///
+    /// This is an associated function that needs to be used as `Mutex::try_lock_owned(...)`.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_lock_owned<P>(
+        this: P,
+    ) -> Result<crate::exclusive_lock::OwnedExclusiveGuard<P, L, T>, P>
+    where
+        P: crate::StableDeref<Target = Self>,
+    {
+        if this.raw.inner().exc_try_lock() {
+            Ok(unsafe { crate::exclusive_lock::OwnedExclusiveGuard::from_raw(this) })
+        } else {
+            Err(this)
+        }
+    }
+
+    /// Creates a guard which only acquires the mutex the first time the data is accessed
+    ///
+    /// If the data is never accessed, then the mutex is never locked. Otherwise the mutex
+    /// is unlocked when the guard goes out of scope, like the guard returned by [`lock`](Self::lock).
+    #[inline]
+    pub fn lock_lazy(&self) -> crate::exclusive_lock::LazyGuard<'_, L, T> {
+        crate::exclusive_lock::LazyGuard::new(self)
+    }
+
+    /// Acquires a pinned mutex, and returns a guard which only gives pinned access to the value
+    ///
+    /// Use [`Pin::as_mut`](core::pin::Pin::as_mut) on the guard to get a `Pin<&mut T>`.
+    ///
+    /// # Safety
+    ///
+    /// Pinning is structural for the value in the mutex, so once this is called
+    /// the value must not be moved until it is dropped. In particular, it must not be moved
+    /// out of a guard returned by the unpinned lock methods, like [`lock`](Self::lock),
+    /// [`replace`](Self::replace) or [`take`](Self::take), unless `T: Unpin`.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub unsafe fn lock_pin(
+        self: core::pin::Pin<&Self>,
+    ) -> core::pin::Pin<ExclusiveGuard<'_, L, T>> {
+        core::pin::Pin::new_unchecked(self.get_ref().lock())
+    }
+
+    /// Attempts to acquire a pinned mutex, and returns a guard which only gives pinned access to the value
+    ///
+    /// This function does not block.
+    ///
+    /// # Safety
+    ///
+    /// See [`lock_pin`](Self::lock_pin)
+    #[inline]
+    pub unsafe fn try_lock_pin(
+        self: core::pin::Pin<&Self>,
+    ) -> Option<core::pin::Pin<ExclusiveGuard<'_, L, T>>> {
+        Some(core::pin::Pin::new_unchecked(self.get_ref().try_lock()?))
+    }
+}
+
+impl<L: RawMutex, T> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Acquires the mutex, and replaces the locked value with `value`, returning the old value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn replace(&self, value: T) -> T {
+        core::mem::replace(&mut self.lock(), value)
+    }
+
+    /// Acquires the mutex, and takes the locked value, leaving `Default::default()` in its place
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        core::mem::take(&mut self.lock())
+    }
+
+    /// Acquires the mutex, and returns a clone of the locked value
+    ///
+    /// The lock is released before this function returns, so no guard is exposed
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn get_cloned(&self) -> T
+    where
+        T: Clone,
+    {
+        T::clone(&self.lock())
+    }
+
+    /// Acquires the mutex, and runs `f` on the locked value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        f(&mut self.lock())
+    }
+
+    /// Acquires the mutex, runs `f` on the locked value, and returns a clone of the new value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn update_and_fetch(&self, f: impl FnOnce(&mut T)) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.lock();
+        f(&mut guard);
+        T::clone(&guard)
+    }
+
+    /// Acquires the mutex, runs `f` on the locked value, and returns a clone of the old value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn fetch_update(&self, f: impl FnOnce(&mut T)) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.lock();
+        let old = T::clone(&guard);
+        f(&mut guard);
+        old
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L: RawMutex, T: ?Sized> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Acquires a mutex through an `Arc`, blocking the current thread until it is able to do so.
+    ///
+    /// This is like [`lock`](Self::lock), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the mutex.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn lock_arc(self: &std::sync::Arc<Self>) -> crate::exclusive_lock::ArcExclusiveGuard<L, T> {
+        self.raw.inner().exc_lock();
+        unsafe { crate::exclusive_lock::ArcExclusiveGuard::from_raw(self.clone()) }
+    }
+
+    /// Attempts to acquire this lock through an `Arc`.
+    ///
+    /// This is like [`try_lock`](Self::try_lock), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the mutex.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_lock_arc(
+        self: &std::sync::Arc<Self>,
+    ) -> Option<crate::exclusive_lock::ArcExclusiveGuard<L, T>> {
+        if self.raw.inner().exc_try_lock() {
+            Some(unsafe { crate::exclusive_lock::ArcExclusiveGuard::from_raw(self.clone()) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<L, T, const N: usize> Mutex<L, [T; N]> {
+    /// View this mutex as a mutex over a slice
+    #[inline]
+    pub fn as_slice(&self) -> &Mutex<L, [T]> {
+        self
+    }
+}
+
+impl<L, T> Mutex<L, [T]> {
+    /// The number of elements in the locked slice
+    ///
+    /// This doesn't lock the mutex, because the length of the slice can't change
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.value.get().len()
+    }
+
+    /// Returns true if the locked slice has no elements
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L, T> Mutex<L, [T]> {
+    /// Move the elements of a boxed slice into a new boxed mutex
+    pub fn from_boxed_slice(
+        raw: raw::Mutex<L>,
+        values: std::boxed::Box<[T]>,
+    ) -> std::boxed::Box<Self> {
+        unsafe { std::boxed::Box::from_raw(box_slice_with_head(raw, values) as *mut Self) }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L: RawMutex + crate::Init, T> From<std::boxed::Box<[T]>> for std::boxed::Box<Mutex<L, [T]>> {
+    #[inline]
+    fn from(values: std::boxed::Box<[T]>) -> Self {
+        Mutex::from_boxed_slice(crate::Init::INIT, values)
+    }
+}
+
+/// Allocate a `repr(C)` struct with `head` as it's first field and the elements of `values`
+/// as it's last field, and return a pointer to it with the metadata of the slice
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub(crate) fn box_slice_with_head<H, T>(head: H, values: std::boxed::Box<[T]>) -> *mut [T] {
+    use core::alloc::Layout;
+    use core::mem::ManuallyDrop;
+
+    let len = values.len();
+    let (layout, offset) = Layout::new::<H>()
+        .extend(Layout::array::<T>(len).expect("slice is too large"))
+        .expect("slice is too large");
+    let layout = layout.pad_to_align();
+
+    let ptr = if layout.size() == 0 {
+        layout.align() as *mut u8
+    } else {
+        let ptr = unsafe { std::alloc::alloc(layout) };
+
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout)
+        }
+
+        ptr
+    };
+
+    let values = std::boxed::Box::into_raw(values);
+
+    unsafe {
+        ptr.cast::<H>().write(head);
+        ptr.add(offset)
+            .cast::<T>()
+            .copy_from_nonoverlapping(values.cast::<T>(), len);
+
+        // the elements were moved out, so only free the old allocation
+        drop(std::boxed::Box::from_raw(values as *mut [ManuallyDrop<T>]));
+    }
+
+    core::ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), len)
+}
+
+impl<L: RawMutex, T: ?Sized> Mutex<L, T> {
+    /// Forcibly unlock the mutex
+    ///
+    /// This is useful when a guard was deliberately forgotten, for example with
+    /// `core::mem::forget` or after it was handed off through FFI.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and there must be no live guards for the lock
+    /// held by the current context
+    #[inline]
+    pub unsafe fn force_unlock(&self) {
+        self.raw.inner().exc_unlock()
+    }
+}
+
+impl<L: RawMutex + RawExclusiveLockFair, T: ?Sized> Mutex<L, T> {
+    /// Forcibly unlock the mutex using a fair unlock protocol
+    ///
+    /// See [`force_unlock`](Self::force_unlock) for more details
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and there must be no live guards for the lock
+    /// held by the current context
+    #[inline]
+    pub unsafe fn force_unlock_fair(&self) {
+        self.raw.inner().exc_unlock_fair()
+    }
+}
+
+impl<L: RawMutex + crate::RawLockQuery, T: ?Sized> Mutex<L, T> {
+    /// Returns true if the mutex is currently locked
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
 }
 
 impl<L: RawMutex + RawExclusiveLockTimed, T: ?Sized> Mutex<L, T>
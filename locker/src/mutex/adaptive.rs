@@ -15,24 +15,81 @@ use core::sync::atomic::{AtomicU8, Ordering};
 use std::time::{Duration, Instant};
 
 /// an adaptive raw mutex
-pub type RawMutex = crate::mutex::raw::Mutex<AdaptiveLock>;
+pub type RawMutex<P = DefaultSpin> = crate::mutex::raw::Mutex<AdaptiveLock<P>>;
 /// an adaptive mutex
-pub type Mutex<T> = crate::mutex::Mutex<AdaptiveLock, T>;
+pub type Mutex<T, P = DefaultSpin> = crate::mutex::Mutex<AdaptiveLock<P>, T>;
+
+/// Decides how long a thread waiting for an [`AdaptiveLock`] spins before parking
+///
+/// The policy is stored in the lock, so it may keep track of the owner of the lock.
+/// The hooks are called while the lock is being acquired or released, so they must not
+/// call into `parking_lot_core`, or panic.
+pub trait SpinPolicy: crate::Init {
+    /// The state of a single waiting thread
+    type Spinner;
+
+    /// Start spinning, this is called again after the waiting thread is unparked
+    fn spinner(&self) -> Self::Spinner;
+
+    /// Spin once, returns false if the waiting thread should park instead
+    fn spin(&self, spinner: &mut Self::Spinner) -> bool;
+
+    /// Called after the current thread acquires the lock
+    #[inline]
+    fn locked(&self) {}
+
+    /// Called before the current thread releases the lock
+    #[inline]
+    fn unlocked(&self) {}
+
+    /// Called before the current thread parks, until the lock is released
+    #[inline]
+    fn parking(&self) {}
+
+    /// Called after the current thread is unparked
+    #[inline]
+    fn unparked(&self) {}
+}
+
+/// The default [`SpinPolicy`], which spins with an exponential back-off,
+/// and yields to the OS a few times before parking
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultSpin;
+
+impl crate::Init for DefaultSpin {
+    const INIT: Self = Self;
+}
+
+impl SpinPolicy for DefaultSpin {
+    type Spinner = SpinWait;
+
+    #[inline]
+    fn spinner(&self) -> Self::Spinner {
+        SpinWait::new()
+    }
+
+    #[inline]
+    fn spin(&self, spinner: &mut Self::Spinner) -> bool {
+        spinner.spin()
+    }
+}
 
 /// An adaptive mutex lock backed by `parking_lot_core`
-pub struct AdaptiveLock {
+///
+/// The entire state of the lock is a single `AtomicU8`, waiting threads are parked
+/// in `parking_lot_core`'s global table, keyed on the address of the lock. So this lock
+/// is small enough to embed in many small objects, without burning CPU like a spin lock.
+///
+/// Before parking, waiting threads spin as long as the [`SpinPolicy`] `P` allows.
+pub struct AdaptiveLock<P = DefaultSpin> {
     state: AtomicU8,
+    policy: P,
 }
 
 impl AdaptiveLock {
-    const LOCK_BIT: u8 = 0b01;
-    const PARK_BIT: u8 = 0b10;
-
     /// Create a new adaptive mutex lock
     pub const fn new() -> Self {
-        AdaptiveLock {
-            state: AtomicU8::new(0),
-        }
+        Self::with_policy(DefaultSpin)
     }
 
     /// Create a new raw mutex
@@ -44,11 +101,31 @@ impl AdaptiveLock {
     pub const fn mutex<T>(value: T) -> Mutex<T> {
         Mutex::from_raw_parts(Self::raw_mutex(), value)
     }
+}
+
+impl<P> AdaptiveLock<P> {
+    const LOCK_BIT: u8 = 0b01;
+    const PARK_BIT: u8 = 0b10;
 
+    /// Create a new adaptive mutex lock which spins with the given policy
+    pub const fn with_policy(policy: P) -> Self {
+        AdaptiveLock {
+            state: AtomicU8::new(0),
+            policy,
+        }
+    }
+
+    /// The spin policy of this lock
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+}
+
+impl<P: SpinPolicy> AdaptiveLock<P> {
     #[cold]
     #[inline(never)]
     fn lock_slow(&self, timeout: Option<Instant>) -> bool {
-        let mut spinwait = SpinWait::new();
+        let mut spinner = self.policy.spinner();
         let mut state = self.state.load(Ordering::Relaxed);
         loop {
             // Grab the lock if it isn't locked, even if there is a queue on it
@@ -59,14 +136,17 @@ impl AdaptiveLock {
                     Ordering::Acquire,
                     Ordering::Relaxed,
                 ) {
-                    Ok(_) => return true,
+                    Ok(_) => {
+                        self.policy.locked();
+                        return true;
+                    }
                     Err(x) => state = x,
                 }
                 continue;
             }
 
             // If there is no queue, try spinning a few times
-            if state & Self::PARK_BIT == 0 && spinwait.spin() {
+            if state & Self::PARK_BIT == 0 && self.policy.spin(&mut spinner) {
                 state = self.state.load(Ordering::Relaxed);
                 continue;
             }
@@ -95,11 +175,13 @@ impl AdaptiveLock {
                 }
             };
 
+            self.policy.parking();
+
             // SAFETY:
             //   * `addr` is an address we control.
             //   * `validate`/`timed_out` does not panic or call into any function of `parking_lot`.
             //   * `before_sleep` does not call `park`, nor does it panic.
-            match unsafe {
+            let result = unsafe {
                 parking_lot_core::park(
                     addr,
                     validate,
@@ -108,10 +190,17 @@ impl AdaptiveLock {
                     DEFAULT_PARK_TOKEN,
                     timeout,
                 )
-            } {
+            };
+
+            self.policy.unparked();
+
+            match result {
                 // The thread that unparked us passed the lock on to us
                 // directly without unlocking it.
-                ParkResult::Unparked(TOKEN_HANDOFF) => return true,
+                ParkResult::Unparked(TOKEN_HANDOFF) => {
+                    self.policy.locked();
+                    return true;
+                }
 
                 // We were unparked normally, try acquiring the lock again
                 ParkResult::Unparked(_) => (),
@@ -124,7 +213,7 @@ impl AdaptiveLock {
             }
 
             // Loop back and try locking again
-            spinwait.reset();
+            spinner = self.policy.spinner();
             state = self.state.load(Ordering::Relaxed);
         }
     }
@@ -167,22 +256,44 @@ impl AdaptiveLock {
 
     #[cold]
     fn bump_slow(&self, force_fair: bool) {
+        self.policy.unlocked();
         self.unlock_slow(force_fair);
         self.exc_lock();
     }
 }
 
-impl crate::Init for AdaptiveLock {
-    const INIT: Self = Self::new();
+impl<P: SpinPolicy> Default for AdaptiveLock<P> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_policy(P::INIT)
+    }
+}
+
+impl<P: SpinPolicy> crate::Init for AdaptiveLock<P> {
+    const INIT: Self = Self::with_policy(P::INIT);
 }
 
-unsafe impl crate::mutex::RawMutex for AdaptiveLock {}
-unsafe impl crate::RawLockInfo for AdaptiveLock {
+unsafe impl<P: SpinPolicy> crate::mutex::RawMutex for AdaptiveLock<P> {}
+unsafe impl<P> crate::RawLockInfo for AdaptiveLock<P> {
     type ExclusiveGuardTraits = ();
     type ShareGuardTraits = core::convert::Infallible;
 }
 
-unsafe impl RawExclusiveLock for AdaptiveLock {
+impl<P> crate::RawLockQuery for AdaptiveLock<P> {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & Self::LOCK_BIT != 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked()
+    }
+}
+
+unsafe impl<P: SpinPolicy> crate::combinators::Elidable for AdaptiveLock<P> {}
+
+unsafe impl<P: SpinPolicy> RawExclusiveLock for AdaptiveLock<P> {
     #[inline]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
@@ -194,7 +305,7 @@ unsafe impl RawExclusiveLock for AdaptiveLock {
     fn exc_try_lock(&self) -> bool {
         let state = self.state.load(Ordering::Acquire);
 
-        (state & Self::LOCK_BIT) == 0
+        let acquired = (state & Self::LOCK_BIT) == 0
             && self
                 .state
                 .compare_exchange_weak(
@@ -203,11 +314,19 @@ unsafe impl RawExclusiveLock for AdaptiveLock {
                     Ordering::Acquire,
                     Ordering::Relaxed,
                 )
-                .is_ok()
+                .is_ok();
+
+        if acquired {
+            self.policy.locked();
+        }
+
+        acquired
     }
 
     #[inline]
     unsafe fn exc_unlock(&self) {
+        self.policy.unlocked();
+
         if self
             .state
             .compare_exchange(Self::LOCK_BIT, 0, Ordering::Release, Ordering::Relaxed)
@@ -225,9 +344,11 @@ unsafe impl RawExclusiveLock for AdaptiveLock {
     }
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
+unsafe impl<P: SpinPolicy> crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock<P> {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
+        self.policy.unlocked();
+
         if self
             .state
             .compare_exchange(Self::LOCK_BIT, 0, Ordering::Release, Ordering::Relaxed)
@@ -245,12 +366,12 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
     }
 }
 
-impl crate::RawTimedLock for AdaptiveLock {
+impl<P> crate::RawTimedLock for AdaptiveLock<P> {
     type Instant = Instant;
     type Duration = Duration;
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
+unsafe impl<P: SpinPolicy> crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock<P> {
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.exc_try_lock() {
             true
@@ -268,4 +389,87 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
     }
 }
 
-unsafe impl crate::condvar::Parkable for AdaptiveLock {}
+unsafe impl<P: SpinPolicy> crate::condvar::Parkable for AdaptiveLock<P> {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        Some(self as *const _ as usize)
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            if state & Self::LOCK_BIT == 0 {
+                return false;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state | Self::PARK_BIT,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.state.fetch_or(Self::PARK_BIT, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: UnparkToken) -> bool {
+        token == TOKEN_HANDOFF
+    }
+}
+
+#[test]
+fn test_size() {
+    assert_eq!(core::mem::size_of::<AdaptiveLock>(), 1);
+    assert_eq!(core::mem::size_of::<Mutex<u8>>(), 2);
+}
+
+#[test]
+fn test_timeout() {
+    use std::time::{Duration, Instant};
+
+    let lock = AdaptiveLock::mutex(0);
+    let guard = lock.lock();
+
+    assert!(lock.try_lock_for(Duration::from_millis(10)).is_none());
+    assert!(lock
+        .try_lock_until(Instant::now() + Duration::from_millis(10))
+        .is_none());
+    drop(guard);
+
+    assert!(lock.try_lock_for(Duration::from_millis(10)).is_some());
+}
+
+#[test]
+fn test_send_guard() {
+    use std::sync::mpsc;
+
+    let lock = AdaptiveLock::mutex(0);
+    let (send, recv) = mpsc::channel();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            let send = send;
+            let mut guard = lock.lock();
+            *guard += 1;
+            send.send(guard).unwrap();
+        });
+
+        s.spawn(move |_| {
+            let mut guard = recv.recv().unwrap();
+            *guard += 1;
+        });
+    })
+    .unwrap();
+
+    assert_eq!(*lock.lock(), 2);
+}
@@ -0,0 +1,283 @@
+//! a CLH queue lock
+//!
+//! Each thread that wants to lock pushes a node on to the end of an implicit queue,
+//! and spins on the node of the thread in front of it. So each waiting thread spins
+//! on a different cache line, and the lock is handed over in FIFO order.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::spin_wait::SpinWait;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::boxed::Box;
+
+/// a raw mutex backed by a CLH queue lock
+pub type RawMutex = crate::mutex::raw::Mutex<ClhLock>;
+
+/// a mutex backed by a CLH queue lock
+pub type Mutex<T> = crate::mutex::Mutex<ClhLock, T>;
+
+#[repr(align(64))]
+struct Node {
+    locked: AtomicBool,
+}
+
+/// A fair (FIFO) queue lock
+///
+/// Unlike a [`TicketLock`](crate::mutex::ticket::TicketLock), where all waiting
+/// threads spin on the same counter, each waiting thread spins on the node of the thread
+/// directly in front of it in the queue. This keeps cache traffic low when the lock
+/// is heavily contended.
+///
+/// Each acquisition needs a queue node. The node of the previous owner is released by the
+/// thread that acquires the lock next (or by the owner, if no thread is waiting), and is
+/// recycled by the next acquisition. So nodes are only allocated when several threads
+/// queue up at once.
+pub struct ClhLock {
+    /// the node of the last thread in the queue, or null if the lock is unlocked
+    tail: AtomicPtr<Node>,
+    /// the node of the thread which currently holds the lock
+    ///
+    /// this is only accessed by the owner of the lock
+    owner: AtomicPtr<Node>,
+    /// a released node, which is reused by the next thread that queues up
+    free: AtomicPtr<Node>,
+}
+
+impl ClhLock {
+    /// create a new CLH lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            tail: AtomicPtr::new(ptr::null_mut()),
+            owner: AtomicPtr::new(ptr::null_mut()),
+            free: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// create a new CLH lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new CLH lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+}
+
+impl Default for ClhLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for ClhLock {
+    const INIT: Self = Self::new();
+}
+
+impl Drop for ClhLock {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+
+        if !tail.is_null() {
+            // the lock was leaked while locked, so the owner's node was never freed
+            unsafe { drop(Box::from_raw(tail)) }
+        }
+
+        let free = *self.free.get_mut();
+
+        if !free.is_null() {
+            unsafe { drop(Box::from_raw(free)) }
+        }
+    }
+}
+
+unsafe impl crate::mutex::RawMutex for ClhLock {}
+unsafe impl crate::RawLockInfo for ClhLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for ClhLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let node = self.new_node();
+
+        let pred = self.tail.swap(node, Ordering::AcqRel);
+
+        if !pred.is_null() {
+            // SAFETY: `pred` can only be freed by us, because we are the
+            // thread that swapped it out of `tail`
+            unsafe { self.lock_slow(pred) }
+        }
+
+        self.owner.store(node, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let node = self.new_node();
+
+        if self
+            .tail
+            .compare_exchange(ptr::null_mut(), node, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.owner.store(node, Ordering::Relaxed);
+            true
+        } else {
+            // SAFETY: the node was never shared with another thread
+            unsafe { self.recycle(node) }
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let node = self.owner.load(Ordering::Relaxed);
+
+        if self
+            .tail
+            .compare_exchange(node, ptr::null_mut(), Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+        {
+            // there is no successor, so no other thread can see our node
+            self.recycle(node);
+        } else {
+            // hand the lock over to our successor, who will recycle our node
+            (*node).locked.store(false, Ordering::Release);
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        let node = self.owner.load(Ordering::Relaxed);
+
+        // if another thread has queued up behind us, let it run
+        if self.tail.load(Ordering::Relaxed) != node {
+            self.bump_slow(node)
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for ClhLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // a queue lock always hands the lock over to the next waiting thread
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.exc_bump()
+    }
+}
+
+impl ClhLock {
+    #[inline]
+    fn new_node(&self) -> *mut Node {
+        let node = self.free.swap(ptr::null_mut(), Ordering::Acquire);
+
+        if node.is_null() {
+            Box::into_raw(Box::new(Node {
+                locked: AtomicBool::new(true),
+            }))
+        } else {
+            // SAFETY: we swapped the node out of `free`, so no other thread can see it
+            unsafe { (*node).locked.store(true, Ordering::Relaxed) }
+            node
+        }
+    }
+
+    /// # Safety
+    ///
+    /// no other thread may be able to see `node`
+    #[inline]
+    unsafe fn recycle(&self, node: *mut Node) {
+        let old = self.free.swap(node, Ordering::AcqRel);
+
+        if !old.is_null() {
+            drop(Box::from_raw(old));
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be the owner's node, and another thread must have queued up behind it
+    #[cold]
+    #[inline(never)]
+    unsafe fn bump_slow(&self, node: *mut Node) {
+        // queue up behind the waiting threads before handing the lock over to our successor,
+        // so no thread that arrives later can get in front of us
+        let next = self.new_node();
+        let pred = self.tail.swap(next, Ordering::AcqRel);
+
+        (*node).locked.store(false, Ordering::Release);
+
+        self.lock_slow(pred);
+        self.owner.store(next, Ordering::Relaxed);
+    }
+
+    /// # Safety
+    ///
+    /// `pred` must be a node that was swapped out of `tail`
+    #[cold]
+    #[inline(never)]
+    unsafe fn lock_slow(&self, pred: *mut Node) {
+        let mut spin = SpinWait::new();
+
+        while (*pred).locked.load(Ordering::Acquire) {
+            spin.spin();
+        }
+
+        // our predecessor has released the lock, and won't touch its node again
+        self.recycle(pred);
+    }
+}
+
+#[test]
+fn test_contention() {
+    let mutex = ClhLock::mutex(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *mutex.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*mutex.lock(), 4000);
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn test_bump() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    let mutex = ClhLock::mutex(0);
+    let lock = mutex.raw().inner();
+
+    crossbeam_utils::thread::scope(|s| {
+        let mut guard = mutex.lock();
+
+        s.spawn(|_| *mutex.lock() += 1);
+
+        // wait for the thread to queue up behind us
+        while lock.tail.load(Ordering::Relaxed) == lock.owner.load(Ordering::Relaxed) {
+            std::thread::yield_now();
+        }
+
+        // the lock is handed over to the queued thread, and back to us
+        ExclusiveGuard::bump(&mut guard);
+        assert_eq!(*guard, 1);
+    })
+    .unwrap();
+
+    assert!(lock.tail.load(Ordering::Relaxed).is_null());
+    assert!(!lock.free.load(Ordering::Relaxed).is_null());
+}
@@ -0,0 +1,242 @@
+//! a NUMA-aware cohort lock
+//!
+//! This is the C-TKT-TKT lock from
+//! [Lock Cohorting: A General Technique for Designing NUMA Locks](https://dl.acm.org/doi/10.1145/2686884).
+//!
+//! Each NUMA node gets its own local lock, and the nodes compete for a single
+//! global lock. When a thread unlocks and there is another thread waiting on the
+//! same node, the global lock is handed over to that thread without being released,
+//! so the lock (and the data it protects) stays on one node for a while. To prevent
+//! other nodes from starving, the global lock is released after a bounded number of
+//! handoffs.
+
+use crate::combinators::CachePadded;
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::mutex::ticket::TicketLock;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// the maximum number of times the global lock is handed over within a node before it is released
+const MAX_HANDOFFS: usize = 64;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NODE: CachePadded<Node> = CachePadded(Node::new());
+
+/// a raw mutex backed by a cohort lock
+pub type RawMutex<const N: usize = 4> = crate::mutex::raw::Mutex<CohortLock<N>>;
+
+/// a mutex backed by a cohort lock
+pub type Mutex<T, const N: usize = 4> = crate::mutex::Mutex<CohortLock<N>, T>;
+
+/// A NUMA-aware cohort lock with `N` local locks
+///
+/// Threads are assigned to local locks by the NUMA node that they are currently running on,
+/// modulo `N`. The current node is only known on linux when the `libc` feature is enabled,
+/// otherwise every thread uses the first local lock, and this behaves like a
+/// [`TicketLock`].
+///
+/// Both the local locks and the global lock are ticket locks, so waiting threads never park,
+/// and this lock is best suited to short critical sections on large multi-socket machines.
+pub struct CohortLock<const N: usize = 4> {
+    global: TicketLock,
+    // the node of the thread that currently holds the lock
+    owner: AtomicUsize,
+    nodes: [CachePadded<Node>; N],
+}
+
+struct Node {
+    lock: TicketLock,
+    // these are only accessed while `lock` is held
+    owns_global: AtomicBool,
+    handoffs: AtomicUsize,
+}
+
+impl Node {
+    const fn new() -> Self {
+        Self {
+            lock: TicketLock::new(),
+            owns_global: AtomicBool::new(false),
+            handoffs: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Returns the NUMA node that the current thread is running on
+#[cfg(all(feature = "libc", target_os = "linux"))]
+fn current_node() -> usize {
+    let mut cpu: libc::c_uint = 0;
+    let mut node: libc::c_uint = 0;
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_getcpu,
+            &mut cpu as *mut libc::c_uint,
+            &mut node as *mut libc::c_uint,
+            core::ptr::null_mut::<libc::c_void>(),
+        )
+    };
+
+    if result == 0 {
+        node as usize
+    } else {
+        0
+    }
+}
+
+/// Returns the NUMA node that the current thread is running on
+#[cfg(not(all(feature = "libc", target_os = "linux")))]
+fn current_node() -> usize {
+    0
+}
+
+impl<const N: usize> CohortLock<N> {
+    /// create a new cohort lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            global: TicketLock::new(),
+            owner: AtomicUsize::new(0),
+            nodes: [NODE; N],
+        }
+    }
+
+    /// create a new cohort lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex<N> {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new cohort lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T, N> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    #[inline]
+    fn current_node(&self) -> usize {
+        current_node() % N
+    }
+
+    /// release the lock, and only hand it over within the node if `fair` is false
+    #[inline]
+    unsafe fn unlock(&self, fair: bool) {
+        let node = &self.nodes[self.owner.load(Ordering::Relaxed)].0;
+        let handoffs = node.handoffs.load(Ordering::Relaxed);
+
+        if !fair && handoffs < MAX_HANDOFFS && node.lock.has_waiters() {
+            // keep the global lock, and pass it to the next thread on this node
+            node.handoffs.store(handoffs + 1, Ordering::Relaxed);
+            node.owns_global.store(true, Ordering::Relaxed);
+        } else {
+            node.handoffs.store(0, Ordering::Relaxed);
+            node.owns_global.store(false, Ordering::Relaxed);
+            self.global.exc_unlock();
+        }
+
+        node.lock.exc_unlock();
+    }
+}
+
+impl<const N: usize> Default for CohortLock<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> crate::Init for CohortLock<N> {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl<const N: usize> crate::mutex::RawMutex for CohortLock<N> {}
+unsafe impl<const N: usize> crate::RawLockInfo for CohortLock<N> {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl<const N: usize> RawExclusiveLock for CohortLock<N> {
+    #[inline]
+    fn exc_lock(&self) {
+        let id = self.current_node();
+        let node = &self.nodes[id].0;
+
+        node.lock.exc_lock();
+
+        if !node.owns_global.load(Ordering::Relaxed) {
+            self.global.exc_lock();
+        }
+
+        self.owner.store(id, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let id = self.current_node();
+        let node = &self.nodes[id].0;
+
+        if !node.lock.exc_try_lock() {
+            return false;
+        }
+
+        if !node.owns_global.load(Ordering::Relaxed) && !self.global.exc_try_lock() {
+            unsafe { node.lock.exc_unlock() }
+            return false;
+        }
+
+        self.owner.store(id, Ordering::Relaxed);
+        true
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.unlock(false)
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        let node = &self.nodes[self.owner.load(Ordering::Relaxed)].0;
+
+        // if another thread is waiting, let it run
+        if node.lock.has_waiters() || self.global.has_waiters() {
+            self.exc_unlock();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl<const N: usize> crate::exclusive_lock::RawExclusiveLockFair for CohortLock<N> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // always release the global lock, so that other nodes get a turn
+        self.unlock(true)
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        let node = &self.nodes[self.owner.load(Ordering::Relaxed)].0;
+
+        if node.lock.has_waiters() || self.global.has_waiters() {
+            self.exc_unlock_fair();
+            self.exc_lock();
+        }
+    }
+}
+
+#[test]
+fn test_cohort() {
+    let lock = CohortLock::<2>::mutex(0);
+
+    let guard = lock.lock();
+    assert!(lock.try_lock().is_none());
+    drop(guard);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *lock.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*lock.lock(), 4000);
+}
@@ -52,6 +52,20 @@ unsafe impl RawLockInfo for DefaultLock {
     type ShareGuardTraits = <Lock as RawLockInfo>::ShareGuardTraits;
 }
 
+impl crate::RawLockQuery for DefaultLock {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.0.is_locked()
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.0.is_locked_exclusive()
+    }
+}
+
+unsafe impl crate::combinators::Elidable for DefaultLock {}
+
 unsafe impl RawExclusiveLock for DefaultLock {
     #[inline]
     fn exc_lock(&self) {
@@ -105,4 +119,24 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for DefaultLock {
 }
 
 #[cfg(feature = "parking_lot_core")]
-unsafe impl crate::condvar::Parkable for DefaultLock {}
+unsafe impl crate::condvar::Parkable for DefaultLock {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.0.park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.0.mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.0.mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.0.is_handoff(token)
+    }
+}
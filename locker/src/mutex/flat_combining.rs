@@ -0,0 +1,263 @@
+//! a flat combining mutex
+//!
+//! Instead of waiting for the lock, contending threads publish their critical
+//! section in a slot, and whichever thread holds the lock (the combiner) runs all
+//! published critical sections on their behalf. This keeps the protected value
+//! in the combiner's cache, which dramatically reduces cache traffic for
+//! small critical sections.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::relax::{DefaultRelax, Relax};
+
+// the number of critical sections that can be published at the same time
+const SLOTS: usize = 32;
+
+const FREE: u8 = 0;
+const CLAIMED: u8 = 1;
+const PENDING: u8 = 2;
+const DONE: u8 = 3;
+
+/// A flat combining mutex
+///
+/// Critical sections are passed to [`apply`](FcMutex::apply), and may be run on
+/// any thread which is calling `apply` at the same time, so they must be `Send`.
+/// If a critical section panics, then the panic is propagated to the thread which
+/// called `apply`.
+///
+/// Calling `apply` from inside a critical section will deadlock.
+pub struct FcMutex<T: ?Sized> {
+    lock: AtomicBool,
+    slots: [Slot<T>; SLOTS],
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for FcMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for FcMutex<T> {}
+
+#[repr(align(64))]
+struct Slot<T: ?Sized> {
+    state: AtomicU8,
+    task: UnsafeCell<Option<Task<T>>>,
+}
+
+// a type erased critical section, which lives on the stack of the thread that published it
+struct Task<T: ?Sized> {
+    data: *mut (),
+    call: unsafe fn(*mut (), &mut T),
+}
+
+impl<T: ?Sized> Clone for Task<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Task<T> {}
+
+struct Request<F, R> {
+    func: Option<F>,
+    result: Option<std::thread::Result<R>>,
+}
+
+/// # Safety
+///
+/// `data` must point to a `Request<F, R>` that hasn't been called yet
+unsafe fn call<T: ?Sized, F: FnOnce(&mut T) -> R, R>(data: *mut (), value: &mut T) {
+    let request = &mut *(data as *mut Request<F, R>);
+    let func = request.func.take().unwrap();
+    request.result = Some(panic::catch_unwind(AssertUnwindSafe(|| func(value))));
+}
+
+impl<T: ?Sized> Slot<T> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NEW: Self = Self {
+        state: AtomicU8::new(FREE),
+        task: UnsafeCell::new(None),
+    };
+}
+
+fn slot_hint() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    std::thread_local! {
+        static HINT: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    HINT.with(|&hint| hint)
+}
+
+impl<T: Default> Default for FcMutex<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> FcMutex<T> {
+    /// Creates a new flat combining mutex in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            lock: AtomicBool::new(false),
+            slots: [Slot::NEW; SLOTS],
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> FcMutex<T> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `FcMutex` mutably, no actual locking needs to take place
+    /// ---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Runs the critical section `func` with exclusive access to the value,
+    /// blocking the current thread until it has been run.
+    ///
+    /// `func` may be run on another thread that is currently combining.
+    pub fn apply<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send,
+        R: Send,
+    {
+        let mut request = Request::<F, R> {
+            func: Some(func),
+            result: None,
+        };
+
+        let task = Task {
+            data: &mut request as *mut Request<F, R> as *mut (),
+            call: call::<T, F, R>,
+        };
+
+        if self.try_lock() {
+            // SAFETY: we hold the lock, and the request hasn't been called yet
+            unsafe {
+                (task.call)(task.data, &mut *self.value.get());
+                self.combine();
+            }
+        } else {
+            self.apply_slow(task);
+        }
+
+        match request.result.unwrap() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        !self.lock.load(Ordering::Relaxed)
+            && self
+                .lock
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+    }
+
+    /// Runs all published critical sections, and then unlocks the mutex
+    ///
+    /// # Safety
+    ///
+    /// the lock must be held
+    unsafe fn combine(&self) {
+        let value = &mut *self.value.get();
+
+        for slot in self.slots.iter() {
+            if slot.state.load(Ordering::Acquire) == PENDING {
+                let task = (*slot.task.get()).take().unwrap();
+                (task.call)(task.data, value);
+                slot.state.store(DONE, Ordering::Release);
+            }
+        }
+
+        self.lock.store(false, Ordering::Release);
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn apply_slow(&self, task: Task<T>) {
+        let mut relax = DefaultRelax::default();
+
+        let slot = match self.publish(task) {
+            Some(slot) => slot,
+            None => {
+                // all slots are taken, so wait for the lock instead
+                while !self.try_lock() {
+                    relax.relax();
+                }
+
+                // SAFETY: we hold the lock, and the request hasn't been called yet
+                unsafe {
+                    (task.call)(task.data, &mut *self.value.get());
+                    self.combine();
+                }
+
+                return;
+            }
+        };
+
+        loop {
+            if slot.state.load(Ordering::Acquire) == DONE {
+                slot.state.store(FREE, Ordering::Relaxed);
+                return;
+            }
+
+            if self.try_lock() {
+                // SAFETY: we hold the lock
+                unsafe { self.combine() }
+            } else {
+                relax.relax();
+            }
+        }
+    }
+
+    fn publish(&self, task: Task<T>) -> Option<&Slot<T>> {
+        let hint = slot_hint();
+
+        (0..SLOTS)
+            .map(|i| &self.slots[(hint + i) % SLOTS])
+            .find(|slot| {
+                slot.state.load(Ordering::Relaxed) == FREE
+                    && slot
+                        .state
+                        .compare_exchange(FREE, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+            })
+            .inspect(|slot| {
+                unsafe { *slot.task.get() = Some(task) }
+                slot.state.store(PENDING, Ordering::Release);
+            })
+    }
+}
+
+#[test]
+fn test_contention() {
+    let mutex = FcMutex::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    mutex.apply(|x| *x += 1);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(mutex.into_inner(), 8000);
+}
@@ -0,0 +1,275 @@
+//! a futex based lock
+//!
+//! This lock blocks using the linux `futex(2)` syscall directly, so it doesn't
+//! need `parking_lot_core` to park threads, and only takes up 4 bytes.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// a raw mutex backed by a futex
+pub type RawMutex = crate::mutex::raw::Mutex<FutexLock>;
+
+/// a mutex backed by a futex
+pub type Mutex<T> = crate::mutex::Mutex<FutexLock, T>;
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+// locked, and there may be threads waiting on the futex
+const CONTENDED: u32 = 2;
+// unlocked, but reserved for a thread that is already waiting
+const HANDOFF: u32 = 3;
+
+/// A lock which parks threads on a linux futex
+pub struct FutexLock {
+    state: AtomicU32,
+}
+
+impl FutexLock {
+    /// create a new futex lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+        }
+    }
+
+    /// create a new futex lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new futex lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+}
+
+impl Default for FutexLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for FutexLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for FutexLock {}
+unsafe impl crate::RawLockInfo for FutexLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for FutexLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if self
+            .state
+            .compare_exchange_weak(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            self.lock_slow();
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            self.wake_one();
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        if self.state.load(Ordering::Relaxed) == CONTENDED {
+            self.exc_unlock();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for FutexLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        if self
+            .state
+            .compare_exchange(LOCKED, UNLOCKED, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            self.unlock_fair_slow();
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        if self.state.load(Ordering::Relaxed) == CONTENDED {
+            self.unlock_fair_slow();
+            self.exc_lock();
+        }
+    }
+}
+
+impl FutexLock {
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self) {
+        let mut spin = SpinWait::new();
+        let mut has_waited = false;
+
+        loop {
+            let mut state = self.state.load(Ordering::Relaxed);
+
+            // spin for a bit while the lock is held, but no threads are waiting
+            while state == LOCKED && spin.spin() {
+                state = self.state.load(Ordering::Relaxed);
+            }
+
+            match state {
+                UNLOCKED => {
+                    // once we have waited, we don't know if there are other threads
+                    // still waiting, so we must assume that there are
+                    let new_state = if has_waited { CONTENDED } else { LOCKED };
+
+                    if self
+                        .state
+                        .compare_exchange_weak(
+                            UNLOCKED,
+                            new_state,
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+                    {
+                        return;
+                    }
+
+                    continue;
+                }
+                // only threads that have already waited may take a handed off lock
+                // otherwise new threads could barge in ahead of the waiting threads
+                HANDOFF if has_waited => {
+                    if self
+                        .state
+                        .compare_exchange_weak(
+                            HANDOFF,
+                            CONTENDED,
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+                    {
+                        return;
+                    }
+
+                    continue;
+                }
+                LOCKED => {
+                    if let Err(x) = self.state.compare_exchange_weak(
+                        LOCKED,
+                        CONTENDED,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        if x == UNLOCKED {
+                            continue;
+                        }
+
+                        state = x;
+                    } else {
+                        state = CONTENDED;
+                    }
+                }
+                _ => (),
+            }
+
+            self.wait(state);
+            has_waited = true;
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn unlock_fair_slow(&self) {
+        // keep the lock reserved, so that only a waiting thread can acquire it
+        self.state.store(HANDOFF, Ordering::Release);
+
+        if !self.wake_one()
+            && self
+                .state
+                .compare_exchange(HANDOFF, UNLOCKED, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            // there was no thread to hand off the lock to, so fully unlock the lock
+            // and wake any thread which started waiting in the meantime
+            self.wake_one();
+        }
+    }
+
+    /// blocks until woken up, if the state is still `expected`
+    fn wait(&self, expected: u32) {
+        // SAFETY: the futex is a valid `u32` for the duration of the call
+        // spurious wake-ups and interrupts are handled by the caller re-checking the state
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                &self.state as *const AtomicU32,
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                core::ptr::null::<libc::timespec>(),
+            );
+        }
+    }
+
+    /// wakes a single thread, returns true if a thread was woken up
+    fn wake_one(&self) -> bool {
+        // SAFETY: the futex is a valid `u32` for the duration of the call
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                &self.state as *const AtomicU32,
+                libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+                1,
+            ) > 0
+        }
+    }
+}
+
+#[test]
+fn test_contention() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    let mutex = std::sync::Arc::new(FutexLock::mutex(0));
+
+    let threads = (0..4)
+        .map(|i| {
+            let mutex = mutex.clone();
+            std::thread::spawn(move || {
+                for _ in 0..1000 {
+                    let mut guard = mutex.lock();
+                    *guard += 1;
+
+                    if i % 2 == 0 {
+                        ExclusiveGuard::bump_fair(&mut guard);
+                    } else {
+                        ExclusiveGuard::unlock_fair(guard);
+                    }
+                }
+            })
+        })
+        .collect::<std::vec::Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(*mutex.lock(), 4000);
+}
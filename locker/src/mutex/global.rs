@@ -1,9 +1,23 @@
 //! A global lock set that uses the [default mutex lock](crate::mutex::default)
+//!
+//! By default, the lock is picked by hashing the address of the mutex, so unrelated
+//! mutexes may contend. If `std` is enabled, then [`by_name`] and [`by_key`] can be used
+//! to pick which mutexes share a lock.
+//!
+//! The builtin table has 61 locks, if that is too small, then you can create a
+//! larger [`LockTable`], and pick how addresses are assigned to locks with an [`AddrHash`].
+
+use core::marker::PhantomData;
 
 use super::default::DefaultLock;
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair};
 use crate::RawLockInfo;
 
+#[cfg(feature = "std")]
+mod named;
+#[cfg(feature = "std")]
+pub use named::{by_key, by_name, NamedLock, NamedMutex, NamedRawMutex};
+
 /// A global lock set that uses the [default mutex lock](crate::mutex::default)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct GlobalLock;
@@ -45,13 +59,13 @@ impl GlobalLock {
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn addr(&self) -> usize {
-        (self as *const _ as usize) % GLOBAL.len()
+        Modulo::shard(self as *const _ as usize, 61)
     }
 
     #[inline(always)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     fn get(&self) -> &'static DefaultLock {
-        &GLOBAL[self.addr()]
+        GLOBAL.get(self as *const _ as usize)
     }
 
     /// Checks if two global locks will contend
@@ -72,28 +86,209 @@ impl GlobalLock {
 // this will reduce contention between unrelated locks
 // because unrealated locks will be unlikely to pick up the same lock,
 // even they are contigious in memory
-#[rustfmt::skip]
-static GLOBAL: [DefaultLock; 61] = [
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT, crate::Init::INIT, crate::Init::INIT, crate::Init::INIT,
-    crate::Init::INIT,
-];
+static GLOBAL: LockTable<61> = LockTable::new();
+
+/// A hash function which picks a shard of a [`LockTable`] from the address of a lock
+pub trait AddrHash {
+    /// Pick a shard from the address of the lock, this must return a value less than `shards`
+    fn shard(addr: usize, shards: usize) -> usize;
+}
+
+/// Picks a shard by taking the address modulo the number of shards
+///
+/// This works best if the number of shards is prime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Modulo;
+
+impl AddrHash for Modulo {
+    #[inline(always)]
+    fn shard(addr: usize, shards: usize) -> usize {
+        addr % shards
+    }
+}
+
+/// Picks a shard with fibonacci hashing
+///
+/// This spreads out addresses which are multiples of a large power of two
+/// (like the addresses of cache aligned values) better than [`Modulo`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fibonacci;
+
+impl AddrHash for Fibonacci {
+    #[inline(always)]
+    fn shard(addr: usize, shards: usize) -> usize {
+        let hash = (addr as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        ((u128::from(hash) * shards as u128) >> 64) as usize
+    }
+}
+
+/// A table of `N` locks, which mutexes are assigned to based on their address
+///
+/// [`GlobalLock`] uses a builtin table of 61 locks, if that's too small then you
+/// can put a larger table in a `static`, and create mutexes from it with [`LockTable::mutex`]
+pub struct LockTable<const N: usize, H = Modulo> {
+    locks: [DefaultLock; N],
+    hash: PhantomData<fn() -> H>,
+}
+
+/// a raw mutex which uses a lock from a [`LockTable`]
+pub type TableRawMutex<const N: usize, H = Modulo> = crate::mutex::raw::Mutex<TableLock<N, H>>;
+/// a mutex which uses a lock from a [`LockTable`]
+pub type TableMutex<T, const N: usize, H = Modulo> = crate::mutex::Mutex<TableLock<N, H>, T>;
+
+impl<const N: usize, H: AddrHash> LockTable<N, H> {
+    /// Create a new lock table
+    pub const fn new() -> Self {
+        assert!(N != 0, "A lock table must have at least one lock");
+
+        Self {
+            locks: [<DefaultLock as crate::Init>::INIT; N],
+            hash: PhantomData,
+        }
+    }
+
+    /// Create a new raw mutex which uses a lock from this table
+    pub const fn raw_mutex(&'static self) -> TableRawMutex<N, H> {
+        unsafe { TableRawMutex::from_raw(TableLock { table: self }) }
+    }
+
+    /// Create a new mutex which uses a lock from this table
+    pub const fn mutex<T>(&'static self, value: T) -> TableMutex<T, N, H> {
+        TableMutex::from_raw_parts(self.raw_mutex(), value)
+    }
+
+    #[inline(always)]
+    fn get(&self, addr: usize) -> &DefaultLock {
+        &self.locks[H::shard(addr, N)]
+    }
+}
+
+impl<const N: usize, H: AddrHash> Default for LockTable<N, H> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A lock which uses a lock from a [`LockTable`]
+pub struct TableLock<const N: usize, H: 'static = Modulo> {
+    table: &'static LockTable<N, H>,
+}
+
+impl<const N: usize, H: AddrHash> TableLock<N, H> {
+    #[inline(always)]
+    fn addr(&self) -> usize {
+        H::shard(self as *const _ as usize, N)
+    }
+
+    #[inline(always)]
+    fn get(&self) -> &'static DefaultLock {
+        self.table.get(self as *const _ as usize)
+    }
+
+    /// Checks if two table locks will contend
+    #[inline]
+    pub fn will_contend(&self, other: &Self) -> bool {
+        core::ptr::eq(self.table, other.table) && self.addr() == other.addr()
+    }
+
+    /// Checks if two table mutexes will contend
+    #[inline]
+    pub fn will_mutex_contend<T: ?Sized, U: ?Sized>(
+        a: &TableMutex<T, N, H>,
+        b: &TableMutex<U, N, H>,
+    ) -> bool {
+        a.raw().inner().will_contend(b.raw().inner())
+    }
+}
+
+unsafe impl<const N: usize, H: AddrHash> crate::mutex::RawMutex for TableLock<N, H> {}
+unsafe impl<const N: usize, H: AddrHash> RawLockInfo for TableLock<N, H> {
+    type ExclusiveGuardTraits = <DefaultLock as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <DefaultLock as RawLockInfo>::ShareGuardTraits;
+}
+
+unsafe impl<const N: usize, H: AddrHash> RawExclusiveLock for TableLock<N, H> {
+    #[inline]
+    fn exc_lock(&self) {
+        self.get().exc_lock()
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.get().exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.get().exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        self.get().exc_bump()
+    }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.get().exc_lock_addr()
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl<const N: usize, H: AddrHash> RawExclusiveLockFair for TableLock<N, H> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.get().exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.get().exc_bump_fair()
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+impl<const N: usize, H: AddrHash> crate::RawTimedLock for TableLock<N, H> {
+    type Instant = std::time::Instant;
+    type Duration = std::time::Duration;
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl<const N: usize, H: AddrHash> crate::exclusive_lock::RawExclusiveLockTimed
+    for TableLock<N, H>
+{
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.get().exc_try_lock_until(instant)
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.get().exc_try_lock_for(duration)
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl<const N: usize, H: AddrHash> crate::condvar::Parkable for TableLock<N, H> {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.get().park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.get().mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.get().mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.get().is_handoff(token)
+    }
+}
 
 impl crate::Init for GlobalLock {
     const INIT: Self = Self;
@@ -125,6 +320,11 @@ unsafe impl RawExclusiveLock for GlobalLock {
     unsafe fn exc_bump(&self) {
         self.get().exc_bump()
     }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.get().exc_lock_addr()
+    }
 }
 
 #[cfg(feature = "parking_lot_core")]
@@ -157,6 +357,29 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for GlobalLock {
     }
 }
 
+#[cfg(feature = "parking_lot_core")]
+unsafe impl crate::condvar::Parkable for GlobalLock {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.get().park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.get().mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.get().mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.get().is_handoff(token)
+    }
+}
+
 #[test]
 fn test_contention() {
     let mtx = [GlobalLock::mutex([0; 61]), GlobalLock::mutex([0; 61])];
@@ -176,3 +399,16 @@ fn test_contention() {
     let _lock = a.lock();
     let _lock = b.lock();
 }
+
+#[test]
+fn test_table() {
+    static TABLE: LockTable<256, Fibonacci> = LockTable::new();
+
+    let mtx = [TABLE.mutex(0), TABLE.mutex(0)];
+
+    let [ref a, ref b] = mtx;
+    let contend = TableLock::will_mutex_contend(a, b);
+
+    let _lock = a.lock();
+    assert_eq!(b.try_lock().is_none(), contend);
+}
@@ -0,0 +1,201 @@
+use core::any::Any;
+use core::hash::{Hash, Hasher};
+use std::boxed::Box;
+use std::collections::hash_map::DefaultHasher;
+use std::vec::Vec;
+
+use crate::exclusive_lock::RawExclusiveLock;
+#[cfg(feature = "parking_lot_core")]
+use crate::exclusive_lock::RawExclusiveLockFair;
+use crate::mutex::default::DefaultLock;
+use crate::RawLockInfo;
+
+/// a raw mutex which uses a named global lock
+pub type NamedRawMutex = crate::mutex::raw::Mutex<NamedLock>;
+/// a mutex which uses a named global lock
+pub type NamedMutex<T> = crate::mutex::Mutex<NamedLock, T>;
+
+struct Entry {
+    hash: u64,
+    key: Box<dyn Any + Send + Sync>,
+    lock: &'static DefaultLock,
+}
+
+static REGISTRY: crate::mutex::default::Mutex<Vec<Entry>> =
+    crate::mutex::default::DefaultLock::mutex(Vec::new());
+
+/// Get the named global lock for the given name
+///
+/// All calls with the same name return the same lock, and calls with different names
+/// return different locks, so mutexes created from different names never contend.
+#[inline]
+pub fn by_name(name: &'static str) -> NamedLock {
+    by_key(name)
+}
+
+/// Get the named global lock for the given key
+///
+/// All calls with equal keys (of the same type) return the same lock, and calls with
+/// different keys return different locks, so mutexes created from different keys never contend.
+///
+/// Locks are never freed, so this should only be used with a bounded set of keys.
+pub fn by_key<K: Hash + Eq + Send + Sync + 'static>(key: K) -> NamedLock {
+    let mut hasher = DefaultHasher::new();
+    core::any::TypeId::of::<K>().hash(&mut hasher);
+    key.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let mut registry = REGISTRY.lock();
+
+    let found = registry
+        .iter()
+        .find(|entry| entry.hash == hash && entry.key.downcast_ref::<K>() == Some(&key));
+
+    let lock = match found {
+        Some(entry) => entry.lock,
+        None => {
+            let lock: &'static DefaultLock = Box::leak(Box::new(DefaultLock::new()));
+            registry.push(Entry {
+                hash,
+                key: Box::new(key),
+                lock,
+            });
+            lock
+        }
+    };
+
+    NamedLock { lock }
+}
+
+/// A lock from the named global lock registry, see [`by_name`] and [`by_key`]
+#[derive(Clone, Copy)]
+pub struct NamedLock {
+    lock: &'static DefaultLock,
+}
+
+impl NamedLock {
+    /// Create a new raw mutex which uses this named lock
+    pub const fn raw_mutex(self) -> NamedRawMutex {
+        unsafe { NamedRawMutex::from_raw(self) }
+    }
+
+    /// Create a new mutex which uses this named lock
+    pub const fn mutex<T>(self, value: T) -> NamedMutex<T> {
+        NamedMutex::from_raw_parts(self.raw_mutex(), value)
+    }
+
+    /// Checks if two named locks will contend
+    #[inline]
+    pub fn will_contend(&self, other: &Self) -> bool {
+        core::ptr::eq(self.lock, other.lock)
+    }
+
+    /// Checks if two named mutexes will contend
+    #[inline]
+    pub fn will_mutex_contend<T: ?Sized, U: ?Sized>(a: &NamedMutex<T>, b: &NamedMutex<U>) -> bool {
+        a.raw().inner().will_contend(b.raw().inner())
+    }
+}
+
+unsafe impl crate::mutex::RawMutex for NamedLock {}
+unsafe impl RawLockInfo for NamedLock {
+    type ExclusiveGuardTraits = <DefaultLock as RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <DefaultLock as RawLockInfo>::ShareGuardTraits;
+}
+
+unsafe impl RawExclusiveLock for NamedLock {
+    #[inline]
+    fn exc_lock(&self) {
+        self.lock.exc_lock()
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.lock.exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.lock.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        self.lock.exc_bump()
+    }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.lock.exc_lock_addr()
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl RawExclusiveLockFair for NamedLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.lock.exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.lock.exc_bump_fair()
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+impl crate::RawTimedLock for NamedLock {
+    type Instant = std::time::Instant;
+    type Duration = std::time::Duration;
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for NamedLock {
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.lock.exc_try_lock_until(instant)
+    }
+
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.lock.exc_try_lock_for(duration)
+    }
+}
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl crate::condvar::Parkable for NamedLock {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.lock.park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.lock.mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.lock.mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.lock.is_handoff(token)
+    }
+}
+
+#[test]
+fn test_named() {
+    let a = by_name("test_named::a").mutex(0);
+    let b = by_name("test_named::a").mutex(0);
+    let c = by_name("test_named::c").mutex(0);
+    let d = by_key(("test_named", 0)).mutex(0);
+
+    assert!(NamedLock::will_mutex_contend(&a, &b));
+    assert!(!NamedLock::will_mutex_contend(&a, &c));
+    assert!(!NamedLock::will_mutex_contend(&a, &d));
+
+    let _lock = a.lock();
+    assert!(b.try_lock().is_none());
+    assert!(c.try_lock().is_some());
+    assert!(d.try_lock().is_some());
+}
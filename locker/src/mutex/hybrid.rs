@@ -0,0 +1,70 @@
+//! a hybrid spin-then-park raw mutex
+//!
+//! This is the [adaptive lock](crate::mutex::adaptive), but instead of using an exponential
+//! back-off and yielding to the OS before parking, it spins for a fixed number of iterations,
+//! which can be tuned for the critical sections that it protects.
+//!
+//! Small values of `SPIN` are better for long critical sections, where spinning
+//! is unlikely to succeed, and large values are better for short critical sections,
+//! where the lock is likely to be released soon.
+
+use crate::mutex::adaptive::{AdaptiveLock, SpinPolicy};
+
+/// The default number of times a [`HybridLock`] spins before parking
+pub const DEFAULT_SPIN: usize = 100;
+
+/// A mutex lock backed by `parking_lot_core`, which spins up to `SPIN` times before parking
+pub type HybridLock<const SPIN: usize = DEFAULT_SPIN> = AdaptiveLock<BoundedSpin<SPIN>>;
+/// A mutex lock backed by `parking_lot_core`, which spins up to `SPIN` times before parking
+pub type RawLock<const SPIN: usize = DEFAULT_SPIN> = HybridLock<SPIN>;
+/// a hybrid raw mutex
+pub type RawMutex<const SPIN: usize = DEFAULT_SPIN> = crate::mutex::raw::Mutex<HybridLock<SPIN>>;
+/// a hybrid mutex
+pub type Mutex<T, const SPIN: usize = DEFAULT_SPIN> = crate::mutex::Mutex<HybridLock<SPIN>, T>;
+
+/// A [`SpinPolicy`] which spins up to `SPIN` times before parking
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BoundedSpin<const SPIN: usize>;
+
+impl<const SPIN: usize> crate::Init for BoundedSpin<SPIN> {
+    const INIT: Self = Self;
+}
+
+impl<const SPIN: usize> SpinPolicy for BoundedSpin<SPIN> {
+    type Spinner = usize;
+
+    #[inline]
+    fn spinner(&self) -> Self::Spinner {
+        0
+    }
+
+    #[inline]
+    fn spin(&self, spinner: &mut Self::Spinner) -> bool {
+        if *spinner < SPIN {
+            *spinner += 1;
+            core::hint::spin_loop();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[test]
+fn test_hybrid() {
+    let mutex = Mutex::<_, 10>::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *mutex.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*mutex.lock(), 4000);
+    assert_eq!(core::mem::size_of::<RawLock<0>>(), 1);
+}
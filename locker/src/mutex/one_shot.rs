@@ -0,0 +1,98 @@
+//! a lock which can only be acquired once
+
+use crate::exclusive_lock::RawExclusiveLock;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// a raw mutex which can only be locked once
+pub type RawMutex = crate::mutex::raw::Mutex<OneShotLock>;
+
+/// a mutex which can only be locked once
+///
+/// This is useful to claim ownership of a static resource, only the first call to
+/// [`try_lock`](crate::mutex::Mutex::try_lock) will succeed.
+pub type Mutex<T> = crate::mutex::Mutex<OneShotLock, T>;
+
+/// A lock which can be acquired exactly once, and is never released
+///
+/// Unlocking is a no-op, so after the first guard is dropped, the lock stays locked forever.
+/// Trying to block on the lock after it was claimed will panic, because it can never be acquired.
+pub struct OneShotLock {
+    claimed: AtomicBool,
+}
+
+impl OneShotLock {
+    /// create a new unclaimed one shot lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            claimed: AtomicBool::new(false),
+        }
+    }
+
+    /// create a new one shot lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new one shot lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// Returns true if the lock has been claimed
+    #[inline]
+    pub fn is_claimed(&self) -> bool {
+        self.claimed.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for OneShotLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for OneShotLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for OneShotLock {}
+unsafe impl crate::RawLockInfo for OneShotLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for OneShotLock {
+    #[inline]
+    fn exc_lock(&self) {
+        assert!(self.exc_try_lock(), "Tried to claim a one shot lock twice");
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        !self.claimed.load(Ordering::Relaxed) && !self.claimed.swap(true, Ordering::Acquire)
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // the lock is never released
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        // the lock is never released, so there is no one to let in
+    }
+}
+
+#[test]
+fn test_claim() {
+    static RESOURCE: Mutex<u32> = OneShotLock::mutex(0);
+
+    let mut guard = RESOURCE.try_lock().unwrap();
+    *guard += 1;
+    drop(guard);
+
+    assert!(RESOURCE.raw().inner().is_claimed());
+    assert!(RESOURCE.try_lock().is_none());
+}
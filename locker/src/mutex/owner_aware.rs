@@ -0,0 +1,187 @@
+//! an adaptive raw mutex that only spins while the lock owner is running
+//!
+//! Spinning on a lock is only useful if the owner of the lock is running, and may release
+//! it soon. So the owner of this lock publishes the cpu that it is running on, and waiting
+//! threads stop spinning and park if the owner can't be running. That is if the owner is
+//! parked (for example, while waiting for another owner aware lock), or if the waiting thread
+//! is running on the owner's cpu, so the owner must have been descheduled.
+//!
+//! The owner is cleared when the lock is released, so waiting threads never act on a stale owner.
+
+use crate::mutex::adaptive::{AdaptiveLock, SpinPolicy};
+use parking_lot_core::SpinWait;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// An adaptive mutex lock backed by `parking_lot_core`, which doesn't spin if the lock owner
+/// can't be running
+pub type OwnerAwareLock = AdaptiveLock<OwnerAware>;
+/// an owner aware raw mutex
+pub type RawMutex = crate::mutex::raw::Mutex<OwnerAwareLock>;
+/// an owner aware mutex
+pub type Mutex<T> = crate::mutex::Mutex<OwnerAwareLock, T>;
+
+// the number of slots in the table of parked threads
+const SLOTS: u32 = 64;
+const SLOT_MASK: u32 = 0xff;
+const CPU_SHIFT: u32 = 8;
+
+// used when the cpu of the owner is not known
+const UNKNOWN_CPU: u32 = u32::MAX >> CPU_SHIFT;
+// used when the lock has no owner
+const NO_OWNER: u32 = u32::MAX;
+
+// the number of parked threads in each slot, threads which share a slot
+// will see each other as parked, so they only park earlier than they need to
+#[allow(clippy::declare_interior_mutable_const)]
+const UNPARKED: AtomicU32 = AtomicU32::new(0);
+static PARKED: [AtomicU32; SLOTS as usize] = [UNPARKED; SLOTS as usize];
+
+std::thread_local! {
+    // the slot of the current thread in the table of parked threads
+    static SLOT: u32 = {
+        static NEXT_SLOT: AtomicU32 = AtomicU32::new(0);
+        NEXT_SLOT.fetch_add(1, Ordering::Relaxed) % SLOTS
+    };
+}
+
+fn current_slot() -> Option<u32> {
+    // the slot may already be destroyed if a lock is used in a thread local destructor
+    SLOT.try_with(|&slot| slot).ok()
+}
+
+fn current_cpu() -> u32 {
+    let cpu = unsafe { libc::sched_getcpu() };
+
+    if cpu < 0 {
+        UNKNOWN_CPU
+    } else {
+        (cpu as u32).min(UNKNOWN_CPU)
+    }
+}
+
+/// A [`SpinPolicy`] which spins only while the owner of the lock is running
+///
+/// This uses `sched_getcpu` to find the cpu that the owner is running on when it acquires
+/// the lock. The owner may migrate to another cpu while holding the lock, so this is only
+/// a heuristic, and a waiting thread will still park after spinning for a while.
+pub struct OwnerAware {
+    owner: AtomicU32,
+}
+
+impl OwnerAware {
+    /// Create a new owner aware spin policy, without an owner
+    pub const fn new() -> Self {
+        Self {
+            owner: AtomicU32::new(NO_OWNER),
+        }
+    }
+
+    /// returns false if the owner of the lock can't be running right now
+    #[inline]
+    pub fn is_owner_running(&self) -> bool {
+        let owner = self.owner.load(Ordering::Relaxed);
+
+        // the lock was just released, or the owner isn't known
+        if owner == NO_OWNER {
+            return true;
+        }
+
+        if PARKED[(owner & SLOT_MASK) as usize].load(Ordering::Relaxed) != 0 {
+            return false;
+        }
+
+        // if we are running on the owner's cpu, then the owner isn't running
+        let owner_cpu = owner >> CPU_SHIFT;
+        owner_cpu == UNKNOWN_CPU || owner_cpu != current_cpu()
+    }
+}
+
+impl Default for OwnerAware {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for OwnerAware {
+    const INIT: Self = Self::new();
+}
+
+impl SpinPolicy for OwnerAware {
+    type Spinner = SpinWait;
+
+    #[inline]
+    fn spinner(&self) -> Self::Spinner {
+        SpinWait::new()
+    }
+
+    #[inline]
+    fn spin(&self, spinner: &mut Self::Spinner) -> bool {
+        self.is_owner_running() && spinner.spin()
+    }
+
+    #[inline]
+    fn locked(&self) {
+        let owner = match current_slot() {
+            Some(slot) => current_cpu() << CPU_SHIFT | slot,
+            None => NO_OWNER,
+        };
+
+        self.owner.store(owner, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn unlocked(&self) {
+        self.owner.store(NO_OWNER, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn parking(&self) {
+        if let Some(slot) = current_slot() {
+            PARKED[slot as usize].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn unparked(&self) {
+        if let Some(slot) = current_slot() {
+            PARKED[slot as usize].fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[test]
+fn test_owner() {
+    let lock = Mutex::new(0);
+    let policy = lock.raw().inner().policy();
+    assert!(policy.is_owner_running());
+
+    let guard = lock.lock();
+    assert_eq!(
+        policy.owner.load(Ordering::Relaxed) & SLOT_MASK,
+        current_slot().unwrap()
+    );
+
+    // the owner is parked, so it can't be running
+    policy.parking();
+    assert!(!policy.is_owner_running());
+    policy.unparked();
+
+    drop(guard);
+    assert_eq!(policy.owner.load(Ordering::Relaxed), NO_OWNER);
+    assert!(policy.is_owner_running());
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *lock.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*lock.lock(), 4000);
+}
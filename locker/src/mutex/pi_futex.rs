@@ -0,0 +1,206 @@
+//! a priority inheriting futex based lock
+//!
+//! This lock uses the linux `FUTEX_LOCK_PI` and `FUTEX_UNLOCK_PI` operations,
+//! so while a thread is blocked on the lock, the lock owner is boosted to the
+//! priority of the highest priority waiter. This prevents priority inversion
+//! in real-time applications.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// set by the kernel when there are threads blocked on the futex
+const FUTEX_WAITERS: u32 = 0x8000_0000;
+
+/// a raw mutex backed by a priority inheriting futex
+pub type RawMutex = crate::mutex::raw::Mutex<PiFutexLock>;
+
+/// a mutex backed by a priority inheriting futex
+pub type Mutex<T> = crate::mutex::Mutex<PiFutexLock, T>;
+
+/// A lock which parks threads on a linux priority inheriting futex
+///
+/// The lock stores the thread id of the owner, and the kernel hands off the
+/// lock directly to the highest priority waiter when it is unlocked.
+///
+/// The kernel checks that the lock is unlocked by the thread that locked it,
+/// so the guards of this lock are not `Send`.
+pub struct PiFutexLock {
+    state: AtomicU32,
+}
+
+fn current_tid() -> u32 {
+    std::thread_local! {
+        static TID: u32 = unsafe { libc::syscall(libc::SYS_gettid) as u32 };
+    }
+
+    TID.with(|&tid| tid)
+}
+
+impl PiFutexLock {
+    /// create a new priority inheriting futex lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(0),
+        }
+    }
+
+    /// create a new priority inheriting futex lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new priority inheriting futex lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self) {
+        loop {
+            // SAFETY: the futex is a valid `u32` for the duration of the call
+            let r = unsafe {
+                libc::syscall(
+                    libc::SYS_futex,
+                    &self.state as *const AtomicU32,
+                    libc::FUTEX_LOCK_PI | libc::FUTEX_PRIVATE_FLAG,
+                    0,
+                    core::ptr::null::<libc::timespec>(),
+                )
+            };
+
+            if r == 0 {
+                return;
+            }
+
+            match std::io::Error::last_os_error().raw_os_error() {
+                Some(libc::EINTR) | Some(libc::EAGAIN) => continue,
+                Some(libc::EDEADLK) => {
+                    panic!("Tried to lock a priority inheriting lock twice on the same thread")
+                }
+                _ => panic!(
+                    "Could not lock priority inheriting lock: {}",
+                    std::io::Error::last_os_error()
+                ),
+            }
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn unlock_slow(&self) {
+        // SAFETY: the futex is a valid `u32` for the duration of the call
+        // the kernel will hand off the lock to the highest priority waiter
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                &self.state as *const AtomicU32,
+                libc::FUTEX_UNLOCK_PI | libc::FUTEX_PRIVATE_FLAG,
+            );
+        }
+    }
+}
+
+impl Default for PiFutexLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for PiFutexLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for PiFutexLock {}
+unsafe impl crate::RawLockInfo for PiFutexLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for PiFutexLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.lock_slow();
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.state
+            .compare_exchange(0, current_tid(), Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // if there are waiters, then the kernel has set `FUTEX_WAITERS`, and must do the unlock
+        if self
+            .state
+            .compare_exchange(current_tid(), 0, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            self.unlock_slow();
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        if self.state.load(Ordering::Relaxed) & FUTEX_WAITERS != 0 {
+            self.unlock_slow();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for PiFutexLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // the kernel always hands off the lock directly to the next waiter
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.exc_bump()
+    }
+}
+
+#[test]
+fn test_priority_inheritance() {
+    let mutex = PiFutexLock::mutex(0);
+    let state = &mutex.raw().inner().state;
+
+    // the futex word holds the owner's thread id, so the kernel knows who to boost
+    let guard = mutex.lock();
+    assert_eq!(state.load(Ordering::Relaxed), current_tid());
+
+    crossbeam_utils::thread::scope(|s| {
+        let waiter = s.spawn(|_| {
+            let mut guard = mutex.lock();
+            // the kernel handed the lock directly to this thread
+            assert_eq!(
+                state.load(Ordering::Relaxed) & !FUTEX_WAITERS,
+                current_tid()
+            );
+            *guard += 1;
+        });
+
+        // the kernel sets `FUTEX_WAITERS` once the waiter blocks in `FUTEX_LOCK_PI`
+        while state.load(Ordering::Relaxed) & FUTEX_WAITERS == 0 {
+            std::thread::yield_now();
+        }
+
+        assert_eq!(state.load(Ordering::Relaxed), current_tid() | FUTEX_WAITERS);
+
+        // the fast path fails because of `FUTEX_WAITERS`, so this unlocks with `FUTEX_UNLOCK_PI`
+        drop(guard);
+        waiter.join().unwrap();
+    })
+    .unwrap();
+
+    assert_eq!(state.load(Ordering::Relaxed), 0);
+    assert_eq!(*mutex.lock(), 1);
+}
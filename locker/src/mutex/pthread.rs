@@ -0,0 +1,217 @@
+//! a lock backed by a `pthread_mutex_t`
+//!
+//! pthread locks can't be moved once they have been used, so they
+//! are lazily allocated and initialized the first time they are locked.
+
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::boxed::Box;
+
+use crate::exclusive_lock::RawExclusiveLock;
+
+/// a raw mutex backed by a `pthread_mutex_t`
+pub type RawMutex = crate::mutex::raw::Mutex<PthreadLock>;
+
+/// a mutex backed by a `pthread_mutex_t`
+pub type Mutex<T> = crate::mutex::Mutex<PthreadLock, T>;
+
+/// A lazily allocated pthread lock, this allows creating the lock in a `const` context
+/// even if the lock needs to be initialized at runtime, and allows moving the lock
+/// after it has been used.
+pub(crate) struct LazyBox<T> {
+    ptr: AtomicPtr<T>,
+}
+
+impl<T> LazyBox<T> {
+    pub const fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// get the lock, initializing it with `init` if it isn't already initialized
+    ///
+    /// # Safety
+    ///
+    /// `destroy` must be safe to call on any value created by `init`
+    #[inline]
+    pub unsafe fn get_or_init(&self, init: fn() -> Box<T>, destroy: unsafe fn(*mut T)) -> *mut T {
+        let ptr = self.ptr.load(Ordering::Acquire);
+
+        if ptr.is_null() {
+            self.init(init, destroy)
+        } else {
+            ptr
+        }
+    }
+
+    #[cold]
+    unsafe fn init(&self, init: fn() -> Box<T>, destroy: unsafe fn(*mut T)) -> *mut T {
+        let new = Box::into_raw(init());
+
+        match self
+            .ptr
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => new,
+            Err(ptr) => {
+                // another thread initialized the lock first, so use theirs
+                destroy(new);
+                drop(Box::from_raw(new));
+                ptr
+            }
+        }
+    }
+
+    /// take the lock out, if it was initialized
+    pub fn take(&mut self) -> Option<Box<T>> {
+        let ptr = core::mem::replace(self.ptr.get_mut(), ptr::null_mut());
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Box::from_raw(ptr) })
+        }
+    }
+}
+
+/// A lock backed by a `pthread_mutex_t`
+///
+/// The underlying mutex is created with `PTHREAD_MUTEX_NORMAL`, so that locking
+/// the mutex again on the same thread deadlocks instead of invoking undefined behavior.
+///
+/// A `pthread_mutex_t` must be unlocked on the same thread that locked it,
+/// so the guards of this lock are not `Send`.
+pub struct PthreadLock {
+    lock: LazyBox<libc::pthread_mutex_t>,
+}
+
+// SAFETY: the mutex is boxed, so it never moves, and it is synchronized by the OS
+unsafe impl Send for PthreadLock {}
+unsafe impl Sync for PthreadLock {}
+
+impl PthreadLock {
+    /// create a new pthread lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: LazyBox::new(),
+        }
+    }
+
+    /// create a new pthread lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new pthread lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    #[inline]
+    fn raw(&self) -> *mut libc::pthread_mutex_t {
+        unsafe { self.lock.get_or_init(Self::init, Self::destroy) }
+    }
+
+    fn init() -> Box<libc::pthread_mutex_t> {
+        let mut mutex = Box::new(libc::PTHREAD_MUTEX_INITIALIZER);
+
+        unsafe {
+            let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+            let attr = attr.as_mut_ptr();
+
+            let r = libc::pthread_mutexattr_init(attr);
+            assert_eq!(r, 0, "could not initialize the pthread mutex attributes");
+
+            let r = libc::pthread_mutexattr_settype(attr, libc::PTHREAD_MUTEX_NORMAL);
+            assert_eq!(r, 0, "could not set the pthread mutex type");
+
+            let r = libc::pthread_mutex_init(&mut *mutex, attr);
+            assert_eq!(r, 0, "could not initialize the pthread mutex");
+
+            libc::pthread_mutexattr_destroy(attr);
+        }
+
+        mutex
+    }
+
+    unsafe fn destroy(mutex: *mut libc::pthread_mutex_t) {
+        libc::pthread_mutex_destroy(mutex);
+    }
+}
+
+impl Drop for PthreadLock {
+    fn drop(&mut self) {
+        if let Some(mut mutex) = self.lock.take() {
+            unsafe {
+                // destroying a locked mutex is undefined behavior,
+                // so if a guard was leaked, then leak the mutex too
+                if libc::pthread_mutex_trylock(&mut *mutex) == 0 {
+                    libc::pthread_mutex_unlock(&mut *mutex);
+                    Self::destroy(&mut *mutex);
+                } else {
+                    Box::leak(mutex);
+                }
+            }
+        }
+    }
+}
+
+impl Default for PthreadLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for PthreadLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for PthreadLock {}
+unsafe impl crate::RawLockInfo for PthreadLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for PthreadLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let r = unsafe { libc::pthread_mutex_lock(self.raw()) };
+        // the lock must not be treated as acquired if this fails
+        assert_eq!(r, 0, "failed to acquire a pthread mutex");
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        unsafe { libc::pthread_mutex_trylock(self.raw()) == 0 }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let r = libc::pthread_mutex_unlock(self.raw());
+        debug_assert_eq!(r, 0);
+    }
+}
+
+#[test]
+fn test_contention() {
+    let mutex = PthreadLock::mutex(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *mutex.lock() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let guard = mutex.lock();
+    assert_eq!(*guard, 4000);
+    assert!(mutex.try_lock().is_none());
+}
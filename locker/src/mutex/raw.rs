@@ -116,6 +116,17 @@ where
     }
 }
 
+impl<L: RawMutex + crate::RawLockQuery> Mutex<L> {
+    /// Returns true if the mutex is currently locked
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+}
+
 impl<L: RawMutex + RawExclusiveLockTimed> Mutex<L>
 where
     L::ExclusiveGuardTraits: crate::Inhabitted,
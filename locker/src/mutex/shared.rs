@@ -0,0 +1,248 @@
+//! a robust process-shared lock backed by a `pthread_mutex_t`
+//!
+//! This lock can be placed in memory which is shared between multiple processes
+//! (for example with `mmap` or `shm_open`). The underlying mutex is process-shared,
+//! robust, and priority inheriting, so it is backed by a robust PI futex.
+//!
+//! If the owner of the lock dies (either the owning thread exits or the owning process
+//! is killed) while holding the lock, then the next thread to acquire the lock will
+//! be notified via [`SharedLock::owner_died`]. The protected value may be in an inconsistent
+//! state, so after repairing it, the new owner must call [`SharedLock::make_consistent`] before
+//! unlocking. Otherwise the lock becomes permanently unusable, and any further attempt to
+//! acquire it will panic.
+//!
+//! All zero bytes is a valid unlocked `SharedLock`, so a zeroed shared memory segment
+//! doesn't need to be initialized. The underlying `pthread_mutex_t` is lazily initialized
+//! the first time the lock is acquired.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLock};
+use crate::spin_wait::SpinWait;
+
+const UNINIT: u32 = 0;
+const INITIALIZING: u32 = 1;
+const READY: u32 = 2;
+
+/// a raw mutex which can be shared between processes
+pub type RawMutex = crate::mutex::raw::Mutex<SharedLock>;
+
+/// a mutex which can be shared between processes
+pub type Mutex<T> = crate::mutex::Mutex<SharedLock, T>;
+
+/// A robust process-shared lock backed by a `pthread_mutex_t`
+///
+/// Unlike [`PthreadLock`](crate::mutex::pthread::PthreadLock), the `pthread_mutex_t` is stored
+/// inline, so that it lives in the shared memory segment. On linux an unlocked `pthread_mutex_t`
+/// doesn't contain any pointers into itself, so it is fine to move the lock while it is unlocked.
+///
+/// A `pthread_mutex_t` must be unlocked on the same thread that locked it,
+/// so the guards of this lock are not `Send`.
+#[repr(C)]
+pub struct SharedLock {
+    state: AtomicU32,
+    owner_died: AtomicBool,
+    mutex: UnsafeCell<libc::pthread_mutex_t>,
+}
+
+// SAFETY: the mutex is synchronized by the OS
+unsafe impl Send for SharedLock {}
+unsafe impl Sync for SharedLock {}
+
+impl SharedLock {
+    /// create a new shared lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(UNINIT),
+            owner_died: AtomicBool::new(false),
+            mutex: UnsafeCell::new(libc::PTHREAD_MUTEX_INITIALIZER),
+        }
+    }
+
+    /// create a new shared lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new shared lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// Returns true if the previous owner of the lock died while holding the lock,
+    /// and the lock hasn't been made consistent yet
+    ///
+    /// This is only meaningful while the current thread holds the lock
+    #[inline]
+    pub fn owner_died(&self) -> bool {
+        self.owner_died.load(Ordering::Relaxed)
+    }
+
+    /// Mark the lock as consistent after the previous owner died
+    ///
+    /// This should be called after repairing the protected value, otherwise
+    /// the lock will become unusable once it is unlocked.
+    ///
+    /// # Safety
+    ///
+    /// The current thread must hold the lock
+    pub unsafe fn make_consistent(&self) {
+        if self.owner_died.swap(false, Ordering::Relaxed) {
+            let r = libc::pthread_mutex_consistent(self.mutex.get());
+            assert_eq!(r, 0, "failed to make a shared lock consistent");
+        }
+    }
+
+    #[inline]
+    fn raw(&self) -> *mut libc::pthread_mutex_t {
+        if self.state.load(Ordering::Acquire) != READY {
+            self.init();
+        }
+
+        self.mutex.get()
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn init(&self) {
+        if self
+            .state
+            .compare_exchange(UNINIT, INITIALIZING, Ordering::Acquire, Ordering::Acquire)
+            .is_err()
+        {
+            // another thread (maybe in another process) is initializing the lock
+            let mut spin = SpinWait::new();
+
+            while self.state.load(Ordering::Acquire) != READY {
+                spin.spin();
+            }
+
+            return;
+        }
+
+        unsafe {
+            let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+            let attr = attr.as_mut_ptr();
+
+            let r = libc::pthread_mutexattr_init(attr);
+            assert_eq!(r, 0, "could not initialize the pthread mutex attributes");
+
+            let r = libc::pthread_mutexattr_settype(attr, libc::PTHREAD_MUTEX_NORMAL);
+            assert_eq!(r, 0, "could not set the pthread mutex type");
+
+            let r = libc::pthread_mutexattr_setpshared(attr, libc::PTHREAD_PROCESS_SHARED);
+            assert_eq!(r, 0, "could not make the pthread mutex process-shared");
+
+            let r = libc::pthread_mutexattr_setrobust(attr, libc::PTHREAD_MUTEX_ROBUST);
+            assert_eq!(r, 0, "could not make the pthread mutex robust");
+
+            let r = libc::pthread_mutexattr_setprotocol(attr, libc::PTHREAD_PRIO_INHERIT);
+            assert_eq!(r, 0, "could not make the pthread mutex priority inheriting");
+
+            let r = libc::pthread_mutex_init(self.mutex.get(), attr);
+            assert_eq!(r, 0, "could not initialize the pthread mutex");
+
+            libc::pthread_mutexattr_destroy(attr);
+        }
+
+        self.state.store(READY, Ordering::Release);
+    }
+}
+
+impl Default for SharedLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for SharedLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for SharedLock {}
+unsafe impl crate::RawLockInfo for SharedLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+unsafe impl RawExclusiveLock for SharedLock {
+    #[inline]
+    fn exc_lock(&self) {
+        match unsafe { libc::pthread_mutex_lock(self.raw()) } {
+            0 => (),
+            libc::EOWNERDEAD => self.owner_died.store(true, Ordering::Relaxed),
+            libc::ENOTRECOVERABLE => panic!(
+                "Tried to lock a shared lock whose owner died without making the lock consistent"
+            ),
+            // relocking on the same thread fails with `EDEADLK` instead of deadlocking,
+            // so the lock must not be treated as acquired if this fails
+            libc::EDEADLK => panic!("shared lock would result in deadlock"),
+            r => panic!("failed to acquire a shared lock: {}", r),
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        match unsafe { libc::pthread_mutex_trylock(self.raw()) } {
+            0 => true,
+            libc::EOWNERDEAD => {
+                self.owner_died.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let r = libc::pthread_mutex_unlock(self.raw());
+        debug_assert_eq!(r, 0);
+    }
+}
+
+impl<T: ?Sized, St> ExclusiveGuard<'_, SharedLock, T, St> {
+    /// Returns true if the previous owner of the lock died while holding the lock,
+    /// and the lock hasn't been made consistent yet
+    ///
+    /// If this returns true, then the guarded value may be in an inconsistent state
+    #[inline]
+    pub fn owner_died(g: &Self) -> bool {
+        ExclusiveGuard::raw(g).inner().owner_died()
+    }
+
+    /// Mark the lock as consistent after the previous owner died
+    ///
+    /// This should be called after repairing the guarded value, otherwise
+    /// the lock will become unusable once this guard is dropped.
+    #[inline]
+    pub fn make_consistent(g: &mut Self) {
+        unsafe { ExclusiveGuard::raw(g).inner().make_consistent() }
+    }
+}
+
+#[test]
+fn test_owner_died() {
+    let mutex = SharedLock::mutex(0);
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            *mutex.lock() = 1;
+            core::mem::forget(mutex.lock());
+        });
+    });
+
+    let mut guard = mutex.lock();
+    assert!(ExclusiveGuard::owner_died(&guard));
+    assert_eq!(*guard, 1);
+    *guard = 2;
+    ExclusiveGuard::make_consistent(&mut guard);
+    drop(guard);
+
+    let guard = mutex.lock();
+    assert!(!ExclusiveGuard::owner_died(&guard));
+    assert_eq!(*guard, 2);
+}
@@ -1,6 +1,7 @@
 //! a spin lock
 
-use crate::spin_wait::SpinWait;
+use crate::relax::{DefaultRelax, Relax};
+use core::marker::PhantomData;
 use core::sync::atomic::{AtomicBool, Ordering};
 
 /// a raw mutex backed by a spin lock
@@ -11,7 +12,7 @@ use core::sync::atomic::{AtomicBool, Ordering};
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub type RawMutex = crate::mutex::raw::Mutex<SpinLock>;
+pub type RawMutex<R = DefaultRelax> = crate::mutex::raw::Mutex<SpinLock<R>>;
 
 /// a mutex backed by a spin lock
 ///
@@ -21,7 +22,7 @@ pub type RawMutex = crate::mutex::raw::Mutex<SpinLock>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub type Mutex<T> = crate::mutex::Mutex<SpinLock, T>;
+pub type Mutex<T, R = DefaultRelax> = crate::mutex::Mutex<SpinLock<R>, T>;
 
 /// A spin lock
 ///
@@ -31,17 +32,22 @@ pub type Mutex<T> = crate::mutex::Mutex<SpinLock, T>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub struct SpinLock {
+///
+/// The entire state of the lock is a single byte, so it can be embedded in many small
+/// objects. If you also need to store a few bits next to the lock, then use
+/// [`TaggedSpinLock`](crate::mutex::tagged_spin::TaggedSpinLock), which is also a single byte.
+///
+/// While waiting for the lock, the spin lock backs off using the [`Relax`] strategy `R`
+pub struct SpinLock<R = DefaultRelax> {
     lock: AtomicBool,
+    relax: PhantomData<fn() -> R>,
 }
 
 impl SpinLock {
     /// create a new spin lock
     #[inline]
     pub const fn new() -> Self {
-        SpinLock {
-            lock: AtomicBool::new(false),
-        }
+        Self::with_relax()
     }
 
     /// create a new spin lock based raw mutex
@@ -55,27 +61,59 @@ impl SpinLock {
     }
 }
 
-impl crate::Init for SpinLock {
-    const INIT: Self = Self::new();
+impl<R> SpinLock<R> {
+    /// create a new spin lock which uses the [`Relax`] strategy `R`
+    #[inline]
+    pub const fn with_relax() -> Self {
+        SpinLock {
+            lock: AtomicBool::new(false),
+            relax: PhantomData,
+        }
+    }
+}
+
+impl<R> Default for SpinLock<R> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_relax()
+    }
+}
+
+impl<R> crate::Init for SpinLock<R> {
+    const INIT: Self = Self::with_relax();
 }
 
-unsafe impl crate::mutex::RawMutex for SpinLock {}
-unsafe impl crate::RawLockInfo for SpinLock {
+unsafe impl<R: Relax> crate::mutex::RawMutex for SpinLock<R> {}
+unsafe impl<R> crate::RawLockInfo for SpinLock<R> {
     type ExclusiveGuardTraits = ();
     type ShareGuardTraits = core::convert::Infallible;
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
+impl<R> crate::RawLockQuery for SpinLock<R> {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.lock.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked()
+    }
+}
+
+unsafe impl<R> crate::combinators::Elidable for SpinLock<R> {}
+
+unsafe impl<R: Relax> crate::exclusive_lock::RawExclusiveLock for SpinLock<R> {
     #[inline]
     fn exc_lock(&self) {
-        let mut spin = SpinWait::new();
+        let mut relax = R::default();
 
         while self
             .lock
             .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
             .is_err()
         {
-            spin.spin();
+            relax.relax();
         }
     }
 
@@ -96,3 +134,221 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
         // there are never any parked threads in a spin lock
     }
 }
+
+#[test]
+fn test_size() {
+    assert_eq!(core::mem::size_of::<SpinLock>(), 1);
+    assert_eq!(
+        core::mem::size_of::<crate::mutex::tagged_spin::TaggedSpinLock>(),
+        1
+    );
+}
+
+#[test]
+fn test_lock_arc() {
+    use std::sync::Arc;
+
+    let lock = Arc::new(SpinLock::mutex(0));
+    let mut guard = lock.lock_arc();
+    assert!(lock.try_lock_arc().is_none());
+
+    let thread = std::thread::spawn(move || {
+        *guard += 1;
+    });
+
+    thread.join().unwrap();
+    assert_eq!(*lock.try_lock_arc().unwrap(), 1);
+}
+
+#[test]
+fn test_force_unlock() {
+    let lock = SpinLock::mutex(0);
+
+    core::mem::forget(lock.lock());
+    assert!(lock.try_lock().is_none());
+    unsafe { lock.force_unlock() }
+
+    assert!(lock.try_lock().is_some());
+}
+
+#[test]
+fn test_debug() {
+    let lock = SpinLock::mutex(10);
+    assert_eq!(format!("{:?}", lock), "Mutex { data: 10 }");
+
+    let guard = lock.lock();
+    assert_eq!(format!("{:?}", guard), "10");
+    assert_eq!(format!("{:?}", lock), "Mutex { data: <locked> }");
+}
+
+#[test]
+fn test_replace() {
+    let lock = SpinLock::mutex(vec![1, 2]);
+
+    assert_eq!(lock.replace(vec![3]), [1, 2]);
+    assert_eq!(lock.take(), [3]);
+    assert!(lock.lock().is_empty());
+}
+
+#[test]
+fn test_get_cloned() {
+    let lock = SpinLock::mutex(vec![1, 2]);
+
+    assert_eq!(lock.get_cloned(), [1, 2]);
+    assert!(lock.try_lock().is_some());
+}
+
+#[test]
+fn test_update() {
+    let lock = SpinLock::mutex(1);
+
+    lock.update(|x| *x += 1);
+    assert_eq!(lock.update_and_fetch(|x| *x *= 10), 20);
+    assert_eq!(lock.fetch_update(|x| *x += 1), 20);
+    assert_eq!(*lock.lock(), 21);
+}
+
+#[test]
+fn test_static() {
+    static LOCK: crate::mutex::Mutex<SpinLock, Vec<u32>> = crate::mutex::Mutex::new(Vec::new());
+
+    LOCK.lock().push(10);
+    assert_eq!(*LOCK.lock(), [10]);
+}
+
+#[test]
+fn test_unsized() {
+    use std::boxed::Box;
+
+    let lock: Box<crate::mutex::Mutex<SpinLock, [String]>> =
+        Box::from(vec![String::from("a"), String::from("b")].into_boxed_slice());
+    assert_eq!(lock.len(), 2);
+    lock.lock()[1].push('c');
+    assert_eq!(*lock.lock(), ["a", "bc"]);
+
+    let empty: Box<crate::mutex::Mutex<SpinLock, [()]>> = Box::from(Vec::new().into_boxed_slice());
+    assert!(empty.is_empty());
+
+    let array = SpinLock::mutex([1, 2, 3]);
+    assert_eq!(array.as_slice().len(), 3);
+
+    let object: Box<crate::mutex::Mutex<SpinLock, dyn core::fmt::Display>> =
+        Box::new(SpinLock::mutex(10));
+    assert_eq!(object.lock().to_string(), "10");
+}
+
+#[test]
+fn test_lock_pin() {
+    use core::marker::PhantomPinned;
+    use core::pin::Pin;
+
+    struct SelfRef {
+        value: u32,
+        _pinned: PhantomPinned,
+    }
+
+    impl SelfRef {
+        fn bump(self: Pin<&mut Self>) {
+            unsafe { self.get_unchecked_mut().value += 1 }
+        }
+    }
+
+    let lock = Box::pin(SpinLock::mutex(SelfRef {
+        value: 0,
+        _pinned: PhantomPinned,
+    }));
+
+    let mut guard = unsafe { lock.as_ref().lock_pin() };
+    guard.as_mut().bump();
+    assert!(unsafe { lock.as_ref().try_lock_pin() }.is_none());
+    drop(guard);
+
+    assert_eq!(lock.lock().value, 1);
+}
+
+#[test]
+fn test_lock_owned() {
+    use crate::exclusive_lock::{OwnedExclusiveGuard, OwnedRawExclusiveGuard};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn make() -> OwnedExclusiveGuard<Box<Mutex<u32>>, SpinLock, u32> {
+        crate::mutex::Mutex::lock_owned(Box::new(SpinLock::mutex(10)))
+    }
+
+    let mut guard = make();
+    *guard += 1;
+    assert_eq!(*guard, 11);
+
+    let lock = Rc::new(SpinLock::mutex(0));
+    let guard = crate::mutex::Mutex::lock_owned(lock.clone());
+    let lock = match crate::mutex::Mutex::try_lock_owned(lock) {
+        Ok(_) => panic!("the lock is already held"),
+        Err(lock) => lock,
+    };
+    drop(guard);
+    assert!(crate::mutex::Mutex::try_lock_owned(lock).is_ok());
+
+    let raw = Arc::new(SpinLock::new());
+    let guard = OwnedRawExclusiveGuard::new(raw.clone());
+    assert!(crate::RawLockQuery::is_locked(&*raw));
+    assert!(OwnedRawExclusiveGuard::try_new(raw.clone()).is_err());
+    drop(guard);
+    assert!(!crate::RawLockQuery::is_locked(&*raw));
+}
+
+#[test]
+fn test_lock_lazy() {
+    use crate::exclusive_lock::LazyGuard;
+
+    let lock = SpinLock::mutex(0);
+
+    let guard = lock.lock_lazy();
+    assert!(!LazyGuard::is_locked(&guard));
+    assert!(lock.try_lock().is_some());
+    assert_eq!(format!("{:?}", guard), "<unlocked>");
+    drop(guard);
+
+    let mut guard = lock.lock_lazy();
+    *guard += 1;
+    assert!(LazyGuard::is_locked(&guard));
+    assert!(lock.try_lock().is_none());
+    assert_eq!(format!("{:?}", guard), "1");
+    drop(guard);
+
+    assert_eq!(*lock.lock(), 1);
+}
+
+#[test]
+fn test_into_raw() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    let lock = SpinLock::mutex((0, 1));
+
+    let value = ExclusiveGuard::into_raw(lock.lock());
+    assert!(lock.try_lock().is_none());
+    let mut guard: ExclusiveGuard<_, _> =
+        unsafe { ExclusiveGuard::from_raw(lock.raw().inner(), value) };
+    guard.0 += 2;
+    drop(guard);
+
+    let value = ExclusiveGuard::into_raw(ExclusiveGuard::map(lock.lock(), |(_, b)| b));
+    assert!(lock.try_lock().is_none());
+    let mut guard =
+        unsafe { crate::exclusive_lock::MappedExclusiveGuard::from_raw(lock.raw().inner(), value) };
+    *guard += 2;
+    drop(guard);
+
+    assert_eq!(*lock.lock(), (2, 3));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde() {
+    let lock = SpinLock::mutex(std::vec![1, 2, 3]);
+    let json = serde_json::to_string(&lock).unwrap();
+    assert_eq!(json, "[1,2,3]");
+
+    let lock: Mutex<std::vec::Vec<u32>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(*lock.try_lock().unwrap(), [1, 2, 3]);
+}
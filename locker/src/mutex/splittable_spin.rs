@@ -1,7 +1,7 @@
 //! a splittable spin lock
 
 use crate::exclusive_lock::RawExclusiveLock;
-use crate::spin_wait::SpinWait;
+use crate::relax::Backoff;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// a splittable spin raw mutex
@@ -70,14 +70,14 @@ impl SplitSpinLock {
     #[cold]
     #[inline(never)]
     fn lock_slow(&self) {
-        let mut wait = SpinWait::new();
+        let mut backoff = Backoff::new();
 
         while self
             .state
             .compare_exchange_weak(0, INC, Ordering::Acquire, Ordering::Relaxed)
             .is_err()
         {
-            wait.spin();
+            backoff.snooze();
         }
     }
 }
@@ -128,3 +128,29 @@ unsafe impl crate::exclusive_lock::SplittableExclusiveLock for SplitSpinLock {
         self.state.fetch_add(INC, Ordering::Relaxed);
     }
 }
+
+#[test]
+fn test_split_map() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    struct Fields {
+        a: Vec<u32>,
+        b: Vec<u32>,
+    }
+
+    let lock = SplitSpinLock::mutex(Fields {
+        a: Vec::new(),
+        b: Vec::new(),
+    });
+
+    let (mut a, mut b) = ExclusiveGuard::split_map(lock.lock(), |f| (&mut f.a, &mut f.b));
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(move |_| a.push(1));
+        s.spawn(move |_| b.push(2));
+    })
+    .unwrap();
+
+    let fields = lock.lock();
+    assert_eq!((&*fields.a, &*fields.b), (&[1][..], &[2][..]));
+}
@@ -150,7 +150,7 @@ impl crate::Init for TaggedLock {
 
 unsafe impl crate::mutex::RawMutex for TaggedLock {}
 unsafe impl crate::RawLockInfo for TaggedLock {
-    type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
+    type ExclusiveGuardTraits = (crate::SendGuard, crate::NoSync);
     type ShareGuardTraits = core::convert::Infallible;
 }
 
@@ -377,6 +377,44 @@ impl crate::RawTimedLock for TaggedLock {
     type Duration = std::time::Duration;
 }
 
+unsafe impl crate::condvar::Parkable for TaggedLock {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        Some(self as *const _ as usize)
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            if state & Self::LOCK_BIT == 0 {
+                return false;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state | Self::PARK_BIT,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.state.fetch_or(Self::PARK_BIT, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: UnparkToken) -> bool {
+        token == TOKEN_HANDOFF
+    }
+}
+
 unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for TaggedLock {
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.exc_try_lock() {
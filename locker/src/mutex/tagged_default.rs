@@ -170,3 +170,26 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for TaggedDefaultLock {
         self.0.exc_try_lock_for(duration)
     }
 }
+
+#[cfg(feature = "parking_lot_core")]
+unsafe impl crate::condvar::Parkable for TaggedDefaultLock {
+    #[inline]
+    fn park_key(&self) -> Option<usize> {
+        self.0.park_key()
+    }
+
+    #[inline]
+    fn mark_parked_if_locked(&self) -> bool {
+        self.0.mark_parked_if_locked()
+    }
+
+    #[inline]
+    fn mark_parked(&self) {
+        self.0.mark_parked()
+    }
+
+    #[inline]
+    fn is_handoff(&self, token: parking_lot_core::UnparkToken) -> bool {
+        self.0.is_handoff(token)
+    }
+}
@@ -1,9 +1,13 @@
 //! a tagged spin lock
 
 use crate::exclusive_lock::RawExclusiveLock;
-use crate::spin_wait::SpinWait;
+use crate::relax::{DefaultRelax, Relax};
+use core::marker::PhantomData;
 use core::sync::atomic::{AtomicU8, Ordering};
 
+const LOCK_BIT: u8 = 0b1000_0000;
+const MASK: u8 = !LOCK_BIT;
+
 /// A tagged spin raw mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
 ///
 /// It is not reccomended to use this type in libraries,
@@ -12,7 +16,7 @@ use core::sync::atomic::{AtomicU8, Ordering};
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub type RawMutex = crate::mutex::raw::Mutex<TaggedSpinLock>;
+pub type RawMutex<R = DefaultRelax> = crate::mutex::raw::Mutex<TaggedSpinLock<R>>;
 
 /// A tagged spin mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
 ///
@@ -22,7 +26,7 @@ pub type RawMutex = crate::mutex::raw::Mutex<TaggedSpinLock>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub type Mutex<T> = crate::mutex::Mutex<TaggedSpinLock, T>;
+pub type Mutex<T, R = DefaultRelax> = crate::mutex::Mutex<TaggedSpinLock<R>, T>;
 
 #[inline]
 fn strongest_failure_ordering(order: Ordering) -> Ordering {
@@ -46,38 +50,62 @@ fn strongest_failure_ordering(order: Ordering) -> Ordering {
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
-pub struct TaggedSpinLock {
+///
+/// While waiting for the lock, the spin lock backs off using the [`Relax`] strategy `R`
+pub struct TaggedSpinLock<R = DefaultRelax> {
     state: AtomicU8,
+    relax: PhantomData<fn() -> R>,
 }
 
 impl TaggedSpinLock {
-    const LOCK_BIT: u8 = 0b1000_0000;
-
     /// The number of bits that this mutex can store
     ///
     /// This is guaranteed to be at least 4
-    pub const TAG_BITS: u8 = (!Self::MASK).trailing_zeros() as u8;
-    const MASK: u8 = !Self::LOCK_BIT;
+    pub const TAG_BITS: u8 = (!MASK).trailing_zeros() as u8;
 
     /// create a new tagged spin lock
     #[inline]
     pub const fn new() -> Self {
-        Self {
-            state: AtomicU8::new(0),
-        }
+        Self::with_relax_and_tag(0)
     }
 
     /// create a new tagged spin lock with the given inital tag
     #[inline]
     pub const fn with_tag(tag: u8) -> Self {
+        Self::with_relax_and_tag(tag)
+    }
+
+    /// Create a new raw tagged mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// Create a new tagged mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+}
+
+impl<R> TaggedSpinLock<R> {
+    /// create a new tagged spin lock which uses the [`Relax`] strategy `R`
+    #[inline]
+    pub const fn with_relax() -> Self {
+        Self::with_relax_and_tag(0)
+    }
+
+    /// create a new tagged spin lock which uses the [`Relax`] strategy `R`
+    /// with the given inital tag
+    #[inline]
+    pub const fn with_relax_and_tag(tag: u8) -> Self {
         Self {
-            state: AtomicU8::new(tag & Self::MASK),
+            state: AtomicU8::new(tag & MASK),
+            relax: PhantomData,
         }
     }
 
     /// Get the tag with the specified load ordering
     pub fn tag(&self, order: Ordering) -> u8 {
-        self.state.load(order) & Self::MASK
+        self.state.load(order) & MASK
     }
 
     /// perform a bit-wise and with the given tag and the stored tag using
@@ -87,9 +115,9 @@ impl TaggedSpinLock {
     ///
     /// this lowers to a single `fetch_and`
     pub fn and_tag(&self, tag: u8, order: Ordering) -> u8 {
-        let tag = (tag & Self::MASK) | !Self::MASK;
+        let tag = (tag & MASK) | !MASK;
 
-        self.state.fetch_and(tag, order) & Self::MASK
+        self.state.fetch_and(tag, order) & MASK
     }
 
     /// perform a bit-wise or with the given tag and the stored tag using
@@ -99,9 +127,9 @@ impl TaggedSpinLock {
     ///
     /// this lowers to a single `fetch_or`
     pub fn or_tag(&self, tag: u8, order: Ordering) -> u8 {
-        let tag = tag & Self::MASK;
+        let tag = tag & MASK;
 
-        self.state.fetch_or(tag, order) & Self::MASK
+        self.state.fetch_or(tag, order) & MASK
     }
 
     /// swap the tag with the given tag using the specied ordering
@@ -129,43 +157,52 @@ impl TaggedSpinLock {
     ) -> Result<u8, u8> {
         let mut state = self.state.load(failure);
 
-        while let Some(tag) = f(state & Self::MASK) {
+        while let Some(tag) = f(state & MASK) {
             match self.state.compare_exchange_weak(
                 state,
-                (state & !Self::MASK) | (tag & Self::MASK),
+                (state & !MASK) | (tag & MASK),
                 success,
                 failure,
             ) {
                 Err(x) => state = x,
-                Ok(x) => return Ok(x & Self::MASK),
+                Ok(x) => return Ok(x & MASK),
             }
         }
 
-        Err(state & Self::MASK)
-    }
-
-    /// Create a new raw tagged mutex
-    pub const fn raw_mutex() -> RawMutex {
-        unsafe { RawMutex::from_raw(Self::new()) }
+        Err(state & MASK)
     }
+}
 
-    /// Create a new tagged mutex
-    pub const fn mutex<T>(value: T) -> Mutex<T> {
-        Mutex::from_raw_parts(Self::raw_mutex(), value)
+impl<R> Default for TaggedSpinLock<R> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_relax()
     }
 }
 
-impl crate::Init for TaggedSpinLock {
-    const INIT: Self = Self::new();
+impl<R> crate::Init for TaggedSpinLock<R> {
+    const INIT: Self = Self::with_relax();
 }
 
-unsafe impl crate::mutex::RawMutex for TaggedSpinLock {}
-unsafe impl crate::RawLockInfo for TaggedSpinLock {
-    type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
+unsafe impl<R: Relax> crate::mutex::RawMutex for TaggedSpinLock<R> {}
+unsafe impl<R> crate::RawLockInfo for TaggedSpinLock<R> {
+    type ExclusiveGuardTraits = (crate::SendGuard, crate::NoSync);
     type ShareGuardTraits = core::convert::Infallible;
 }
 
-unsafe impl RawExclusiveLock for TaggedSpinLock {
+impl<R> crate::RawLockQuery for TaggedSpinLock<R> {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & LOCK_BIT != 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked()
+    }
+}
+
+unsafe impl<R: Relax> RawExclusiveLock for TaggedSpinLock<R> {
     #[inline]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
@@ -177,12 +214,12 @@ unsafe impl RawExclusiveLock for TaggedSpinLock {
     fn exc_try_lock(&self) -> bool {
         let state = self.state.load(Ordering::Relaxed);
 
-        (state & Self::LOCK_BIT == 0)
+        (state & LOCK_BIT == 0)
             && self
                 .state
                 .compare_exchange(
                     state,
-                    state | Self::LOCK_BIT,
+                    state | LOCK_BIT,
                     Ordering::Acquire,
                     Ordering::Relaxed,
                 )
@@ -193,11 +230,11 @@ unsafe impl RawExclusiveLock for TaggedSpinLock {
     unsafe fn exc_unlock(&self) {
         let mut state = self.state.load(Ordering::Relaxed);
 
-        debug_assert_ne!(state & Self::LOCK_BIT, 0);
+        debug_assert_ne!(state & LOCK_BIT, 0);
 
         while let Err(x) = self.state.compare_exchange_weak(
             state,
-            state & !Self::LOCK_BIT,
+            state & !LOCK_BIT,
             Ordering::Release,
             Ordering::Relaxed,
         ) {
@@ -209,22 +246,22 @@ unsafe impl RawExclusiveLock for TaggedSpinLock {
     unsafe fn exc_bump(&self) {}
 }
 
-impl TaggedSpinLock {
+impl<R: Relax> TaggedSpinLock<R> {
     #[cold]
     fn lock_slow(&self) {
         let mut state = self.state.load(Ordering::Relaxed);
-        let mut spin = SpinWait::new();
+        let mut relax = R::default();
 
         loop {
-            spin.spin();
-
-            if state & Self::LOCK_BIT == 0 {
+            if state & LOCK_BIT != 0 {
+                relax.relax();
+                state = self.state.load(Ordering::Relaxed);
                 continue;
             }
 
             if let Err(x) = self.state.compare_exchange(
                 state,
-                state | Self::LOCK_BIT,
+                state | LOCK_BIT,
                 Ordering::Acquire,
                 Ordering::Relaxed,
             ) {
@@ -0,0 +1,156 @@
+//! a fair ticket spin lock
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// a raw mutex backed by a ticket lock
+///
+/// Threads acquire the lock in the order that they first tried to lock it,
+/// so no thread can be starved by other threads repeatedly re-acquiring the lock.
+pub type RawMutex = crate::mutex::raw::Mutex<TicketLock>;
+
+/// a mutex backed by a ticket lock
+///
+/// Threads acquire the lock in the order that they first tried to lock it,
+/// so no thread can be starved by other threads repeatedly re-acquiring the lock.
+pub type Mutex<T> = crate::mutex::Mutex<TicketLock, T>;
+
+/// A fair (FIFO) spin lock
+///
+/// Each thread that wants to lock takes a ticket, then waits until its ticket
+/// is served. Unlocking serves the next ticket, so the lock is always handed
+/// over to the longest waiting thread.
+///
+/// Like [`SpinLock`](crate::mutex::spin::SpinLock), waiting threads never park,
+/// so this lock is best suited to short critical sections.
+pub struct TicketLock {
+    next: AtomicUsize,
+    serving: AtomicUsize,
+}
+
+impl TicketLock {
+    /// create a new ticket lock
+    #[inline]
+    pub const fn new() -> Self {
+        TicketLock {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new ticket lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+}
+
+impl Default for TicketLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for TicketLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for TicketLock {}
+unsafe impl crate::RawLockInfo for TicketLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = core::convert::Infallible;
+}
+
+impl crate::RawLockQuery for TicketLock {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.next.load(Ordering::Relaxed) != self.serving.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.is_locked()
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLock for TicketLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+
+        if self.serving.load(Ordering::Acquire) != ticket {
+            self.lock_slow(ticket);
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let serving = self.serving.load(Ordering::Relaxed);
+
+        // only take a ticket if it would be served immediately
+        self.next
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // only the lock owner may write to `serving`
+        let serving = self.serving.load(Ordering::Relaxed);
+        self.serving
+            .store(serving.wrapping_add(1), Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        // if another thread has taken a ticket, let it run
+        if self.has_waiters() {
+            self.exc_unlock();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for TicketLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // a ticket lock always hands the lock over to the next waiting thread
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.exc_bump()
+    }
+}
+
+impl TicketLock {
+    /// Returns true if another thread has taken a ticket,
+    /// this is only meaningful while the lock is held
+    #[inline]
+    pub(crate) fn has_waiters(&self) -> bool {
+        let serving = self.serving.load(Ordering::Relaxed);
+        self.next.load(Ordering::Relaxed) != serving.wrapping_add(1)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn lock_slow(&self, ticket: usize) {
+        let mut spin = SpinWait::new();
+
+        while self.serving.load(Ordering::Acquire) != ticket {
+            spin.spin();
+        }
+    }
+}
@@ -1,16 +1,33 @@
 use crate::exclusive_lock::RawExclusiveLock;
+#[cfg(feature = "std")]
+use crate::exclusive_lock::RawExclusiveLockTimed;
 use crate::RawLockInfo;
 
+use core::any::Any;
 use core::cell::UnsafeCell;
-use core::marker::PhantomData;
-use core::mem::MaybeUninit;
+use core::fmt;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use core::ops::{Deref, DerefMut};
 
+#[cfg(all(feature = "extra", feature = "std", feature = "parking_lot_core"))]
+mod global;
 #[cfg(feature = "parking_lot_core")]
 pub mod local;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod once_box;
 #[cfg(feature = "parking_lot_core")]
 pub mod simple;
+#[cfg(all(feature = "std", feature = "parking_lot_core"))]
+pub mod std_once;
+#[cfg(feature = "parking_lot_core")]
+pub mod sync;
+
+#[cfg(all(feature = "extra", feature = "std", feature = "parking_lot_core"))]
+pub use global::global_once;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use once_box::OnceBox;
 
 pub trait AsRawExclusiveLock {
     fn as_raw_exclusive_lock(&self) -> &dyn RawExclusiveLock;
@@ -50,6 +67,13 @@ impl<L: crate::Init> crate::Init for Once<L> {
     const INIT: Self = unsafe { Once::from_raw(crate::Init::INIT) };
 }
 
+impl<L: crate::Init> Once<L> {
+    #[inline]
+    pub const fn new() -> Self {
+        crate::Init::INIT
+    }
+}
+
 impl<L> Once<L> {
     /// # Safety
     ///
@@ -69,13 +93,26 @@ impl OnceState {
     }
 }
 
+#[cold]
+fn handle_poison() {
+    panic!("tried to call `call_once*` on a poisoned `Once`");
+}
+
 #[inline(always)]
 fn panic_on_poison(f: impl FnOnce()) -> impl FnOnce(&OnceState) {
-    #[cold]
-    fn handle_poison() {
-        panic!("tried to call `call_once*` on a poisoned `Once`");
+    move |once_state| {
+        if once_state.is_poisoned() {
+            handle_poison()
+        }
+
+        f()
     }
+}
 
+#[inline(always)]
+fn try_panic_on_poison<E>(
+    f: impl FnOnce() -> Result<(), E>,
+) -> impl FnOnce(&OnceState) -> Result<(), E> {
     move |once_state| {
         if once_state.is_poisoned() {
             handle_poison()
@@ -88,22 +125,102 @@ fn panic_on_poison(f: impl FnOnce()) -> impl FnOnce(&OnceState) {
 #[cold]
 #[inline(never)]
 fn run_once_unchecked<F: ?Sized + Finish>(lock: &F, f: impl FnOnce(&OnceState)) {
+    let _ = try_run_once_unchecked(lock, move |once_state| {
+        f(once_state);
+        Ok::<(), core::convert::Infallible>(())
+    });
+}
+
+// if `f` fails, then the `Once` is left incomplete so that it can be retried
+#[cold]
+#[inline(never)]
+fn try_run_once_unchecked<F: ?Sized + Finish, E>(
+    lock: &F,
+    f: impl FnOnce(&OnceState) -> Result<(), E>,
+) -> Result<(), E> {
     struct Poison<'a, F: ?Sized + Finish>(&'a F);
 
     impl<F: ?Sized + Finish> Drop for Poison<'_, F> {
         fn drop(&mut self) {
             self.0.mark_poisoned();
+            unpark_waiters(self.0);
         }
     }
 
     let is_poisoned = lock.is_poisoned();
     let poison = Poison(lock);
 
-    f(&OnceState(is_poisoned));
+    let result = f(&OnceState(is_poisoned));
 
     core::mem::forget(poison);
 
-    lock.mark_done();
+    if result.is_ok() {
+        lock.mark_done();
+        unpark_waiters(lock);
+    }
+
+    result
+}
+
+// threads in `wait` are parked on this key, with the address of the `Once`'s lock as their park
+// token, because the lock may already park threads on its own address
+#[cfg(feature = "parking_lot_core")]
+static WAIT_KEY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "parking_lot_core")]
+#[inline]
+fn wait_token<F: ?Sized>(lock: &F) -> parking_lot_core::ParkToken {
+    parking_lot_core::ParkToken(lock as *const F as *const () as usize)
+}
+
+// wake up all threads in `wait` after the `Once` was completed or poisoned
+#[cfg(feature = "parking_lot_core")]
+#[cold]
+fn unpark_waiters<F: ?Sized>(lock: &F) {
+    use parking_lot_core::FilterOp;
+
+    let token = wait_token(lock);
+    let filter = |park_token| {
+        if park_token == token {
+            FilterOp::Unpark
+        } else {
+            FilterOp::Skip
+        }
+    };
+    let callback = |_result| parking_lot_core::DEFAULT_UNPARK_TOKEN;
+
+    // SAFETY:
+    //   * `WAIT_KEY` is only used to park threads in `wait`
+    //   * `filter` and the callback do not panic or call into any function of `parking_lot`
+    unsafe {
+        parking_lot_core::unpark_filter(&WAIT_KEY as *const AtomicBool as usize, filter, callback);
+    }
+}
+
+#[cfg(not(feature = "parking_lot_core"))]
+#[inline]
+fn unpark_waiters<F: ?Sized>(_lock: &F) {}
+
+// park until the `Once` is completed or poisoned, or the deadline is reached
+#[cfg(feature = "parking_lot_core")]
+#[cold]
+fn park_waiter(lock: &dyn Finish, deadline: Option<std::time::Instant>) {
+    let validate = || !lock.is_done() && !lock.is_poisoned();
+
+    // SAFETY:
+    //   * `WAIT_KEY` is only used to park threads in `wait`
+    //   * `validate` does not panic or call into any function of `parking_lot`
+    //   * `before_sleep` and `timed_out` do nothing
+    unsafe {
+        parking_lot_core::park(
+            &WAIT_KEY as *const AtomicBool as usize,
+            validate,
+            || {},
+            |_, _| {},
+            wait_token(lock),
+            deadline,
+        );
+    }
 }
 
 #[cold]
@@ -125,12 +242,170 @@ fn force_call_once_slow(lock: &dyn Finish, f: &mut dyn FnMut(&OnceState)) {
     }
 }
 
+#[cold]
+#[inline(never)]
+fn try_call_once_slow<E>(
+    lock: &dyn Finish,
+    f: impl FnOnce(&OnceState) -> Result<(), E>,
+) -> Result<(), E> {
+    struct LocalGuard<'a>(&'a dyn RawExclusiveLock);
+
+    impl Drop for LocalGuard<'_> {
+        fn drop(&mut self) {
+            unsafe { self.0.exc_unlock() }
+        }
+    }
+
+    lock.exc_lock();
+    let _guard = LocalGuard(lock.as_raw_exclusive_lock());
+
+    if lock.is_done() {
+        Ok(())
+    } else {
+        try_run_once_unchecked(lock, f)
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn wait_slow(lock: &dyn Finish) {
+    #[cfg(not(feature = "parking_lot_core"))]
+    let mut backoff = crate::relax::Backoff::new();
+
+    loop {
+        // if another thread is running the initializer, then this blocks until it's done
+        lock.exc_lock();
+        let is_done = lock.is_done();
+        let is_poisoned = lock.is_poisoned();
+        unsafe { lock.exc_unlock() }
+
+        if is_done {
+            return;
+        }
+
+        if is_poisoned {
+            panic!("tried to call `wait` on a poisoned `Once`");
+        }
+
+        #[cfg(feature = "parking_lot_core")]
+        park_waiter(lock, None);
+        #[cfg(not(feature = "parking_lot_core"))]
+        backoff.snooze();
+    }
+}
+
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+fn wait_timeout_slow<L>(lock: &L, deadline: Option<std::time::Instant>) -> bool
+where
+    L: Finish + RawExclusiveLockTimed<Instant = std::time::Instant>,
+{
+    #[cfg(not(feature = "parking_lot_core"))]
+    let mut backoff = crate::relax::Backoff::new();
+
+    loop {
+        // if another thread is running the initializer, then this blocks until it's
+        // done, or the deadline is reached
+        match deadline {
+            Some(deadline) => {
+                if !lock.exc_try_lock_until(deadline) {
+                    return lock.is_done();
+                }
+            }
+            // the deadline can't be represented, so it's effectively never reached
+            None => lock.exc_lock(),
+        }
+
+        let is_done = lock.is_done();
+        let is_poisoned = lock.is_poisoned();
+        unsafe { lock.exc_unlock() }
+
+        if is_done {
+            return true;
+        }
+
+        if is_poisoned {
+            panic!("tried to call `wait_timeout` on a poisoned `Once`");
+        }
+
+        if let Some(deadline) = deadline {
+            if std::time::Instant::now() >= deadline {
+                return false;
+            }
+        }
+
+        #[cfg(feature = "parking_lot_core")]
+        park_waiter(lock, deadline);
+        #[cfg(not(feature = "parking_lot_core"))]
+        backoff.snooze();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L> Once<L>
+where
+    L: Finish + Sync + RawExclusiveLockTimed<Instant = std::time::Instant>,
+{
+    /// Blocks the current thread until another thread completes the initialization,
+    /// or the `timeout` elapses, without ever running an initializer on this thread
+    ///
+    /// The thread is parked like in [`wait`](Once::wait)
+    ///
+    /// returns true if the `Once` was completed
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the `Once` is poisoned
+    #[inline]
+    pub fn wait_timeout(&self, timeout: std::time::Duration) -> bool {
+        self.lock.is_done()
+            || wait_timeout_slow(&self.lock, std::time::Instant::now().checked_add(timeout))
+    }
+}
+
+// only another thread can complete the `Once`, so `wait` requires a lock that can be shared
+impl<L: Finish + Sync> Once<L> {
+    /// Blocks the current thread until another thread completes the initialization,
+    /// without ever running an initializer on this thread
+    ///
+    /// The thread is parked until the `Once` is completed or poisoned. Without the
+    /// `parking_lot_core` feature, it spins instead.
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the `Once` is poisoned
+    #[inline]
+    pub fn wait(&self) {
+        if !self.lock.is_done() {
+            wait_slow(&self.lock)
+        }
+    }
+}
+
 impl<L: Finish> Once<L> {
     #[inline]
     pub fn call_once(&self, f: impl FnOnce()) {
         self.force_call_once(panic_on_poison(f))
     }
 
+    /// Runs the fallible initializer if the `Once` isn't complete
+    ///
+    /// If the initializer returns an error, then the `Once` is left incomplete (not poisoned),
+    /// so that initialization can be retried later.
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the `Once` is poisoned
+    #[inline]
+    pub fn try_call_once<E>(&self, f: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+        if self.lock.is_done() {
+            Ok(())
+        } else {
+            try_call_once_slow(&self.lock, try_panic_on_poison(f))
+        }
+    }
+
     #[inline]
     pub fn call_once_mut(&mut self, f: impl FnOnce()) {
         self.force_call_once_mut(panic_on_poison(f))
@@ -187,12 +462,81 @@ impl<L: Finish + crate::Init, T> crate::Init for OnceCell<L, T> {
     };
 }
 
-#[cfg(feature = "nightly")]
 impl<L: Finish + crate::Init, T> OnceCell<L, T> {
     #[inline]
     pub const fn new() -> Self {
         crate::Init::INIT
     }
+
+    /// Takes the value out of the `OnceCell`, leaving it empty
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        core::mem::take(self).into_inner()
+    }
+}
+
+impl<L: Finish> fmt::Debug for Once<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.lock.is_done() {
+            "<done>"
+        } else if self.lock.is_poisoned() {
+            "<poisoned>"
+        } else {
+            "<incomplete>"
+        };
+
+        f.debug_struct("Once")
+            .field("state", &crate::guard::Placeholder(state))
+            .finish()
+    }
+}
+
+impl<L: Finish, T: fmt::Debug> fmt::Debug for OnceCell<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("OnceCell");
+
+        match self.get() {
+            Some(value) => f.field("value", value),
+            None => f.field("value", &crate::guard::Placeholder("<uninit>")),
+        }
+        .finish()
+    }
+}
+
+/// An empty `OnceCell` is serialized as `None`
+#[cfg(feature = "serde")]
+impl<L: Finish, T: serde::Serialize> serde::Serialize for OnceCell<L, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, L: Finish + crate::Init, T: serde::Deserialize<'de>> serde::Deserialize<'de>
+    for OnceCell<L, T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cell = Self::new();
+
+        if let Some(value) = Option::deserialize(deserializer)? {
+            let _ = cell.set(value);
+        }
+
+        Ok(cell)
+    }
+}
+
+impl<L: Finish + Sync, T> OnceCell<L, T> {
+    /// Blocks the current thread until another thread initializes the `OnceCell`
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the `OnceCell` is poisoned
+    #[inline]
+    pub fn wait(&self) -> &T {
+        self.once.wait();
+        unsafe { self.get_unchecked() }
+    }
 }
 
 impl<L: Finish, T> OnceCell<L, T> {
@@ -230,6 +574,40 @@ impl<L: Finish, T> OnceCell<L, T> {
         &mut *self.value.get().cast::<T>()
     }
 
+    /// Initializes the `OnceCell` with `value` if it is empty
+    ///
+    /// If the `OnceCell` was already initialized, then `value` is given back
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let ptr = self.value.get().cast::<T>();
+        let mut value = Some(value);
+        let slot = &mut value;
+
+        self.once
+            .force_call_once(move |_once_state| unsafe { ptr.write(slot.take().unwrap()) });
+
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    /// Consumes the `OnceCell`, and returns the value if it was initialized
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        let mut this = core::mem::ManuallyDrop::new(self);
+
+        let value = if this.once.lock.is_done() {
+            unsafe { Some(this.value.get().cast::<T>().read()) }
+        } else {
+            None
+        };
+
+        unsafe { core::ptr::drop_in_place(&mut this.once) }
+
+        value
+    }
+
     #[inline]
     pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
         let ptr = self.value.get().cast::<T>();
@@ -240,6 +618,31 @@ impl<L: Finish, T> OnceCell<L, T> {
         unsafe { &*ptr }
     }
 
+    /// Gets the value, or initializes it with the fallible initializer if the `OnceCell` is empty
+    ///
+    /// If the initializer returns an error, then the `OnceCell` is left empty,
+    /// so that initialization can be retried later.
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the `OnceCell` is poisoned
+    #[inline]
+    pub fn get_or_try_init<E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<&T, E> {
+        let ptr = self.value.get().cast::<T>();
+
+        if !self.once.lock.is_done() {
+            try_call_once_slow(
+                &self.once.lock,
+                try_panic_on_poison(move || {
+                    unsafe { ptr.write(f()?) }
+                    Ok(())
+                }),
+            )?;
+        }
+
+        unsafe { Ok(&*ptr) }
+    }
+
     #[inline]
     pub fn get_or_init_mut(&mut self, f: impl FnOnce() -> T) -> &mut T {
         let ptr = self.value.get().cast::<T>();
@@ -270,65 +673,174 @@ impl<L: Finish, T> OnceCell<L, T> {
     }
 }
 
-enum LazyInner<F, T> {
-    Func(F),
+// which field is initialized is tracked by the `Once`
+//
+// * if it's done, then `value` is initialized
+// * if it isn't poisoned, then `func` is initialized
+// * if it's poisoned, then `func` is initialized only if the strategy keeps the
+//   initializer when it panics
+union LazyInner<F, T> {
+    func: ManuallyDrop<F>,
+    value: ManuallyDrop<T>,
+}
+
+/// The [`Lazy`] strategy which consumes the initializer, so if it panics the `Lazy` is poisoned,
+/// and every later access panics
+pub struct Panic;
+
+/// The [`Strategy`] which keeps the initializer if it panics, and calls it again on the next access
+///
+/// The initializer is given the [`OnceState`], so it can check if a previous call panicked,
+/// for example to return a fallback value instead of trying again.
+pub struct Retry;
+
+/// The [`Strategy`] which initializes the [`Lazy`] with a clone of the fallback value
+/// if the initializer panics
+pub struct Fallback<T>(pub T);
+
+/// What a [`Lazy`] does after its initializer panicked, decided by [`Strategy::on_panic`]
+#[derive(Debug)]
+pub enum Recovery<T> {
+    /// Continue unwinding, which poisons the `Lazy`
+    ///
+    /// The initializer is kept, and it will be called again on the next access
+    Resume,
+    /// Call the initializer again right away
+    Retry,
+    /// Initialize the `Lazy` with this value
     Value(T),
-    Empty,
 }
 
-pub enum Panic {}
-pub enum Retry {}
+mod seal {
+    pub trait Seal {
+        // true if the initializer is still valid after it panics
+        const KEEPS_FUNC: bool;
+    }
+}
+
+/// A strategy which keeps the initializer of a [`Lazy`] if it panics
+///
+/// This must be implemented for every [`Strategy`]. It's a separate trait because `Strategy`
+/// is generic over the value, so otherwise other crates could implement `Strategy` for
+/// [`Panic`], which consumes its initializer.
+pub trait KeepsInit {}
+
+/// The strategy that a [`Lazy`] uses if the initializer panics
+///
+/// A `Lazy` either uses [`Panic`], which consumes the initializer, or a `Strategy`, which keeps
+/// the initializer (an `FnMut(&OnceState) -> T`), and decides what to do each time it panics.
+/// The strategy is stored in the `Lazy`, so it can keep state between failures, for example to
+/// count the attempts, or to back off before retrying.
+///
+/// Panics can only be caught with the `std` feature. Without it, `on_panic` is never called,
+/// and every panic is resumed.
+pub trait Strategy<T>: KeepsInit {
+    /// Called after the initializer panicked with `payload`
+    ///
+    /// The initializer is called again with a poisoned [`OnceState`] when it is retried
+    fn on_panic(&mut self, payload: &(dyn Any + Send)) -> Recovery<T>;
+}
+
+impl KeepsInit for Retry {}
+impl<T> KeepsInit for Fallback<T> {}
+
+impl<T> Strategy<T> for Retry {
+    #[inline]
+    fn on_panic(&mut self, _payload: &(dyn Any + Send)) -> Recovery<T> {
+        Recovery::Resume
+    }
+}
+
+impl<T: Clone> Strategy<T> for Fallback<T> {
+    #[inline]
+    fn on_panic(&mut self, _payload: &(dyn Any + Send)) -> Recovery<T> {
+        Recovery::Value(self.0.clone())
+    }
+}
 
-pub struct Lazy<L, T, F, S> {
+impl seal::Seal for Panic {
+    const KEEPS_FUNC: bool = false;
+}
+
+impl<S: KeepsInit> seal::Seal for S {
+    const KEEPS_FUNC: bool = true;
+}
+
+pub struct Lazy<L: Finish, T, F, S: seal::Seal> {
     once: Once<L>,
     inner: UnsafeCell<LazyInner<F, T>>,
-    strategy: PhantomData<S>,
+    // only accessed while initializing
+    strategy: UnsafeCell<S>,
 }
 
-unsafe impl<L, F: Send + Sync, T: Send + Sync, S> Sync for Lazy<L, T, F, S> where Once<L>: Sync {}
+unsafe impl<L: Finish, F: Send + Sync, T: Send + Sync, S: Send + seal::Seal> Sync
+    for Lazy<L, T, F, S>
+where
+    Once<L>: Sync,
+{
+}
 
-impl<L: Finish + crate::Init, T, F: FnOnce() -> T> Lazy<L, T, F, Panic> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            #[inline]
-            pub const fn new(func: F) -> Self {
-                unsafe { Self::from_raw_parts(crate::Init::INIT, func) }
-            }
-        } else {
-            #[inline]
-            pub fn new(func: F) -> Self {
-                unsafe { Self::from_raw_parts(crate::Init::INIT, func) }
+impl<L: Finish, T, F, S: seal::Seal> Drop for Lazy<L, T, F, S> {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+
+        unsafe {
+            if self.once.lock.is_done() {
+                ManuallyDrop::drop(&mut inner.value)
+            } else if S::KEEPS_FUNC || !self.once.lock.is_poisoned() {
+                ManuallyDrop::drop(&mut inner.func)
             }
         }
     }
 }
 
-impl<L: Finish + crate::Init, T, F: FnMut() -> T> Lazy<L, T, F, Retry> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            #[inline]
-            pub const fn new_retry(func: F) -> Self {
-                unsafe { Self::from_raw_parts(crate::Init::INIT, func) }
-            }
+impl<L: Finish, T: fmt::Debug, F, S: seal::Seal> fmt::Debug for Lazy<L, T, F, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Lazy");
+
+        if self.once.lock.is_done() {
+            f.field("value", unsafe { Self::get_unchecked(self) })
         } else {
-            #[inline]
-            pub fn new_retry(func: F) -> Self {
-                unsafe { Self::from_raw_parts(crate::Init::INIT, func) }
-            }
+            f.field("value", &crate::guard::Placeholder("<uninit>"))
         }
+        .finish()
     }
 }
 
-impl<L, F, T, S> Lazy<L, T, F, S> {
+impl<L: Finish + crate::Init, T, F: FnOnce() -> T> Lazy<L, T, F, Panic> {
+    #[inline]
+    pub const fn new(func: F) -> Self {
+        unsafe { Self::from_raw_parts(crate::Init::INIT, func, Panic) }
+    }
+}
+
+impl<L: Finish + crate::Init, T, F: FnMut(&OnceState) -> T> Lazy<L, T, F, Retry> {
+    #[inline]
+    pub const fn new_retry(func: F) -> Self {
+        unsafe { Self::from_raw_parts(crate::Init::INIT, func, Retry) }
+    }
+}
+
+impl<L: Finish + crate::Init, T, F: FnMut(&OnceState) -> T, S: Strategy<T>> Lazy<L, T, F, S> {
+    /// Create a new `Lazy` which uses `strategy` if the initializer panics
+    #[inline]
+    pub const fn with_strategy(func: F, strategy: S) -> Self {
+        unsafe { Self::from_raw_parts(crate::Init::INIT, func, strategy) }
+    }
+}
+
+impl<L: Finish, F, T, S: seal::Seal> Lazy<L, T, F, S> {
     /// # Safety
     ///
     /// * `once` must be a freshly created `Once`
     #[inline]
-    pub const unsafe fn from_raw_parts(once: Once<L>, func: F) -> Self {
+    pub const unsafe fn from_raw_parts(once: Once<L>, func: F, strategy: S) -> Self {
         Self {
             once,
-            strategy: PhantomData,
-            inner: UnsafeCell::new(LazyInner::Func(func)),
+            strategy: UnsafeCell::new(strategy),
+            inner: UnsafeCell::new(LazyInner {
+                func: ManuallyDrop::new(func),
+            }),
         }
     }
 
@@ -336,29 +848,64 @@ impl<L, F, T, S> Lazy<L, T, F, S> {
     ///
     /// `Lazy::force` or `Lazy::force_mut` mut have been called before this
     #[inline]
-    #[allow(unreachable_code)]
     pub unsafe fn get_unchecked(this: &Self) -> &T {
-        if let LazyInner::Value(ref value) = *this.inner.get() {
-            value
-        } else {
-            #[cfg(debug_assertions)]
-            unreachable!("soundness hole");
-            core::hint::unreachable_unchecked()
-        }
+        &(*this.inner.get()).value
     }
 
     /// # Safety
     ///
     /// `Lazy::force` or `Lazy::force_mut` mut have been called before this
     #[inline]
-    #[allow(unreachable_code)]
     pub unsafe fn get_unchecked_mut(this: &mut Self) -> &mut T {
-        if let LazyInner::Value(ref mut value) = *this.inner.get() {
-            value
+        &mut this.inner.get_mut().value
+    }
+
+    /// Gets the value if the `Lazy` was already forced
+    #[inline]
+    pub fn get(this: &Self) -> Option<&T> {
+        if this.once.lock.is_done() {
+            unsafe { Some(Self::get_unchecked(this)) }
         } else {
-            #[cfg(debug_assertions)]
-            unreachable!("soundness hole");
-            core::hint::unreachable_unchecked()
+            None
+        }
+    }
+
+    /// Gets a mutable reference to the value if the `Lazy` was already forced
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if this.once.lock.is_done() {
+            unsafe { Some(Self::get_unchecked_mut(this)) }
+        } else {
+            None
+        }
+    }
+
+    /// Consumes the `Lazy`, and returns the value if it was forced, or the initializer otherwise
+    ///
+    /// # Panic
+    ///
+    /// This function panics if the initializer panicked, and consumed itself
+    #[inline]
+    pub fn into_value(this: Self) -> Result<T, F> {
+        let mut this = ManuallyDrop::new(this);
+        let inner = unsafe { this.inner.get().read() };
+
+        let is_done = this.once.lock.is_done();
+        let has_func = S::KEEPS_FUNC || !this.once.lock.is_poisoned();
+
+        unsafe {
+            core::ptr::drop_in_place(&mut this.once);
+            core::ptr::drop_in_place(this.strategy.get());
+        }
+
+        unsafe {
+            if is_done {
+                Ok(ManuallyDrop::into_inner(inner.value))
+            } else if has_func {
+                Err(ManuallyDrop::into_inner(inner.func))
+            } else {
+                panic!("tried to call `into_value` on a poisoned `Lazy`")
+            }
         }
     }
 }
@@ -368,13 +915,10 @@ impl<L: Finish, F: FnOnce() -> T, T> Lazy<L, T, F, Panic> {
     pub fn force(this: &Self) -> &T {
         let inner = this.inner.get();
 
-        this.once.call_once(move || {
-            let inner = unsafe { &mut *inner };
-            let func = core::mem::replace(inner, LazyInner::Empty);
-
-            if let LazyInner::Func(func) = func {
-                *inner = LazyInner::Value(func());
-            }
+        this.once.call_once(move || unsafe {
+            let inner = &mut *inner;
+            let func = ManuallyDrop::take(&mut inner.func);
+            inner.value = ManuallyDrop::new(func());
         });
 
         unsafe { Self::get_unchecked(this) }
@@ -384,47 +928,85 @@ impl<L: Finish, F: FnOnce() -> T, T> Lazy<L, T, F, Panic> {
     pub fn force_mut(this: &mut Self) -> &mut T {
         let inner = this.inner.get();
 
-        this.once.call_once(move || {
-            let inner = unsafe { &mut *inner };
-            let func = core::mem::replace(inner, LazyInner::Empty);
-
-            if let LazyInner::Func(func) = func {
-                *inner = LazyInner::Value(func());
-            }
+        this.once.call_once_mut(move || unsafe {
+            let inner = &mut *inner;
+            let func = ManuallyDrop::take(&mut inner.func);
+            inner.value = ManuallyDrop::new(func());
         });
 
         unsafe { Self::get_unchecked_mut(this) }
     }
 }
 
-impl<L: Finish, F: FnMut(&OnceState) -> T, T> Lazy<L, T, F, Retry> {
+// calls the initializer, and replaces it with the value it returns
+//
+// the initializer is returned so that it can be dropped after the `Once` is marked as done,
+// otherwise if it panics while being dropped the `Once` would be poisoned with the value
+// in place of the initializer
+unsafe fn retry<S: Strategy<T>, F: FnMut(&OnceState) -> T, T>(
+    inner: &mut LazyInner<F, T>,
+    strategy: &mut S,
+    once_state: &OnceState,
+) -> F {
+    #[cfg(feature = "std")]
+    let value = {
+        use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+        let mut is_poisoned = once_state.is_poisoned();
+
+        loop {
+            let func = &mut *inner.func;
+
+            match catch_unwind(AssertUnwindSafe(|| func(&OnceState(is_poisoned)))) {
+                Ok(value) => break value,
+                Err(payload) => match strategy.on_panic(&*payload) {
+                    Recovery::Resume => resume_unwind(payload),
+                    Recovery::Retry => is_poisoned = true,
+                    Recovery::Value(value) => break value,
+                },
+            }
+        }
+    };
+
+    #[cfg(not(feature = "std"))]
+    let value = {
+        let _ = strategy;
+        (*inner.func)(once_state)
+    };
+
+    let func = ManuallyDrop::take(&mut inner.func);
+    inner.value = ManuallyDrop::new(value);
+    func
+}
+
+impl<L: Finish, F: FnMut(&OnceState) -> T, T, S: Strategy<T>> Lazy<L, T, F, S> {
     #[inline]
     pub fn force(this: &Self) -> &T {
         let inner = this.inner.get();
+        let strategy = this.strategy.get();
+        let mut func = None;
 
-        this.once.force_call_once(move |once_state| {
-            let inner = unsafe { &mut *inner };
-
-            if let LazyInner::Func(ref mut func) = *inner {
-                *inner = LazyInner::Value(func(once_state));
-            }
+        this.once.force_call_once(|once_state| unsafe {
+            func = Some(retry(&mut *inner, &mut *strategy, once_state));
         });
 
+        drop(func);
+
         unsafe { Self::get_unchecked(this) }
     }
 
     #[inline]
     pub fn force_mut(this: &mut Self) -> &mut T {
         let inner = this.inner.get();
+        let strategy = this.strategy.get();
+        let mut func = None;
 
-        this.once.force_call_once_mut(move |once_state| {
-            let inner = unsafe { &mut *inner };
-
-            if let LazyInner::Func(ref mut func) = *inner {
-                *inner = LazyInner::Value(func(once_state));
-            }
+        this.once.force_call_once_mut(|once_state| unsafe {
+            func = Some(retry(&mut *inner, &mut *strategy, once_state));
         });
 
+        drop(func);
+
         unsafe { Self::get_unchecked_mut(this) }
     }
 }
@@ -445,7 +1027,7 @@ impl<L: Finish, F: FnOnce() -> T, T> DerefMut for Lazy<L, T, F, Panic> {
     }
 }
 
-impl<L: Finish, F: FnMut(&OnceState) -> T, T> Deref for Lazy<L, T, F, Retry> {
+impl<L: Finish, F: FnMut(&OnceState) -> T, T, S: Strategy<T>> Deref for Lazy<L, T, F, S> {
     type Target = T;
 
     #[inline]
@@ -454,7 +1036,7 @@ impl<L: Finish, F: FnMut(&OnceState) -> T, T> Deref for Lazy<L, T, F, Retry> {
     }
 }
 
-impl<L: Finish, F: FnMut(&OnceState) -> T, T> DerefMut for Lazy<L, T, F, Retry> {
+impl<L: Finish, F: FnMut(&OnceState) -> T, T, S: Strategy<T>> DerefMut for Lazy<L, T, F, S> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
         Self::force_mut(self)
@@ -462,6 +1044,9 @@ impl<L: Finish, F: FnMut(&OnceState) -> T, T> DerefMut for Lazy<L, T, F, Retry>
 }
 
 pub struct RacyLazy<L: Finish, T, F = fn() -> T> {
+    // set after the value is published, so that reading an initialized
+    // `RacyLazy` is a single load, and never touches the lock
+    ready: AtomicBool,
     once: OnceCell<L, T>,
     func: F,
 }
@@ -469,18 +1054,25 @@ pub struct RacyLazy<L: Finish, T, F = fn() -> T> {
 unsafe impl<L: Finish, F: Send + Sync, T: Send + Sync> Sync for RacyLazy<L, T, F> where Once<L>: Sync
 {}
 
+impl<L: Finish, T: fmt::Debug, F> fmt::Debug for RacyLazy<L, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("RacyLazy");
+
+        match self.once.get() {
+            Some(value) => f.field("value", value),
+            None => f.field("value", &crate::guard::Placeholder("<uninit>")),
+        }
+        .finish()
+    }
+}
+
 impl<L: Finish + crate::Init, T, F: Fn() -> T> RacyLazy<L, T, F> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            #[inline]
-            pub const fn new(func: F) -> Self {
-                Self { once: crate::Init::INIT, func }
-            }
-        } else {
-            #[inline]
-            pub fn new(func: F) -> Self {
-                Self { once: crate::Init::INIT, func }
-            }
+    #[inline]
+    pub const fn new(func: F) -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            once: crate::Init::INIT,
+            func,
         }
     }
 }
@@ -508,12 +1100,26 @@ impl<L: Finish, F, T> RacyLazy<L, T, F> {
 impl<L: Finish, F: Fn() -> T, T> RacyLazy<L, T, F> {
     #[inline]
     pub fn force(this: &Self) -> &T {
-        this.once.get_or_init_racy(&this.func)
+        if this.ready.load(Ordering::Acquire) {
+            unsafe { this.once.get_unchecked() }
+        } else {
+            Self::force_slow(this)
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn force_slow(this: &Self) -> &T {
+        let value = this.once.get_or_init_racy(&this.func);
+        this.ready.store(true, Ordering::Release);
+        value
     }
 
     #[inline]
     pub fn force_mut(this: &mut Self) -> &mut T {
-        this.once.get_or_init_mut(&this.func)
+        let value = this.once.get_or_init_mut(&this.func);
+        *this.ready.get_mut() = true;
+        value
     }
 }
 
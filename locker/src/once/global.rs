@@ -0,0 +1,76 @@
+use core::any::{Any, TypeId};
+use core::hash::{Hash, Hasher};
+use std::boxed::Box;
+use std::collections::hash_map::DefaultHasher;
+use std::vec::Vec;
+
+use super::sync::{OnceCell, RawLock};
+use crate::mutex::default::{DefaultLock, Mutex};
+
+struct Entry {
+    type_id: TypeId,
+    cell: &'static (dyn Any + Send + Sync),
+}
+
+const SHARDS: usize = 16;
+
+// the shards have their own locks, rather than locks from the global lock table, because
+// a caller may hold a global mutex, which could share a lock with its shard
+#[allow(clippy::declare_interior_mutable_const)]
+const SHARD: Mutex<Vec<Entry>> = DefaultLock::mutex(Vec::new());
+static REGISTRY: [Mutex<Vec<Entry>>; SHARDS] = [SHARD; SHARDS];
+
+/// Get the process-wide `OnceCell` for the type `T`
+///
+/// All calls with the same `T` return the same `OnceCell`, so this can be used to lazily
+/// register exactly one instance of a type without declaring a `static` for it.
+///
+/// The `OnceCell`s are never freed, so this should only be used with a bounded set of types.
+pub fn global_once<T: Send + Sync + 'static>() -> &'static OnceCell<T> {
+    let type_id = TypeId::of::<T>();
+
+    let mut hasher = DefaultHasher::new();
+    type_id.hash(&mut hasher);
+    let shard = &REGISTRY[hasher.finish() as usize % SHARDS];
+
+    let mut shard = shard.lock();
+
+    let cell = match shard.iter().find(|entry| entry.type_id == type_id) {
+        Some(entry) => entry.cell,
+        None => {
+            let cell: &'static OnceCell<T> = Box::leak(Box::new(RawLock::once_cell()));
+            shard.push(Entry { type_id, cell });
+            cell
+        }
+    };
+
+    match cell.downcast_ref() {
+        Some(cell) => cell,
+        None => unreachable!(),
+    }
+}
+
+#[test]
+fn test_global_once() {
+    struct Registry(u32);
+
+    assert!(global_once::<Registry>().get().is_none());
+    global_once::<Registry>().get_or_init(|| Registry(10));
+    assert_eq!(global_once::<Registry>().get().map(|r| r.0), Some(10));
+    assert!(global_once::<u8>().get().is_none());
+
+    crossbeam_utils::thread::scope(|s| {
+        for i in 0..4 {
+            s.spawn(move |_| global_once::<(Registry, u8)>().get_or_init(|| (Registry(i), 0)));
+        }
+    })
+    .unwrap();
+
+    assert!(global_once::<(Registry, u8)>().get().is_some());
+
+    // holding global locks must not block the registry
+    use crate::mutex::global::GlobalLock;
+    let globals: [_; 64] = core::array::from_fn(GlobalLock::mutex);
+    let _guards: std::vec::Vec<_> = globals.iter().filter_map(|m| m.try_lock()).collect();
+    assert!(global_once::<u16>().get().is_none());
+}
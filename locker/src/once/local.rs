@@ -6,7 +6,8 @@ pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
 pub type Once = crate::once::Once<RawLock>;
 pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
 pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
-pub type RertyLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RertyLazy<T, F = fn(&crate::once::OnceState) -> T> =
+    crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
 pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
 
 pub struct RawLock {
@@ -45,15 +46,16 @@ impl RawLock {
     }
 
     pub const fn lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+        unsafe { Lazy::from_raw_parts(Self::once(), func, crate::once::Panic) }
     }
 
     pub const fn retry_lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+        unsafe { Lazy::from_raw_parts(Self::once(), func, crate::once::Panic) }
     }
 
     pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
         RacyLazy {
+            ready: core::sync::atomic::AtomicBool::new(false),
             once: Self::once_cell(),
             func,
         }
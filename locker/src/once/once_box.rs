@@ -0,0 +1,150 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::boxed::Box;
+
+/// A cell which can be written to only once, and stores its value on the heap
+///
+/// The value is published with a single atomic pointer, so reads never touch a lock.
+/// Because the value is boxed, `T` may be unsized, like `dyn Trait` or `[T]`.
+///
+/// Initialization is racy, if multiple threads try to initialize the `OnceBox` at the
+/// same time, then all of them may run their initializer, but only one value is kept.
+pub struct OnceBox<T: ?Sized> {
+    // a thin pointer to the box, so that `T` can be unsized
+    ptr: AtomicPtr<Box<T>>,
+    _value: PhantomData<Box<T>>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for OnceBox<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for OnceBox<T> {}
+
+impl<T: ?Sized> Drop for OnceBox<T> {
+    fn drop(&mut self) {
+        let ptr = *self.ptr.get_mut();
+
+        if !ptr.is_null() {
+            drop(unsafe { Box::from_raw(ptr) })
+        }
+    }
+}
+
+impl<T: ?Sized> Default for OnceBox<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for OnceBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("OnceBox");
+
+        match self.get() {
+            Some(value) => f.field("value", &value),
+            None => f.field("value", &crate::guard::Placeholder("<uninit>")),
+        }
+        .finish()
+    }
+}
+
+impl<T: ?Sized> OnceBox<T> {
+    /// Create a new empty `OnceBox`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            _value: PhantomData,
+        }
+    }
+
+    /// Gets the value if the `OnceBox` was initialized
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        let ptr = self.ptr.load(Ordering::Acquire);
+
+        if ptr.is_null() {
+            None
+        } else {
+            unsafe { Some(&**ptr) }
+        }
+    }
+
+    /// Gets a mutable reference to the value if the `OnceBox` was initialized
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let ptr = *self.ptr.get_mut();
+
+        if ptr.is_null() {
+            None
+        } else {
+            unsafe { Some(&mut **ptr) }
+        }
+    }
+
+    /// Initializes the `OnceBox` with `value` if it is empty
+    ///
+    /// If the `OnceBox` was already initialized, then `value` is given back
+    pub fn set(&self, value: Box<T>) -> Result<(), Box<T>> {
+        let ptr = Box::into_raw(Box::new(value));
+
+        match self
+            .ptr
+            .compare_exchange(ptr::null_mut(), ptr, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(*unsafe { Box::from_raw(ptr) }),
+        }
+    }
+
+    /// Gets the value, or initializes it with `f` if the `OnceBox` is empty
+    ///
+    /// If another thread initializes the `OnceBox` while `f` is running, then the value
+    /// returned by `f` is dropped, and the other thread's value is returned
+    #[inline]
+    pub fn get_or_init(&self, f: impl FnOnce() -> Box<T>) -> &T {
+        match self.get() {
+            Some(value) => value,
+            None => self.init(f()),
+        }
+    }
+
+    #[cold]
+    fn init(&self, value: Box<T>) -> &T {
+        let _ = self.set(value);
+
+        match self.get() {
+            Some(value) => value,
+            None => unreachable!(),
+        }
+    }
+
+    /// Consumes the `OnceBox`, and returns the value if it was initialized
+    #[inline]
+    pub fn into_inner(mut self) -> Option<Box<T>> {
+        let ptr = core::mem::replace(self.ptr.get_mut(), ptr::null_mut());
+
+        if ptr.is_null() {
+            None
+        } else {
+            Some(*unsafe { Box::from_raw(ptr) })
+        }
+    }
+}
+
+#[test]
+fn test_once_box() {
+    use std::fmt::Display;
+    use std::string::ToString;
+
+    let cell = OnceBox::<dyn Display>::new();
+    assert!(cell.get().is_none());
+    assert_eq!(cell.get_or_init(|| Box::new(10)).to_string(), "10");
+    assert_eq!(cell.get_or_init(|| Box::new("hello")).to_string(), "10");
+    assert!(cell.set(Box::new(0)).is_err());
+
+    let cell = OnceBox::<[u8]>::new();
+    assert!(cell.set(Box::new([1, 2, 3])).is_ok());
+    assert_eq!(cell.into_inner().as_deref(), Some(&[1, 2, 3][..]));
+}
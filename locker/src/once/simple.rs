@@ -1,4 +1,4 @@
-use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair};
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair, RawExclusiveLockTimed};
 use crate::mutex::tagged::TaggedLock as Tagged;
 use core::sync::atomic::Ordering;
 
@@ -7,7 +7,8 @@ pub type Mutex<T> = crate::mutex::Mutex<RawLock, T>;
 pub type Once = crate::once::Once<RawLock>;
 pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
 pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
-pub type RertyLazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RertyLazy<T, F = fn(&crate::once::OnceState) -> T> =
+    crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
 pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
 
 pub struct RawLock {
@@ -68,15 +69,16 @@ impl RawLock {
     }
 
     pub const fn lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+        unsafe { Lazy::from_raw_parts(Self::once(), func, crate::once::Panic) }
     }
 
     pub const fn retry_lazy<T, F>(func: F) -> Lazy<T, F> {
-        unsafe { Lazy::from_raw_parts(Self::once(), func) }
+        unsafe { Lazy::from_raw_parts(Self::once(), func, crate::once::Panic) }
     }
 
     pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
         RacyLazy {
+            ready: core::sync::atomic::AtomicBool::new(false),
             once: Self::once_cell(),
             func,
         }
@@ -125,3 +127,315 @@ unsafe impl RawExclusiveLockFair for RawLock {
         self.inner.exc_bump_fair();
     }
 }
+
+impl crate::RawTimedLock for RawLock {
+    type Instant = <Tagged as crate::RawTimedLock>::Instant;
+    type Duration = <Tagged as crate::RawTimedLock>::Duration;
+}
+
+unsafe impl RawExclusiveLockTimed for RawLock {
+    #[inline]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.inner.exc_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.inner.exc_try_lock_for(duration)
+    }
+}
+
+#[test]
+fn test_wait() {
+    use std::sync::atomic::AtomicBool;
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    let cell = RawLock::once_cell::<u32>();
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            while !STARTED.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+
+            assert_eq!(*cell.wait(), 10);
+        });
+
+        s.spawn(|_| {
+            cell.get_or_init(|| {
+                STARTED.store(true, Ordering::Release);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                10
+            });
+        });
+    })
+    .unwrap();
+
+    assert_eq!(cell.get(), Some(&10));
+}
+
+#[test]
+fn test_wait_before_init() {
+    let cell = RawLock::once_cell::<u32>();
+    let once = RawLock::once();
+
+    crossbeam_utils::thread::scope(|s| {
+        // nothing is running the initializer yet, so these threads park
+        s.spawn(|_| assert_eq!(*cell.wait(), 10));
+        s.spawn(|_| {
+            let waited = std::panic::catch_unwind(|| once.wait());
+            assert!(waited.is_err());
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cell.get_or_init(|| 10);
+        let _ = std::panic::catch_unwind(|| once.call_once(|| panic!()));
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_try_init() {
+    let once = RawLock::once();
+
+    assert_eq!(once.try_call_once(|| Err(0)), Err(0));
+    assert_eq!(format!("{:?}", once), "Once { state: <incomplete> }");
+    assert_eq!(once.try_call_once(|| Ok::<_, i32>(())), Ok(()));
+    assert_eq!(once.try_call_once(|| Err(1)), Ok(()));
+
+    let cell = RawLock::once_cell::<u32>();
+
+    assert_eq!(cell.get_or_try_init(|| Err(())), Err(()));
+    assert_eq!(cell.get(), None);
+    assert_eq!(cell.get_or_try_init(|| Ok::<_, ()>(10)), Ok(&10));
+    assert_eq!(cell.get_or_try_init(|| Err(())), Ok(&10));
+
+    let cell = RawLock::once_cell::<u32>();
+    let cell = std::panic::AssertUnwindSafe(&cell);
+    let _ = std::panic::catch_unwind(|| cell.get_or_init(|| panic!()));
+    assert!(std::panic::catch_unwind(|| cell.get_or_try_init(|| Ok::<_, ()>(10))).is_err());
+}
+
+#[test]
+fn test_once_cell_set() {
+    let mut cell = RawLock::once_cell::<String>();
+
+    assert_eq!(cell.take(), None);
+    assert_eq!(cell.set(String::from("a")), Ok(()));
+    assert_eq!(cell.set(String::from("b")), Err(String::from("b")));
+    assert_eq!(cell.get_mut().map(|s| s.as_str()), Some("a"));
+
+    assert_eq!(cell.take().as_deref(), Some("a"));
+    assert_eq!(cell.get(), None);
+
+    assert_eq!(cell.set(String::from("c")), Ok(()));
+    assert_eq!(cell.into_inner().as_deref(), Some("c"));
+}
+
+#[test]
+fn test_lazy_get_mut() {
+    let mut lazy = RawLock::lazy(|| vec![1, 2]);
+
+    assert_eq!(Lazy::get(&lazy), None);
+    assert_eq!(Lazy::get_mut(&mut lazy), None);
+
+    Lazy::force_mut(&mut lazy).push(3);
+    Lazy::get_mut(&mut lazy).unwrap().push(4);
+    assert_eq!(Lazy::get(&lazy).map(Vec::as_slice), Some(&[1, 2, 3, 4][..]));
+}
+
+#[test]
+fn test_lazy_into_value() {
+    let lazy: Lazy<i32, _> = RawLock::lazy(|| 10);
+    let func = Lazy::into_value(lazy).unwrap_err();
+    assert_eq!(func(), 10);
+
+    let lazy = RawLock::lazy(|| 20);
+    assert_eq!(*lazy, 20);
+    assert_eq!(Lazy::into_value(lazy).ok(), Some(20));
+}
+
+#[test]
+fn test_lazy_layout() {
+    use std::rc::Rc;
+
+    use core::mem::size_of;
+
+    // the value and the initializer share storage, so there is no extra discriminant
+    assert_eq!(
+        size_of::<Lazy<u64>>(),
+        size_of::<(Once, u64)>().max(size_of::<(Once, fn() -> u64)>())
+    );
+
+    let name = String::from("lazy");
+    let lazy: Lazy<String, _> = RawLock::lazy(move || name);
+    assert_eq!(*lazy, "lazy");
+
+    // the initializer is dropped exactly once, whether or not it ran
+    let counter = Rc::new(());
+    let captured = counter.clone();
+    let lazy: Lazy<usize, _> = RawLock::lazy(move || Rc::strong_count(&captured));
+    assert_eq!(Rc::strong_count(&counter), 2);
+    drop(lazy);
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let captured = counter.clone();
+    let lazy: Lazy<usize, _> = RawLock::lazy(move || Rc::strong_count(&captured));
+    assert_eq!(*lazy, 2);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_retry_lazy_after_panic() {
+    use crate::once::{OnceState, Retry};
+
+    let mut calls = 0;
+    let lazy = unsafe {
+        crate::once::Lazy::from_raw_parts(
+            RawLock::once(),
+            |_: &OnceState| {
+                calls += 1;
+                if calls == 1 {
+                    panic!()
+                }
+                calls
+            },
+            Retry,
+        )
+    };
+
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *lazy)).is_err());
+    assert_eq!(*lazy, 2);
+}
+
+#[test]
+fn test_retry_strategy() {
+    use crate::once::{Fallback, KeepsInit, OnceState, Recovery, Strategy};
+    use core::any::Any;
+
+    // retry twice, and then give up with the panic message
+    struct Attempts(u32);
+
+    impl KeepsInit for Attempts {}
+
+    impl Strategy<String> for Attempts {
+        fn on_panic(&mut self, payload: &(dyn Any + Send)) -> Recovery<String> {
+            self.0 += 1;
+
+            match payload.downcast_ref::<&str>() {
+                Some(message) if self.0 > 2 => Recovery::Value(message.to_string()),
+                _ => Recovery::Retry,
+            }
+        }
+    }
+
+    let mut calls = 0;
+    let lazy = crate::once::Lazy::<RawLock, _, _, _>::with_strategy(
+        |once_state: &OnceState| {
+            calls += 1;
+            assert_eq!(once_state.is_poisoned(), calls > 1);
+            panic!("unavailable")
+        },
+        Attempts(0),
+    );
+
+    assert_eq!(*lazy, "unavailable");
+    drop(lazy);
+    assert_eq!(calls, 3);
+
+    let lazy = crate::once::Lazy::<RawLock, _, _, _>::with_strategy(
+        |_: &OnceState| -> u32 { panic!() },
+        Fallback(10),
+    );
+
+    assert_eq!(*lazy, 10);
+}
+
+#[test]
+fn test_sync_once() {
+    static CELL: super::sync::OnceCell<u32> = RawLock::once_cell();
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                let value = CELL.get_or_init(|| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    10
+                });
+                assert_eq!(*value, 10);
+            });
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_racy_lazy() {
+    use std::sync::atomic::AtomicUsize;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: RacyLazy<usize> = RawLock::racy_lazy(|| CALLS.fetch_add(1, Ordering::Relaxed));
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                let value = *LAZY;
+                for _ in 0..100 {
+                    assert_eq!(*LAZY, value);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let calls = CALLS.load(Ordering::Relaxed);
+    assert!(calls >= 1);
+    assert!(*LAZY < calls);
+
+    let mut lazy = RawLock::racy_lazy(|| vec![1]);
+    RacyLazy::force_mut(&mut lazy).push(2);
+    assert_eq!(*lazy, [1, 2]);
+}
+
+#[test]
+fn test_wait_timeout() {
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    let once = RawLock::once();
+    assert!(!once.wait_timeout(Duration::from_millis(1)));
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            once.call_once(|| {
+                STARTED.store(true, Ordering::Release);
+                std::thread::sleep(Duration::from_millis(100));
+            });
+        });
+
+        while !STARTED.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+
+        assert!(!once.wait_timeout(Duration::from_millis(1)));
+        assert!(once.wait_timeout(Duration::from_secs(10)));
+    })
+    .unwrap();
+
+    assert!(once.wait_timeout(Duration::from_secs(0)));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde() {
+    let cell = RawLock::once_cell::<u32>();
+    assert_eq!(serde_json::to_string(&cell).unwrap(), "null");
+    cell.set(10).unwrap();
+    assert_eq!(serde_json::to_string(&cell).unwrap(), "10");
+
+    let cell: OnceCell<u32> = serde_json::from_str("10").unwrap();
+    assert_eq!(cell.get(), Some(&10));
+    let cell: OnceCell<u32> = serde_json::from_str("null").unwrap();
+    assert_eq!(cell.get(), None);
+}
@@ -0,0 +1,184 @@
+//! `Once` types whose completion is tracked by a [`std::sync::Once`]
+//!
+//! Exclusion and poisoning are handled by a parking [`TaggedLock`](crate::mutex::tagged::TaggedLock),
+//! but the `Once` is only marked as done by completing the inner `std::sync::Once`. This allows
+//! state to be moved between `std` and `locker` with [`RawLock::from_std`] and [`RawLock::into_std`],
+//! while still using `locker`'s `Lazy`, `OnceCell`, and strategies.
+
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair, RawExclusiveLockTimed};
+use crate::mutex::tagged::TaggedLock as Tagged;
+use core::sync::atomic::Ordering;
+
+pub type Once = crate::once::Once<RawLock>;
+pub type OnceCell<T> = crate::once::OnceCell<RawLock, T>;
+pub type Lazy<T, F = fn() -> T> = crate::once::Lazy<RawLock, T, F, crate::once::Panic>;
+pub type RertyLazy<T, F = fn(&crate::once::OnceState) -> T> =
+    crate::once::Lazy<RawLock, T, F, crate::once::Retry>;
+pub type RacyLazy<T, F = fn() -> T> = crate::once::RacyLazy<RawLock, T, F>;
+
+pub struct RawLock {
+    once: std::sync::Once,
+    inner: Tagged,
+}
+
+unsafe impl crate::once::Finish for RawLock {
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.once.is_completed()
+    }
+
+    #[inline]
+    fn mark_done(&self) {
+        // a poisoned `Once` is incomplete, and `call_once` would panic on it
+        self.once.call_once_force(|_| ());
+    }
+
+    #[inline]
+    fn is_poisoned(&self) -> bool {
+        self.inner.tag(Ordering::Relaxed) & Self::POISON_BIT != 0
+    }
+
+    #[inline]
+    fn mark_poisoned(&self) {
+        self.inner.or_tag(Self::POISON_BIT, Ordering::Relaxed);
+    }
+}
+
+impl RawLock {
+    const POISON_BIT: u8 = 0b10;
+
+    pub const fn new() -> Self {
+        Self::from_std(std::sync::Once::new())
+    }
+
+    /// Create a new `RawLock` which is done if `once` was completed
+    ///
+    /// A poisoned `std::sync::Once` is treated as incomplete
+    pub const fn from_std(once: std::sync::Once) -> Self {
+        Self {
+            once,
+            inner: Tagged::new(),
+        }
+    }
+
+    /// Get the `std::sync::Once` that tracks whether this lock is done
+    pub fn into_std(self) -> std::sync::Once {
+        self.once
+    }
+
+    pub const fn once() -> Once {
+        unsafe { Once::from_raw(Self::new()) }
+    }
+
+    pub const fn once_cell<T>() -> OnceCell<T> {
+        unsafe {
+            OnceCell {
+                once: Once::from_raw(Self::new()),
+                value: super::UnsafeCell::new(super::MaybeUninit::uninit()),
+            }
+        }
+    }
+
+    pub const fn lazy<T, F>(func: F) -> Lazy<T, F> {
+        unsafe { Lazy::from_raw_parts(Self::once(), func, crate::once::Panic) }
+    }
+
+    pub const fn racy_lazy<T, F>(func: F) -> RacyLazy<T, F> {
+        RacyLazy {
+            ready: core::sync::atomic::AtomicBool::new(false),
+            once: Self::once_cell(),
+            func,
+        }
+    }
+}
+
+impl Default for RawLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for RawLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::RawLockInfo for RawLock {
+    type ExclusiveGuardTraits = <Tagged as crate::RawLockInfo>::ExclusiveGuardTraits;
+    type ShareGuardTraits = <Tagged as crate::RawLockInfo>::ShareGuardTraits;
+}
+
+unsafe impl RawExclusiveLock for RawLock {
+    #[inline]
+    fn exc_lock(&self) {
+        self.inner.exc_lock();
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        self.inner.exc_try_lock()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.inner.exc_unlock_fair();
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        self.inner.exc_bump_fair();
+    }
+}
+
+unsafe impl RawExclusiveLockFair for RawLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.inner.exc_unlock_fair();
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.inner.exc_bump_fair();
+    }
+}
+
+impl crate::RawTimedLock for RawLock {
+    type Instant = <Tagged as crate::RawTimedLock>::Instant;
+    type Duration = <Tagged as crate::RawTimedLock>::Duration;
+}
+
+unsafe impl RawExclusiveLockTimed for RawLock {
+    #[inline]
+    fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.inner.exc_try_lock_until(instant)
+    }
+
+    #[inline]
+    fn exc_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.inner.exc_try_lock_for(duration)
+    }
+}
+
+#[test]
+fn test_std_once() {
+    let done = std::sync::Once::new();
+    done.call_once(|| ());
+
+    let once = unsafe { Once::from_raw(RawLock::from_std(done)) };
+    once.call_once(|| panic!("the std `Once` was already completed"));
+
+    let lazy = RawLock::lazy(|| 10);
+    assert_eq!(*lazy, 10);
+
+    let lock = RawLock::new();
+    crate::once::Finish::mark_done(&lock);
+    assert!(lock.into_std().is_completed());
+
+    let poisoned = std::sync::Once::new();
+    let _ = std::panic::catch_unwind(|| poisoned.call_once(|| panic!()));
+
+    let once = unsafe { Once::from_raw(RawLock::from_std(poisoned)) };
+    let mut ran = false;
+    once.call_once(|| ran = true);
+    assert!(ran);
+}
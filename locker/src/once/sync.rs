@@ -0,0 +1,7 @@
+//! `Once` types that can be shared across threads
+//!
+//! These are backed by the parking [`TaggedLock`](crate::mutex::tagged::TaggedLock), so threads
+//! that wait for another thread to finish initializing are parked instead of spinning.
+//! They are the same types as in [`once::simple`](super::simple).
+
+pub use super::simple::{Lazy, Once, OnceCell, RacyLazy, RawLock, RertyLazy};
@@ -0,0 +1,422 @@
+//! Opt-in lock poisoning, like `std::sync`
+//!
+//! The [`Mutex`] and [`RwLock`] in this module wrap the containers from [`mutex`](crate::mutex)
+//! and [`rwlock`](crate::rwlock). If a thread panics while it holds an exclusive guard, then
+//! the lock is marked as poisoned, and all later attempts to lock it return a [`PoisonError`].
+//! The guard is still available from the error, so the poison can be ignored.
+//!
+//! *shr lock*s can't modify the value, so they never poison the lock.
+
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::mutex::RawMutex;
+use crate::rwlock::RawRwLock;
+use crate::share_lock::ShareGuard;
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A type alias for the result of a lock method which can be poisoned
+pub type LockResult<G> = Result<G, PoisonError<G>>;
+
+/// A type alias for the result of a nonblocking lock method
+pub type TryLockResult<G> = Result<G, TryLockError<G>>;
+
+/// An error returned when a lock was poisoned
+///
+/// The guard of the poisoned lock can be recovered with [`into_inner`](PoisonError::into_inner)
+pub struct PoisonError<G> {
+    guard: G,
+}
+
+/// An error returned from the nonblocking lock methods
+pub enum TryLockError<G> {
+    /// The lock was acquired, but it was poisoned
+    Poisoned(PoisonError<G>),
+    /// The lock could not be acquired without blocking
+    WouldBlock,
+}
+
+impl<G> PoisonError<G> {
+    /// Create a new `PoisonError`
+    pub fn new(guard: G) -> Self {
+        Self { guard }
+    }
+
+    /// Consume the error, and return the guard
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    /// A reference to the guard
+    pub fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    /// A mutable reference to the guard
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+impl<G> From<PoisonError<G>> for TryLockError<G> {
+    fn from(error: PoisonError<G>) -> Self {
+        TryLockError::Poisoned(error)
+    }
+}
+
+impl<G> fmt::Debug for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+impl<G> fmt::Display for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("poisoned lock: another task failed inside")
+    }
+}
+
+impl<G> std::error::Error for PoisonError<G> {}
+
+impl<G> fmt::Debug for TryLockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryLockError::Poisoned(error) => f.debug_tuple("Poisoned").field(error).finish(),
+            TryLockError::WouldBlock => f.write_str("WouldBlock"),
+        }
+    }
+}
+
+impl<G> fmt::Display for TryLockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryLockError::Poisoned(error) => error.fmt(f),
+            TryLockError::WouldBlock => {
+                f.write_str("try_lock failed because the operation would block")
+            }
+        }
+    }
+}
+
+impl<G> std::error::Error for TryLockError<G> {}
+
+struct Flag {
+    poisoned: AtomicBool,
+}
+
+impl Flag {
+    const fn new() -> Self {
+        Self {
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    fn get(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    fn clear(&self) {
+        self.poisoned.store(false, Ordering::Relaxed)
+    }
+
+    fn result<G>(&self, guard: G) -> LockResult<G> {
+        if self.get() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+/// RAII structure used to release the exclusive access of a poisonable lock when dropped.
+///
+/// If the current thread panics while this guard is alive, the lock is poisoned.
+#[must_use = "if unused the `PoisonGuard` will immediately unlock"]
+pub struct PoisonGuard<'a, L: RawMutex, T: ?Sized> {
+    guard: ExclusiveGuard<'a, L, T>,
+    flag: &'a Flag,
+    // if we were already panicking when the guard was created,
+    // then that panic didn't happen while holding the lock
+    panicking: bool,
+}
+
+impl<'a, L: RawMutex, T: ?Sized> PoisonGuard<'a, L, T> {
+    fn new(flag: &'a Flag, guard: ExclusiveGuard<'a, L, T>) -> LockResult<Self> {
+        flag.result(Self {
+            guard,
+            flag,
+            panicking: std::thread::panicking(),
+        })
+    }
+
+    fn try_new(flag: &'a Flag, guard: Option<ExclusiveGuard<'a, L, T>>) -> TryLockResult<Self> {
+        match guard {
+            Some(guard) => Ok(Self::new(flag, guard)?),
+            None => Err(TryLockError::WouldBlock),
+        }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> Drop for PoisonGuard<'_, L, T> {
+    fn drop(&mut self) {
+        if !self.panicking && std::thread::panicking() {
+            self.flag.poisoned.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> Deref for PoisonGuard<'_, L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> DerefMut for PoisonGuard<'_, L, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<L: RawMutex, T: ?Sized + fmt::Debug> fmt::Debug for PoisonGuard<'_, L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A mutex which is poisoned if a thread panics while holding it
+pub struct Mutex<L, T: ?Sized> {
+    flag: Flag,
+    mutex: crate::mutex::Mutex<L, T>,
+}
+
+impl<L: RawMutex, T: ?Sized + fmt::Debug> fmt::Debug for Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("Mutex");
+
+        match self.mutex.try_lock() {
+            Some(guard) => f.field("data", &&*guard),
+            None => f.field("data", &crate::guard::Placeholder("<locked>")),
+        }
+        .field("poisoned", &self.flag.get())
+        .finish()
+    }
+}
+
+impl<L, T> Mutex<L, T> {
+    /// Wrap a mutex, so that it is poisoned if a thread panics while holding it
+    pub const fn from_mutex(mutex: crate::mutex::Mutex<L, T>) -> Self {
+        Self {
+            flag: Flag::new(),
+            mutex,
+        }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// If the mutex is poisoned, the data is returned inside the error
+    pub fn into_inner(self) -> LockResult<T> {
+        self.flag.result(self.mutex.into_inner())
+    }
+}
+
+impl<L: RawMutex + crate::Init, T> Mutex<L, T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    pub fn new(value: T) -> Self {
+        Self::from_mutex(crate::mutex::Mutex::new(value))
+    }
+}
+
+impl<L, T: ?Sized> Mutex<L, T> {
+    /// The underlying mutex, which ignores poisoning
+    pub fn inner(&self) -> &crate::mutex::Mutex<L, T> {
+        &self.mutex
+    }
+
+    /// Returns true if the mutex is poisoned
+    pub fn is_poisoned(&self) -> bool {
+        self.flag.get()
+    }
+
+    /// Clear the poison from this mutex
+    pub fn clear_poison(&self) {
+        self.flag.clear()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// If the mutex is poisoned, the reference is returned inside the error
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.flag.result(self.mutex.get_mut())
+    }
+}
+
+impl<L: RawMutex, T: ?Sized> Mutex<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    /// Acquires the mutex, blocking the current thread until it is able to do so.
+    ///
+    /// If the mutex is poisoned, the guard is returned inside the error
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    pub fn lock(&self) -> LockResult<PoisonGuard<'_, L, T>> {
+        PoisonGuard::new(&self.flag, self.mutex.lock())
+    }
+
+    /// Attempts to acquire the mutex without blocking.
+    ///
+    /// If the mutex is poisoned, the guard is returned inside the error
+    pub fn try_lock(&self) -> TryLockResult<PoisonGuard<'_, L, T>> {
+        PoisonGuard::try_new(&self.flag, self.mutex.try_lock())
+    }
+}
+
+/// A rwlock which is poisoned if a thread panics while holding an *exc lock*
+pub struct RwLock<L, T: ?Sized> {
+    flag: Flag,
+    rwlock: crate::rwlock::RwLock<L, T>,
+}
+
+impl<L: RawRwLock, T: ?Sized + fmt::Debug> fmt::Debug for RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("RwLock");
+
+        match self.rwlock.try_read() {
+            Some(guard) => f.field("data", &&*guard),
+            None => f.field("data", &crate::guard::Placeholder("<locked>")),
+        }
+        .field("poisoned", &self.flag.get())
+        .finish()
+    }
+}
+
+impl<L, T> RwLock<L, T> {
+    /// Wrap a rwlock, so that it is poisoned if a thread panics while holding an *exc lock*
+    pub const fn from_rwlock(rwlock: crate::rwlock::RwLock<L, T>) -> Self {
+        Self {
+            flag: Flag::new(),
+            rwlock,
+        }
+    }
+
+    /// Consumes this rwlock, returning the underlying data.
+    ///
+    /// If the rwlock is poisoned, the data is returned inside the error
+    pub fn into_inner(self) -> LockResult<T> {
+        self.flag.result(self.rwlock.into_inner())
+    }
+}
+
+impl<L: RawRwLock + crate::Init, T> RwLock<L, T> {
+    /// Creates a new rwlock in an unlocked state ready for use.
+    pub fn new(value: T) -> Self {
+        Self::from_rwlock(crate::rwlock::RwLock::new(value))
+    }
+}
+
+impl<L, T: ?Sized> RwLock<L, T> {
+    /// The underlying rwlock, which ignores poisoning
+    pub fn inner(&self) -> &crate::rwlock::RwLock<L, T> {
+        &self.rwlock
+    }
+
+    /// Returns true if the rwlock is poisoned
+    pub fn is_poisoned(&self) -> bool {
+        self.flag.get()
+    }
+
+    /// Clear the poison from this rwlock
+    pub fn clear_poison(&self) {
+        self.flag.clear()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// If the rwlock is poisoned, the reference is returned inside the error
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.flag.result(self.rwlock.get_mut())
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this rwlock with exclusive write access, blocking the current thread until it can be acquired.
+    ///
+    /// If the rwlock is poisoned, the guard is returned inside the error
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    pub fn write(&self) -> LockResult<PoisonGuard<'_, L, T>> {
+        PoisonGuard::new(&self.flag, self.rwlock.write())
+    }
+
+    /// Attempts to lock this rwlock with exclusive write access without blocking.
+    ///
+    /// If the rwlock is poisoned, the guard is returned inside the error
+    pub fn try_write(&self) -> TryLockResult<PoisonGuard<'_, L, T>> {
+        PoisonGuard::try_new(&self.flag, self.rwlock.try_write())
+    }
+
+    /// Locks this rwlock with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// If the rwlock is poisoned, the guard is returned inside the error
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    pub fn read(&self) -> LockResult<ShareGuard<'_, L, T>> {
+        self.flag.result(self.rwlock.read())
+    }
+
+    /// Attempts to lock this rwlock with shared read access without blocking.
+    ///
+    /// If the rwlock is poisoned, the guard is returned inside the error
+    pub fn try_read(&self) -> TryLockResult<ShareGuard<'_, L, T>> {
+        match self.rwlock.try_read() {
+            Some(guard) => Ok(self.flag.result(guard)?),
+            None => Err(TryLockError::WouldBlock),
+        }
+    }
+}
+
+#[test]
+fn test_poison() {
+    use crate::mutex::spin::SpinLock;
+
+    let lock = Mutex::from_mutex(SpinLock::mutex(0));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut guard = lock.lock().unwrap();
+        *guard += 1;
+        panic!("poison the lock");
+    }));
+
+    assert!(result.is_err());
+    assert!(lock.is_poisoned());
+
+    let guard = match lock.lock() {
+        Ok(_) => panic!("the lock should be poisoned"),
+        Err(error) => error.into_inner(),
+    };
+    assert_eq!(*guard, 1);
+    drop(guard);
+
+    lock.clear_poison();
+    assert!(lock.try_lock().is_ok());
+}
@@ -0,0 +1,332 @@
+//! A read-mostly cell, which gives readers wait-free access to an immutable snapshot of the value
+//!
+//! Writers install a new snapshot under an exclusive lock, and the old snapshots are reclaimed
+//! once every reader that could see them has drained. Each [`Reader`] owns a slot, which it
+//! stores the current epoch into while it is reading. So reading only uses atomic loads and
+//! stores, and never does an atomic read-modify-write, and never waits for writers.
+//!
+//! Registering a reader takes the lock, so a [`Reader`] should be created once and reused.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::sync::atomic::{fence, AtomicPtr, AtomicUsize, Ordering};
+use std::boxed::Box;
+use std::vec::Vec;
+
+use crate::mutex::default::DefaultLock;
+use crate::mutex::{Mutex, RawMutex};
+use crate::Inhabitted;
+
+// the slot isn't owned by any reader
+const FREE: usize = usize::MAX;
+// the slot is owned by a reader, which isn't reading
+const INACTIVE: usize = usize::MAX - 1;
+
+/// A read-mostly cell, see the [module level docs](self) for details
+pub struct ReadMostly<T, L: RawMutex = DefaultLock> {
+    ptr: AtomicPtr<T>,
+    epoch: AtomicUsize,
+    state: Mutex<L, State<T>>,
+    _value: PhantomData<Box<T>>,
+}
+
+struct State<T> {
+    // boxed, so that the slots don't move when more readers are registered
+    #[allow(clippy::vec_box)]
+    slots: Vec<Box<AtomicUsize>>,
+    retired: Vec<Retired<T>>,
+}
+
+struct Retired<T> {
+    epoch: usize,
+    ptr: *mut T,
+}
+
+unsafe impl<T: Send> Send for Retired<T> {}
+
+/// A registered reader of a [`ReadMostly`], created by [`ReadMostly::reader`]
+pub struct Reader<'a, T, L: RawMutex = DefaultLock> {
+    cell: &'a ReadMostly<T, L>,
+    slot: &'a AtomicUsize,
+}
+
+/// An RAII guard which keeps a snapshot of a [`ReadMostly`] alive, created by [`Reader::read`]
+pub struct ReadGuard<'a, T> {
+    value: &'a T,
+    slot: &'a AtomicUsize,
+}
+
+impl<T, L: RawMutex + crate::Init> ReadMostly<T, L> {
+    /// Create a new read-mostly cell
+    pub fn new(value: T) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(Box::new(value))),
+            epoch: AtomicUsize::new(0),
+            state: Mutex::new(State {
+                slots: Vec::new(),
+                retired: Vec::new(),
+            }),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T, L: RawMutex> ReadMostly<T, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// Register a new reader
+    ///
+    /// The slots of dropped readers are reused by new readers
+    pub fn reader(&self) -> Reader<'_, T, L> {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+
+        let slot = match state
+            .slots
+            .iter()
+            .find(|slot| slot.load(Ordering::Acquire) == FREE)
+        {
+            Some(slot) => slot,
+            None => {
+                state.slots.push(Box::new(AtomicUsize::new(FREE)));
+                state.slots.last().unwrap()
+            }
+        };
+
+        slot.store(INACTIVE, Ordering::Relaxed);
+
+        // SAFETY: the slots are boxed, and are only freed when the cell is dropped
+        let slot = unsafe { &*(&**slot as *const AtomicUsize) };
+
+        Reader { cell: self, slot }
+    }
+
+    /// Install a new snapshot
+    ///
+    /// The old snapshot is dropped once all readers that could see it have drained
+    pub fn write(&self, value: T) {
+        let mut state = self.state.lock();
+        self.install(&mut state, value);
+    }
+
+    /// Install a new snapshot computed from the current one
+    ///
+    /// Writers are serialized, so no other writer can install a snapshot while `f` is running
+    pub fn update<F: FnOnce(&T) -> T>(&self, f: F) {
+        let mut state = self.state.lock();
+        // SAFETY: the current snapshot is only retired by writers, which hold the lock
+        let value = f(unsafe { &*self.ptr.load(Ordering::Acquire) });
+        self.install(&mut state, value);
+    }
+
+    /// Drop all old snapshots which no reader can see anymore
+    ///
+    /// This is also done by every write
+    pub fn reclaim(&self) {
+        let mut state = self.state.lock();
+        Self::reclaim_locked(&mut state);
+    }
+
+    fn install(&self, state: &mut State<T>, value: T) {
+        let ptr = Box::into_raw(Box::new(value));
+        let old = self.ptr.swap(ptr, Ordering::SeqCst);
+
+        // readers which saw the new epoch must also see the new snapshot
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        self.epoch.store(epoch.wrapping_add(1), Ordering::Release);
+
+        state.retired.push(Retired { epoch, ptr: old });
+        Self::reclaim_locked(state);
+    }
+
+    fn reclaim_locked(state: &mut State<T>) {
+        // pairs with the fence in `Reader::read`, either the reader's slot is visible here,
+        // or the reader sees the new snapshot
+        fence(Ordering::SeqCst);
+
+        let min_epoch = state
+            .slots
+            .iter()
+            .map(|slot| slot.load(Ordering::Acquire))
+            .min()
+            .unwrap_or(FREE);
+
+        state.retired.retain(|retired| {
+            if retired.epoch < min_epoch {
+                // SAFETY: every reader which could see this snapshot has drained
+                drop(unsafe { Box::from_raw(retired.ptr) });
+                false
+            } else {
+                true
+            }
+        })
+    }
+}
+
+impl<T, L: RawMutex> ReadMostly<T, L> {
+    /// Get a mutable reference to the current snapshot
+    ///
+    /// Because this borrows the cell uniquely, no readers are registered
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: the current snapshot is always valid, and is uniquely borrowed
+        unsafe { &mut **self.ptr.get_mut() }
+    }
+
+    /// Consume the cell, and get the current snapshot
+    pub fn into_inner(self) -> T {
+        let mut this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used again, and the state is dropped exactly once
+        unsafe {
+            core::ptr::drop_in_place(&mut this.state);
+            *Box::from_raw(*this.ptr.get_mut())
+        }
+    }
+}
+
+impl<T> Drop for State<T> {
+    fn drop(&mut self) {
+        for retired in self.retired.drain(..) {
+            // SAFETY: no readers are registered while the cell is being dropped
+            drop(unsafe { Box::from_raw(retired.ptr) });
+        }
+    }
+}
+
+impl<T, L: RawMutex> Drop for ReadMostly<T, L> {
+    fn drop(&mut self) {
+        // SAFETY: no readers are registered while the cell is being dropped
+        drop(unsafe { Box::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+impl<'a, T, L: RawMutex> Reader<'a, T, L> {
+    /// Get the current snapshot
+    ///
+    /// This never blocks, and the snapshot isn't dropped until the guard is dropped
+    #[inline]
+    pub fn read(&mut self) -> ReadGuard<'_, T> {
+        let epoch = self.cell.epoch.load(Ordering::Acquire);
+        self.slot.store(epoch, Ordering::Relaxed);
+        // pairs with the fence in `ReadMostly::reclaim_locked`
+        fence(Ordering::SeqCst);
+        let ptr = self.cell.ptr.load(Ordering::Acquire);
+
+        ReadGuard {
+            // SAFETY: the snapshot isn't reclaimed while the slot holds an epoch
+            // which is not newer than the epoch it was retired in
+            value: unsafe { &*ptr },
+            slot: self.slot,
+        }
+    }
+
+    /// The cell this reader is registered with
+    #[inline]
+    pub fn cell(&self) -> &'a ReadMostly<T, L> {
+        self.cell
+    }
+}
+
+impl<T, L: RawMutex> Clone for Reader<'_, T, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    fn clone(&self) -> Self {
+        self.cell.reader()
+    }
+}
+
+impl<T, L: RawMutex> Drop for Reader<'_, T, L> {
+    fn drop(&mut self) {
+        self.slot.store(FREE, Ordering::Release);
+    }
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.slot.store(INACTIVE, Ordering::Release);
+    }
+}
+
+impl<T: fmt::Debug, L: RawMutex> fmt::Debug for ReadMostly<T, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+{
+    /// This uses `try_lock`, so it never blocks, and prints `<locked>` if a writer holds the lock
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("ReadMostly");
+
+        match self.state.try_lock() {
+            // SAFETY: the current snapshot is only retired by writers, which hold the lock
+            Some(_state) => f.field("value", unsafe { &*self.ptr.load(Ordering::Acquire) }),
+            None => f.field("value", &crate::guard::Placeholder("<locked>")),
+        }
+        .finish()
+    }
+}
+
+impl<T, L: RawMutex> fmt::Debug for Reader<'_, T, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader").finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+#[test]
+fn test_read_mostly() {
+    use std::sync::Arc;
+
+    let cell = ReadMostly::<Arc<usize>>::new(Arc::new(0));
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            let mut reader = cell.reader();
+            s.spawn(move |_| {
+                let mut last = 0;
+                for _ in 0..1000 {
+                    let value = **reader.read();
+                    assert!(value >= last);
+                    last = value;
+                }
+            });
+        }
+
+        for _ in 0..100 {
+            cell.update(|value| Arc::new(**value + 1));
+        }
+    })
+    .unwrap();
+
+    let mut reader = cell.reader();
+    let guard = reader.read();
+    assert_eq!(**guard, 100);
+
+    // the snapshot held by the guard is kept alive
+    let snapshot = Arc::clone(&guard);
+    cell.write(Arc::new(200));
+    assert_eq!(Arc::strong_count(&snapshot), 2);
+    drop(guard);
+    cell.reclaim();
+    assert_eq!(Arc::strong_count(&snapshot), 1);
+
+    assert_eq!(**reader.read(), 200);
+    drop(reader);
+    assert_eq!(*cell.into_inner(), 200);
+}
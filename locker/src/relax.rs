@@ -0,0 +1,194 @@
+//! Strategies for waiting in a spin loop
+//!
+//! Spin locks are generic over a [`Relax`] strategy, which decides what to do
+//! each time a thread fails to acquire the lock. A new strategy is created for
+//! every contended lock acquisition, so strategies may keep some state between
+//! iterations, like the number of times they have already spun.
+
+use crate::spin_wait::SpinWait;
+
+/// A strategy to back off in a spin loop
+pub trait Relax: Default {
+    /// Called each time the lock could not be acquired, before trying again
+    fn relax(&mut self);
+}
+
+/// Spin with a hint to the CPU that we are in a spin loop
+///
+/// This is the lowest latency strategy, but it never gives up the CPU,
+/// so it may waste a lot of CPU time under contention.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spin;
+
+impl Relax for Spin {
+    #[inline]
+    fn relax(&mut self) {
+        core::hint::spin_loop()
+    }
+}
+
+/// Yield the current thread's time slice to the OS scheduler
+///
+/// This allows other threads, including the lock owner, to run on this core.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Yield;
+
+#[cfg(feature = "std")]
+impl Relax for Yield {
+    #[inline]
+    fn relax(&mut self) {
+        std::thread::yield_now()
+    }
+}
+
+/// Spin for a random, exponentially increasing number of iterations
+///
+/// This reduces the contention on the lock's cache line when many threads
+/// are waiting on the lock. The number of iterations is picked at random up to
+/// `2^SPIN_LIMIT`, so that threads which failed at the same time don't all retry
+/// at the same time.
+///
+/// [`spin`](Backoff::spin) should be used after a failed compare exchange, where
+/// another thread made progress, and [`snooze`](Backoff::snooze) should be used
+/// while waiting for another thread to release the lock. Once the backoff reaches
+/// its cap, `snooze` will yield to the OS scheduler if `std` is enabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Backoff {
+    step: u32,
+    seed: u32,
+}
+
+impl Backoff {
+    const SPIN_LIMIT: u32 = 6;
+    const YIELD_LIMIT: u32 = 10;
+
+    /// create a new backoff
+    #[inline]
+    pub const fn new() -> Self {
+        Self { step: 0, seed: 0 }
+    }
+
+    /// reset the backoff to its initial state
+    #[inline]
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Returns true if the backoff has reached its cap,
+    /// at which point it may be better to park the thread
+    #[inline]
+    pub fn is_completed(&self) -> bool {
+        self.step > Self::YIELD_LIMIT
+    }
+
+    // a xorshift rng, seeded by the address of the backoff so that each thread gets a different sequence
+    #[inline]
+    fn random(&mut self) -> u32 {
+        if self.seed == 0 {
+            self.seed = (self as *mut Self as usize >> 4) as u32 | 1;
+        }
+
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed = x;
+        x
+    }
+
+    #[inline]
+    fn spin_random(&mut self) {
+        let max = 1 << self.step.min(Self::SPIN_LIMIT);
+
+        for _ in 0..=self.random() % max {
+            core::hint::spin_loop()
+        }
+    }
+
+    /// back off after a failed compare exchange
+    #[inline]
+    pub fn spin(&mut self) {
+        self.spin_random();
+
+        if self.step <= Self::SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// back off while waiting for another thread to release the lock
+    #[inline]
+    pub fn snooze(&mut self) {
+        if self.step <= Self::SPIN_LIMIT {
+            self.spin_random();
+        } else {
+            #[cfg(feature = "std")]
+            std::thread::yield_now();
+
+            #[cfg(not(feature = "std"))]
+            self.spin_random();
+        }
+
+        if self.step <= Self::YIELD_LIMIT {
+            self.step += 1;
+        }
+    }
+}
+
+impl Relax for Backoff {
+    #[inline]
+    fn relax(&mut self) {
+        self.spin()
+    }
+}
+
+/// Wait for an event using the ARM `wfe` instruction
+///
+/// This puts the core into a low power state until another core signals an event,
+/// or the periodic event stream wakes it up. Unlocking a lock doesn't signal an event,
+/// so this relies on the event stream to wake up, which is only guaranteed on Linux
+/// (and Android), where the kernel always enables it. So this is only supported on
+/// `aarch64` Linux, on all other targets this acts like [`Spin`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Wfe;
+
+impl Relax for Wfe {
+    #[inline]
+    fn relax(&mut self) {
+        #[cfg(all(
+            target_arch = "aarch64",
+            any(target_os = "linux", target_os = "android")
+        ))]
+        unsafe {
+            core::arch::asm!("wfe", options(nomem, nostack, preserves_flags))
+        }
+
+        #[cfg(not(all(
+            target_arch = "aarch64",
+            any(target_os = "linux", target_os = "android")
+        )))]
+        core::hint::spin_loop()
+    }
+}
+
+/// The default strategy used by spin locks
+///
+/// Spins for an exponentially increasing number of iterations, and if `std` is enabled,
+/// starts yielding to the OS scheduler after a few iterations.
+pub struct DefaultRelax(SpinWait);
+
+impl Default for DefaultRelax {
+    #[inline]
+    fn default() -> Self {
+        Self(SpinWait::new())
+    }
+}
+
+impl Relax for DefaultRelax {
+    #[inline]
+    fn relax(&mut self) {
+        if !self.0.spin() {
+            core::hint::spin_loop()
+        }
+    }
+}
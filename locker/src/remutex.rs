@@ -43,7 +43,7 @@ pub unsafe trait RawReentrantMutex: crate::RawLockInfo + RawShareLock {}
 /// A mutual exclusion primitive useful for protecting shared data
 ///
 /// This reentrant mutex will block threads waiting for the lock to become available.
-/// The reentrant mutex can also be statically initialized or created via a `new` constructor (via `nightly` feature flag)
+/// The reentrant mutex can also be statically initialized or created via the `const` `new` constructor
 /// or with the `from_raw_parts` (even on `stable`, but `unsafe`).
 /// Each reentrant mutex has a type parameter which represents the data that it is protecting.
 /// The data can only be accessed through the RAII guards returned from `lock` and
@@ -143,20 +143,10 @@ impl<L, T: ?Sized> ReentrantMutex<L, T> {
 }
 
 impl<L: RawReentrantMutex + crate::Init, T> ReentrantMutex<L, T> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            /// Create a new reentrant mutex
-            #[inline]
-            pub const fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        } else {
-            /// Create a new reentrant mutex
-            #[inline]
-            pub fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        }
+    /// Create a new reentrant mutex
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self::from_raw_parts(crate::Init::INIT, value)
     }
 }
 
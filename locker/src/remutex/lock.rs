@@ -19,6 +19,12 @@ cfg_if::cfg_if! {
             owner: AtomicUsize,
             count: Cell<S>,
         }
+
+        /// a raw reentrant mutex built from any [`RawExclusiveLock`]
+        pub type RawReentrantMutex<L> = super::raw::ReentrantMutex<ReLock<L>>;
+
+        /// a reentrant mutex built from any [`RawExclusiveLock`]
+        pub type ReentrantMutex<L, T> = super::ReentrantMutex<ReLock<L>, T>;
     } else {
         /// A wrapper around a [`RawExclusiveLock`] that allows it to be used as a
         /// reentrant mutex
@@ -183,12 +189,12 @@ unsafe impl<L: RawExclusiveLockTimed, S: Scalar, I: ThreadInfo> RawShareLockTime
 
 #[cfg(test)]
 mod test {
+    use core::cell::Cell;
+
     #[test]
-    #[cfg(all(feature = "std", feature = "parking_lot"))]
+    #[cfg(feature = "std")]
     fn reentrant() {
-        use crate::mutex::simple::RawLock;
-
-        type ReentrantMutex<T> = super::ReentrantMutex<ReLock<RawLock>, T>;
+        type ReentrantMutex<T> = super::ReentrantMutex<crate::mutex::spin::SpinLock, T>;
 
         let mtx = ReentrantMutex::new(Cell::new(0));
 
@@ -202,12 +208,11 @@ mod test {
     }
 
     #[test]
-    #[cfg(all(feature = "std", feature = "parking_lot"))]
+    #[cfg(feature = "std")]
     fn reentrant_multi() {
-        use crate::mutex::simple::RawLock;
-        use crossbeam_utils::WaitGroup;
+        use crossbeam_utils::sync::WaitGroup;
 
-        type ReentrantMutex<T> = super::ReentrantMutex<ReLock<RawLock>, T>;
+        type ReentrantMutex<T> = super::ReentrantMutex<crate::mutex::spin::SpinLock, T>;
 
         let mtx = ReentrantMutex::new(Cell::new(0));
         let mtx = std::sync::Arc::new(mtx);
@@ -225,13 +230,12 @@ mod test {
                 second.wait();
                 let _lock = mtx.lock();
 
-                assert!(_lock.get() == 0 || _lock.get() == 10);
+                assert_eq!(_lock.get(), 10);
             }
         });
 
-        first.wait();
         let _lock = mtx.lock();
-        second.wait();
+        first.wait();
 
         assert_eq!(_lock.get(), 0);
 
@@ -239,6 +243,7 @@ mod test {
 
         assert_eq!(_lock.get(), 10);
 
+        second.wait();
         drop(_lock);
 
         t.join().unwrap();
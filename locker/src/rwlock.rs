@@ -2,13 +2,24 @@
 
 use core::cell::UnsafeCell;
 
-use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLockTimed};
-use crate::share_lock::{RawShareLock, RawShareLockTimed, ShareGuard};
+use crate::exclusive_lock::{ExclusiveGuard, RawExclusiveLockFair, RawExclusiveLockTimed};
+use crate::share_lock::{
+    RawShareLock, RawShareLockCount, RawShareLockFair, RawShareLockRecursive, RawShareLockTimed,
+    RawShareLockUpgrade, ShareGuard, UpgradableGuard,
+};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::{exclusive_lock::ArcExclusiveGuard, share_lock::ArcShareGuard};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use std::sync::Arc;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "extra")] {
         pub mod global;
         pub mod spin;
+        pub mod tagged;
+        pub mod tagged_spin;
+        pub mod ticket;
+        pub mod phase_fair;
         pub mod local;
         pub mod default;
         pub mod local_splittable;
@@ -16,6 +27,12 @@ cfg_if::cfg_if! {
         pub mod splittable_default;
         #[cfg(feature = "std")]
         pub mod sharded;
+        #[cfg(feature = "std")]
+        pub mod biased;
+        #[cfg(windows)]
+        pub mod srw;
+        #[cfg(all(unix, feature = "libc", any(feature = "std", feature = "alloc")))]
+        pub mod pthread;
 
         #[cfg(feature = "parking_lot_core")]
         pub mod adaptive;
@@ -52,8 +69,54 @@ impl<L: RawRwLock + crate::Init, T: Default> Default for RwLock<L, T> {
     }
 }
 
-unsafe impl<L: Send, T: Send> Send for RwLock<L, T> {}
-unsafe impl<L: Sync, T: Send + Sync> Sync for RwLock<L, T> {}
+impl<L: RawRwLock + crate::Init, T> From<T> for RwLock<L, T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized + core::fmt::Debug> core::fmt::Debug for RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// This uses `try_read`, so it never blocks, and prints `<locked>` if there is a writer
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut f = f.debug_struct("RwLock");
+
+        match self.try_read() {
+            Some(guard) => f.field("data", &&*guard),
+            None => f.field("data", &crate::guard::Placeholder("<locked>")),
+        }
+        .finish()
+    }
+}
+
+/// This acquires a *shr lock* while serializing the value
+#[cfg(feature = "serde")]
+impl<L: RawRwLock, T: ?Sized + serde::Serialize> serde::Serialize for RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.read().serialize(serializer)
+    }
+}
+
+/// This creates a new unlocked rwlock
+#[cfg(feature = "serde")]
+impl<'de, L: RawRwLock + crate::Init, T: serde::Deserialize<'de>> serde::Deserialize<'de>
+    for RwLock<L, T>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+unsafe impl<L: Send, T: ?Sized + Send> Send for RwLock<L, T> {}
+unsafe impl<L: Sync, T: ?Sized + Send + Sync> Sync for RwLock<L, T> {}
 
 impl<L, T> RwLock<L, T> {
     /// # Safety
@@ -92,6 +155,26 @@ impl<L, T: ?Sized> RwLock<L, T> {
         self.value.get()
     }
 
+    /// Get a raw pointer to the value, without locking the rwlock
+    ///
+    /// This is the same as [`as_mut_ptr`](Self::as_mut_ptr). It is up to the caller to
+    /// make sure that the appropriate lock is held while the value is accessed through this pointer.
+    pub fn data_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// View this rwlock as a mutex
+    ///
+    /// The mutex only uses the *exc lock*, so it will wait for all readers and
+    /// writers of this rwlock, and they will wait for it.
+    pub fn as_mutex(&self) -> &crate::mutex::Mutex<L, T> {
+        // SAFETY: `Mutex` and `RwLock` are both `repr(C)` with the same fields,
+        // and `raw::Mutex` and `raw::RwLock` are both `repr(transparent)` over `L`.
+        // `Mutex` has weaker `Sync` bounds than `RwLock`, so this can't be used
+        // to share `T` across threads when it isn't `Sync`
+        unsafe { &*(self as *const Self as *const crate::mutex::Mutex<L, T>) }
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(feature = "nightly")] {
             /// the underlying raw rwlock
@@ -136,20 +219,10 @@ impl<L, T: ?Sized> RwLock<L, T> {
 }
 
 impl<L: RawRwLock + crate::Init, T> RwLock<L, T> {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "nightly")] {
-            /// Creates a new rwlock in an unlocked state ready for use.
-            #[inline]
-            pub const fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        } else {
-            /// Creates a new rwlock in an unlocked state ready for use.
-            #[inline]
-            pub fn new(value: T) -> Self {
-                Self::from_raw_parts(crate::Init::INIT, value)
-            }
-        }
+    /// Creates a new rwlock in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self::from_raw_parts(crate::Init::INIT, value)
     }
 }
 
@@ -231,6 +304,430 @@ where
     pub fn try_read(&self) -> Option<ShareGuard<'_, L, T>> {
         Some(self.wrap_read(self.raw.try_read()?))
     }
+
+    /// Locks this `RwLock` with shared read access, and runs the given function on the locked value
+    ///
+    /// The lock is released once the function returns, so it can't be held
+    /// for longer than the function call.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.read())
+    }
+
+    /// Locks this `RwLock` with exclusive write access, and runs the given function on the locked value
+    ///
+    /// The lock is released once the function returns, so it can't be held
+    /// for longer than the function call.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn write_with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.write())
+    }
+
+    /// Locks a pinned rwlock with exclusive write access, and returns a guard which only gives
+    /// pinned access to the value
+    ///
+    /// Use [`Pin::as_mut`](core::pin::Pin::as_mut) on the guard to get a `Pin<&mut T>`.
+    ///
+    /// # Safety
+    ///
+    /// Pinning is structural for the value in the rwlock, so once this or [`read_pin`](Self::read_pin)
+    /// is called the value must not be moved until it is dropped. In particular, it must not be moved
+    /// out of a guard returned by the unpinned lock methods, like [`write`](Self::write),
+    /// [`replace`](Self::replace) or [`take`](Self::take), unless `T: Unpin`.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub unsafe fn write_pin(
+        self: core::pin::Pin<&Self>,
+    ) -> core::pin::Pin<ExclusiveGuard<'_, L, T>> {
+        core::pin::Pin::new_unchecked(self.get_ref().write())
+    }
+
+    /// Locks a pinned rwlock with shared read access, and returns a guard which only gives
+    /// pinned access to the value
+    ///
+    /// # Safety
+    ///
+    /// See [`write_pin`](Self::write_pin)
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub unsafe fn read_pin(self: core::pin::Pin<&Self>) -> core::pin::Pin<ShareGuard<'_, L, T>> {
+        core::pin::Pin::new_unchecked(self.get_ref().read())
+    }
+}
+
+impl<L: RawRwLock, T> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with exclusive write access, and replaces the locked value with `value`,
+    /// returning the old value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn replace(&self, value: T) -> T {
+        core::mem::replace(&mut self.write(), value)
+    }
+
+    /// Locks this `RwLock` with exclusive write access, and takes the locked value,
+    /// leaving `Default::default()` in its place
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        core::mem::take(&mut self.write())
+    }
+
+    /// Locks this `RwLock` with shared read access, and returns a clone of the locked value
+    ///
+    /// The lock is released before this function returns, so no guard is exposed
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn read_cloned(&self) -> T
+    where
+        T: Clone,
+    {
+        T::clone(&self.read())
+    }
+
+    /// Locks this `RwLock` with exclusive write access, and runs `f` on the locked value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        f(&mut self.write())
+    }
+
+    /// Locks this `RwLock` with exclusive write access, runs `f` on the locked value, and returns a clone of the new value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn update_and_fetch(&self, f: impl FnOnce(&mut T)) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.write();
+        f(&mut guard);
+        T::clone(&guard)
+    }
+
+    /// Locks this `RwLock` with exclusive write access, runs `f` on the locked value, and returns a clone of the old value
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn fetch_update(&self, f: impl FnOnce(&mut T)) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.write();
+        let old = T::clone(&guard);
+        f(&mut guard);
+        old
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L: RawRwLock, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn arc_as_mutex(self: &Arc<Self>) -> Arc<crate::mutex::Mutex<L, T>> {
+        // SAFETY: `Mutex` has the same layout as `RwLock`, see `as_mutex`
+        unsafe { Arc::from_raw(Arc::into_raw(self.clone()) as *const crate::mutex::Mutex<L, T>) }
+    }
+
+    /// Locks this `RwLock` with exclusive write access through an `Arc`,
+    /// blocking the current thread until it can be acquired.
+    ///
+    /// This is like [`write`](Self::write), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the rwlock.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn write_arc(self: &Arc<Self>) -> ArcExclusiveGuard<L, T> {
+        self.raw.inner().exc_lock();
+        unsafe { ArcExclusiveGuard::from_raw(self.arc_as_mutex()) }
+    }
+
+    /// Attempts to lock this `RwLock` with exclusive write access through an `Arc`.
+    ///
+    /// This is like [`try_write`](Self::try_write), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the rwlock.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_write_arc(self: &Arc<Self>) -> Option<ArcExclusiveGuard<L, T>> {
+        if self.raw.inner().exc_try_lock() {
+            Some(unsafe { ArcExclusiveGuard::from_raw(self.arc_as_mutex()) })
+        } else {
+            None
+        }
+    }
+
+    /// Locks this `RwLock` with shared read access through an `Arc`,
+    /// blocking the current thread until it can be acquired.
+    ///
+    /// This is like [`read`](Self::read), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the rwlock.
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if it is impossible to acquire the lock
+    #[inline]
+    pub fn read_arc(self: &Arc<Self>) -> ArcShareGuard<L, T> {
+        self.raw.inner().shr_lock();
+        unsafe { ArcShareGuard::from_raw(self.clone()) }
+    }
+
+    /// Attempts to lock this `RwLock` with shared read access through an `Arc`.
+    ///
+    /// This is like [`try_read`](Self::try_read), but the returned guard keeps the `Arc` alive
+    /// instead of borrowing the rwlock.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_read_arc(self: &Arc<Self>) -> Option<ArcShareGuard<L, T>> {
+        if self.raw.inner().shr_try_lock() {
+            Some(unsafe { ArcShareGuard::from_raw(self.clone()) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<L: RawRwLock + RawShareLockRecursive, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// Unlike [`read`](Self::read), this will acquire the lock even if there are writers waiting
+    /// for the lock, so it is safe to use when the current thread already holds a read lock.
+    /// This may starve writers.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    #[inline]
+    pub fn read_recursive(&self) -> ShareGuard<'_, L, T> {
+        self.wrap_read(self.raw.read_recursive())
+    }
+
+    /// Attempts to acquire this `RwLock` with shared read access, even if there are writers waiting
+    /// for the lock.
+    ///
+    /// If the access could not be granted at this time, then None is returned.
+    /// Otherwise, an RAII guard is returned which will release the shared access when it is dropped.
+    ///
+    /// This function does not block or panic.
+    #[inline]
+    pub fn try_read_recursive(&self) -> Option<ShareGuard<'_, L, T>> {
+        Some(self.wrap_read(self.raw.try_read_recursive()?))
+    }
+}
+
+impl<L, T, const N: usize> RwLock<L, [T; N]> {
+    /// View this rwlock as a rwlock over a slice
+    #[inline]
+    pub fn as_slice(&self) -> &RwLock<L, [T]> {
+        self
+    }
+}
+
+impl<L, T> RwLock<L, [T]> {
+    /// The number of elements in the locked slice
+    ///
+    /// This doesn't lock the rwlock, because the length of the slice can't change
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.value.get().len()
+    }
+
+    /// Returns true if the locked slice has no elements
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L, T> RwLock<L, [T]> {
+    /// Move the elements of a boxed slice into a new boxed rwlock
+    pub fn from_boxed_slice(
+        raw: raw::RwLock<L>,
+        values: std::boxed::Box<[T]>,
+    ) -> std::boxed::Box<Self> {
+        let ptr = crate::mutex::box_slice_with_head(raw, values);
+        unsafe { std::boxed::Box::from_raw(ptr as *mut Self) }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<L: RawRwLock + crate::Init, T> From<std::boxed::Box<[T]>> for std::boxed::Box<RwLock<L, [T]>> {
+    #[inline]
+    fn from(values: std::boxed::Box<[T]>) -> Self {
+        RwLock::from_boxed_slice(crate::Init::INIT, values)
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> RwLock<L, T> {
+    /// Forcibly release an *exc lock* on the rwlock
+    ///
+    /// This is useful when a write guard was deliberately forgotten, for example with
+    /// `core::mem::forget` or after it was handed off through FFI.
+    ///
+    /// # Safety
+    ///
+    /// The rwlock must be locked with an *exc lock*, and there must be no live write guards
+    /// for the lock held by the current context
+    #[inline]
+    pub unsafe fn force_unlock_write(&self) {
+        self.raw.inner().exc_unlock()
+    }
+
+    /// Forcibly release a *shr lock* on the rwlock
+    ///
+    /// This is useful when a read guard was deliberately forgotten, for example with
+    /// `core::mem::forget` or after it was handed off through FFI.
+    ///
+    /// # Safety
+    ///
+    /// The rwlock must be locked with a *shr lock*, and the *shr lock* being released must
+    /// not belong to a live read guard
+    #[inline]
+    pub unsafe fn force_unlock_read(&self) {
+        self.raw.inner().shr_unlock()
+    }
+}
+
+impl<L: RawRwLock + RawExclusiveLockFair + RawShareLockFair, T: ?Sized> RwLock<L, T> {
+    /// Forcibly release an *exc lock* on the rwlock using a fair unlock protocol
+    ///
+    /// See [`force_unlock_write`](Self::force_unlock_write) for more details
+    ///
+    /// # Safety
+    ///
+    /// The rwlock must be locked with an *exc lock*, and there must be no live write guards
+    /// for the lock held by the current context
+    #[inline]
+    pub unsafe fn force_unlock_write_fair(&self) {
+        self.raw.inner().exc_unlock_fair()
+    }
+
+    /// Forcibly release a *shr lock* on the rwlock using a fair unlock protocol
+    ///
+    /// See [`force_unlock_read`](Self::force_unlock_read) for more details
+    ///
+    /// # Safety
+    ///
+    /// The rwlock must be locked with a *shr lock*, and the *shr lock* being released must
+    /// not belong to a live read guard
+    #[inline]
+    pub unsafe fn force_unlock_read_fair(&self) {
+        self.raw.inner().shr_unlock_fair()
+    }
+}
+
+impl<L: RawRwLock + crate::RawLockQuery, T: ?Sized> RwLock<L, T> {
+    /// Returns true if there are any readers or writers which currently hold this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.raw.is_locked()
+    }
+
+    /// Returns true if there is a writer which currently holds this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked_exclusive(&self) -> bool {
+        self.raw.is_locked_exclusive()
+    }
+}
+
+impl<L: RawRwLock + RawShareLockCount, T: ?Sized> RwLock<L, T> {
+    /// Returns the number of readers which currently hold this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for diagnostics, like metrics or debugging writer starvation.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.raw.reader_count()
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade, T: ?Sized> RwLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn wrap_upgradable<'s>(
+        &'s self,
+        raw: crate::share_lock::RawUpgradableGuard<'s, L>,
+    ) -> UpgradableGuard<'s, L, T> {
+        assert!(core::ptr::eq(self.raw.inner(), raw.inner()));
+        unsafe { UpgradableGuard::from_raw_parts(raw, self.value.get()) }
+    }
+
+    /// Locks this `RwLock` with upgradable read access, blocking the current thread until it can be acquired.
+    ///
+    /// The calling thread will be blocked until there are no more writers or other upgradable reads
+    /// which hold the lock. There may be other readers currently inside the lock when this method returns.
+    ///
+    /// Returns an RAII guard which will release this thread's upgradable access once it is dropped,
+    /// or which can be upgraded to write access with [`UpgradableGuard::upgrade`] without deadlocking.
+    #[inline]
+    pub fn upgradable_read(&self) -> UpgradableGuard<'_, L, T> {
+        self.wrap_upgradable(self.raw.upgradable_read())
+    }
+
+    /// Attempts to acquire this `RwLock` with upgradable read access.
+    ///
+    /// If the access could not be granted at this time, then None is returned.
+    /// Otherwise, an RAII guard is returned which will release the upgradable access when it is dropped.
+    ///
+    /// This function does not block or panic.
+    #[inline]
+    pub fn try_upgradable_read(&self) -> Option<UpgradableGuard<'_, L, T>> {
+        Some(self.wrap_upgradable(self.raw.try_upgradable_read()?))
+    }
 }
 
 impl<L: RawRwLock + RawExclusiveLockTimed + RawShareLockTimed, T: ?Sized> RwLock<L, T>
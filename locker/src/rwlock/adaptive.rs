@@ -1,7 +1,7 @@
 //! an adaptive raw rwlock
 
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade};
-use crate::share_lock::RawShareLock;
+use crate::share_lock::{RawShareLock, RawShareLockRecursive};
 
 use parking_lot_core::{self, ParkResult, ParkToken, SpinWait, UnparkResult, UnparkToken};
 
@@ -31,30 +31,72 @@ const TOKEN_EXCLUSIVE: ParkToken = ParkToken(1);
 // thread directly without unlocking it.
 const TOKEN_SHARED: ParkToken = ParkToken(2);
 
+use core::marker::PhantomData;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
 /// an adaptive raw mutex
-pub type RawMutex = crate::mutex::raw::Mutex<AdaptiveLock>;
+pub type RawMutex<P = ReaderPreferred> = crate::mutex::raw::Mutex<AdaptiveLock<P>>;
 /// an adaptive mutex
-pub type Mutex<T> = crate::mutex::Mutex<AdaptiveLock, T>;
+pub type Mutex<T, P = ReaderPreferred> = crate::mutex::Mutex<AdaptiveLock<P>, T>;
 /// an adaptive raw rwlock
-pub type RawRwLock = crate::rwlock::raw::RwLock<AdaptiveLock>;
+pub type RawRwLock<P = ReaderPreferred> = crate::rwlock::raw::RwLock<AdaptiveLock<P>>;
 /// an adaptive rwlock
-pub type RwLock<T> = crate::rwlock::RwLock<AdaptiveLock, T>;
+pub type RwLock<T, P = ReaderPreferred> = crate::rwlock::RwLock<AdaptiveLock<P>, T>;
+
+/// A policy which decides who gets an [`AdaptiveLock`] when both readers and writers are waiting
+pub trait Preference {
+    /// If true, a waiting writer stops new readers from acquiring the lock,
+    /// otherwise writers wait until there are no readers left
+    const WRITER_PREFERRED: bool;
+
+    /// If true, every *exc unlock* hands the lock directly to the next parked thread
+    const FAIR: bool;
+}
+
+/// Readers can acquire the lock as long as no writer holds it, even if writers are waiting
+///
+/// This gives the best read throughput, but a steady stream of readers can starve writers.
+pub struct ReaderPreferred;
+
+/// Once a writer starts waiting, new readers wait until that writer is done
+///
+/// Readers which already hold the lock can still acquire it recursively.
+pub struct WriterPreferred;
+
+/// Like [`WriterPreferred`], but every *exc unlock* is a fair unlock
+///
+/// This trades throughput for making sure that every parked thread eventually gets the lock.
+pub struct Fair;
+
+impl Preference for ReaderPreferred {
+    const WRITER_PREFERRED: bool = false;
+    const FAIR: bool = false;
+}
+
+impl Preference for WriterPreferred {
+    const WRITER_PREFERRED: bool = true;
+    const FAIR: bool = false;
+}
+
+impl Preference for Fair {
+    const WRITER_PREFERRED: bool = true;
+    const FAIR: bool = true;
+}
 
 /// An adaptive rwlock lock backed by `parking_lot_core`
-pub struct AdaptiveLock {
+///
+/// Whether readers or writers get the lock first is decided by the [`Preference`] `P`
+pub struct AdaptiveLock<P = ReaderPreferred> {
     state: AtomicUsize,
+    preference: PhantomData<fn() -> P>,
 }
 
 impl AdaptiveLock {
     /// Create a new adaptive rwlock lock
     #[inline]
     pub const fn new() -> Self {
-        Self {
-            state: AtomicUsize::new(0),
-        }
+        Self::with_preference()
     }
 
     /// Create a new adaptive raw mutex
@@ -78,18 +120,44 @@ impl AdaptiveLock {
     }
 }
 
-impl crate::Init for AdaptiveLock {
-    const INIT: Self = Self::new();
+impl<P> AdaptiveLock<P> {
+    /// Create a new adaptive rwlock lock which uses the [`Preference`] `P`
+    #[inline]
+    pub const fn with_preference() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            preference: PhantomData,
+        }
+    }
+}
+
+impl<P> crate::Init for AdaptiveLock<P> {
+    const INIT: Self = Self::with_preference();
 }
 
-unsafe impl crate::mutex::RawMutex for AdaptiveLock {}
-unsafe impl crate::rwlock::RawRwLock for AdaptiveLock {}
-unsafe impl crate::RawLockInfo for AdaptiveLock {
+unsafe impl<P: Preference> crate::mutex::RawMutex for AdaptiveLock<P> {}
+unsafe impl<P: Preference> crate::rwlock::RawRwLock for AdaptiveLock<P> {}
+unsafe impl<P> crate::RawLockInfo for AdaptiveLock<P> {
     type ExclusiveGuardTraits = ();
     type ShareGuardTraits = ();
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLock for AdaptiveLock {
+impl<P> crate::RawLockQuery for AdaptiveLock<P> {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & (EXC_BIT | READERS) != 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        // a writer which still waits for readers to leave doesn't hold the lock yet
+        self.state.load(Ordering::Relaxed) & (EXC_BIT | READERS) == EXC_BIT
+    }
+}
+
+unsafe impl<P: Preference> crate::combinators::Elidable for AdaptiveLock<P> {}
+
+unsafe impl<P: Preference> crate::exclusive_lock::RawExclusiveLock for AdaptiveLock<P> {
     #[inline]
     fn exc_lock(&self) {
         if !self.exc_try_lock() {
@@ -115,19 +183,19 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for AdaptiveLock {
             .compare_exchange(EXC_BIT, 0, Ordering::Release, Ordering::Relaxed)
             .is_err()
         {
-            self.exc_unlock_slow(false);
+            self.exc_unlock_slow(P::FAIR);
         }
     }
 
     #[inline]
     unsafe fn exc_bump(&self) {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
-            self.exc_bump_slow(false);
+            self.exc_bump_slow(P::FAIR);
         }
     }
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
+unsafe impl<P: Preference> crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock<P> {
     #[inline]
     unsafe fn exc_unlock_fair(&self) {
         if self
@@ -147,7 +215,7 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockFair for AdaptiveLock {
     }
 }
 
-unsafe impl RawShareLock for AdaptiveLock {
+unsafe impl<P: Preference> RawShareLock for AdaptiveLock<P> {
     #[inline]
     fn shr_lock(&self) {
         if !self.shr_try_lock() {
@@ -170,43 +238,89 @@ unsafe impl RawShareLock for AdaptiveLock {
 
     #[inline]
     unsafe fn shr_split(&self) {
-        let was_locked = self.shr_try_lock();
+        let was_locked = self.shr_try_lock_recursive();
         assert!(was_locked, "Tried to create too many shared locks!");
     }
 
     #[inline]
     unsafe fn shr_unlock(&self) {
-        self.shr_unlock_inner(false)
+        self.shr_unlock_inner()
     }
 
     #[inline]
     unsafe fn shr_bump(&self) {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
-            self.shr_bump_slow(false);
+            self.shr_bump_slow();
         }
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockFair for AdaptiveLock {
+unsafe impl<P: Preference> crate::share_lock::RawShareLockCount for AdaptiveLock<P> {
+    #[inline]
+    fn shr_count(&self) -> usize {
+        (self.state.load(Ordering::Relaxed) & READERS) / INC
+    }
+}
+
+unsafe impl<P: Preference> crate::share_lock::RawShareLockRecursive for AdaptiveLock<P> {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        if !self.shr_try_lock_recursive() {
+            self.shr_lock_slow(None);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // if there are readers, then a writer which holds `EXC_BIT` is still waiting
+        // for them to leave, so it's fine to let another reader in
+        while state & EXC_BIT == 0 || state & READERS != 0 {
+            let (next_state, overflow) = state.overflowing_add(INC);
+
+            if overflow {
+                return false;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                next_state,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        false
+    }
+}
+
+unsafe impl<P: Preference> crate::share_lock::RawShareLockFair for AdaptiveLock<P> {
+    // readers never hand off the lock directly, so a fair *shr unlock*
+    // is the same as a normal *shr unlock*
+
     #[inline]
     unsafe fn shr_unlock_fair(&self) {
-        self.shr_unlock_inner(true)
+        self.shr_unlock_inner()
     }
 
     #[inline]
     unsafe fn shr_bump_fair(&self) {
         if self.state.load(Ordering::Relaxed) & PARK_BIT != 0 {
-            self.shr_bump_slow(true);
+            self.shr_bump_slow();
         }
     }
 }
 
-impl crate::RawTimedLock for AdaptiveLock {
+impl<P> crate::RawTimedLock for AdaptiveLock<P> {
     type Instant = std::time::Instant;
     type Duration = std::time::Duration;
 }
 
-unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
+unsafe impl<P: Preference> crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock<P> {
     fn exc_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.exc_try_lock() {
             true
@@ -224,7 +338,7 @@ unsafe impl crate::exclusive_lock::RawExclusiveLockTimed for AdaptiveLock {
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockTimed for AdaptiveLock {
+unsafe impl<P: Preference> crate::share_lock::RawShareLockTimed for AdaptiveLock<P> {
     fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
         if self.shr_try_lock() {
             true
@@ -242,7 +356,7 @@ unsafe impl crate::share_lock::RawShareLockTimed for AdaptiveLock {
     }
 }
 
-unsafe impl RawExclusiveLockDowngrade for AdaptiveLock {
+unsafe impl<P: Preference> RawExclusiveLockDowngrade for AdaptiveLock<P> {
     unsafe fn downgrade(&self) {
         let mut state = self.state.load(Ordering::Relaxed);
 
@@ -255,15 +369,15 @@ unsafe impl RawExclusiveLockDowngrade for AdaptiveLock {
             state = x;
         }
 
+        // `EXC_BIT` was already cleared above, so a new writer may already be
+        // waiting for readers, and it must not be cleared again here
         if state & PARK_BIT != 0 {
             self.unpark_shared();
-            self.state
-                .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
         }
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockUpgrade for AdaptiveLock {
+unsafe impl<P: Preference> crate::share_lock::RawShareLockUpgrade for AdaptiveLock<P> {
     unsafe fn upgrade(&self) {
         if !self.try_upgrade() {
             self.upgrade_slow(None);
@@ -274,7 +388,7 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for AdaptiveLock {
         let state = self.state.load(Ordering::Relaxed);
 
         state & READERS == INC
-            && state & EXC_PARK_BIT == 0
+            && state & (EXC_PARK_BIT | EXC_BIT) == 0
             && self
                 .state
                 .compare_exchange(
@@ -287,7 +401,7 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for AdaptiveLock {
     }
 }
 
-unsafe impl crate::share_lock::RawShareLockUpgradeTimed for AdaptiveLock {
+unsafe impl<P: Preference> crate::share_lock::RawShareLockUpgradeTimed for AdaptiveLock<P> {
     unsafe fn try_upgrade_until(&self, instant: Self::Instant) -> bool {
         use crate::share_lock::RawShareLockUpgrade;
 
@@ -309,7 +423,7 @@ unsafe impl crate::share_lock::RawShareLockUpgradeTimed for AdaptiveLock {
     }
 }
 
-impl AdaptiveLock {
+impl<P: Preference> AdaptiveLock<P> {
     #[cold]
     fn exc_bump_slow(&self, force_fair: bool) {
         self.exc_unlock_slow(force_fair);
@@ -317,13 +431,13 @@ impl AdaptiveLock {
     }
 
     #[cold]
-    fn shr_bump_slow(&self, force_fair: bool) {
-        self.shr_unlock_slow(force_fair);
+    fn shr_bump_slow(&self) {
+        self.shr_unlock_inner();
         self.shr_lock();
     }
 
     #[inline]
-    fn shr_unlock_inner(&self, force_fair: bool) {
+    fn shr_unlock_inner(&self) {
         let mut state = self.state.load(Ordering::Relaxed);
 
         debug_assert!(state >= INC);
@@ -341,7 +455,7 @@ impl AdaptiveLock {
             }
         }
 
-        self.shr_unlock_slow(force_fair);
+        self.shr_unlock_slow();
     }
 
     #[cold]
@@ -381,17 +495,76 @@ impl AdaptiveLock {
         }
     }
 
+    #[cold]
+    fn unpark_exclusive(&self) {
+        // the writer waiting for readers to leave is parked on the 2nd key
+        let key = self as *const _ as usize + 1;
+        let callback = |result: UnparkResult| {
+            if result.unparked_threads != 0 {
+                self.state.fetch_and(!EXC_PARK_BIT, Ordering::Relaxed);
+            }
+            TOKEN_NORMAL
+        };
+
+        unsafe {
+            parking_lot_core::unpark_one(key, callback);
+        }
+    }
+
+    /// give up `EXC_BIT` after timing out while waiting for readers to leave
+    #[cold]
+    fn abort_exclusive(&self) {
+        let state = self
+            .state
+            .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
+
+        // threads which parked while we held `EXC_BIT` won't be woken up
+        // by the readers, so let all of them try again
+        if state & PARK_BIT != 0 {
+            self.state.fetch_and(!PARK_BIT, Ordering::Relaxed);
+            let key = self as *const _ as usize;
+
+            unsafe {
+                parking_lot_core::unpark_all(key, TOKEN_NORMAL);
+            }
+        }
+    }
+
     #[cold]
     fn upgrade_slow(&self, timeout: Option<Instant>) -> bool {
-        self.state.fetch_or(EXC_BIT, Ordering::Acquire);
-        self.state.fetch_sub(INC, Ordering::Acquire);
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            if state & EXC_BIT != 0 {
+                // another writer is waiting for all readers to leave, including us,
+                // so we can't keep our *shr lock* while upgrading
+                if timeout.is_some() {
+                    return false;
+                }
+
+                unsafe {
+                    self.shr_unlock();
+                }
+                self.exc_lock();
+                return true;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                (state - INC) | EXC_BIT,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(x) => state = x,
+            }
+        }
 
         let has_upgraded = self.wait_for_shared(0, timeout);
 
         if !has_upgraded {
             self.state.fetch_add(INC, Ordering::Relaxed);
-            self.state
-                .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
+            self.abort_exclusive();
         }
 
         has_upgraded
@@ -426,61 +599,68 @@ impl AdaptiveLock {
 
     #[cold]
     #[inline(never)]
-    fn shr_unlock_slow(&self, force_fair: bool) {
-        // this is the last reader, but there may be new
+    fn shr_unlock_slow(&self) {
+        // this may be the last reader, but there may be new
         // shared locks acquired during this call
 
-        if self.state.load(Ordering::Relaxed) & EXC_PARK_BIT == 0 {
-            let key = self as *const _ as usize;
-            let callback = |result: UnparkResult| {
-                if result.unparked_threads != 0 && (force_fair || result.be_fair) {
-                    if result.have_more_threads {
-                        self.state.fetch_or(PARK_BIT, Ordering::Release);
-                    }
-
-                    TOKEN_HANDOFF_SHARED
-                } else {
-                    if result.have_more_threads {
-                        self.state.fetch_sub(INC, Ordering::Release);
-                    } else {
-                        let mut state = self.state.load(Ordering::Relaxed);
-
-                        loop {
-                            let new_state = (state - INC) & !PARK_BIT;
-
-                            if let Err(x) = self.state.compare_exchange_weak(
-                                state,
-                                new_state,
-                                Ordering::Release,
-                                Ordering::Relaxed,
-                            ) {
-                                state = x;
-                            } else {
-                                break;
-                            }
-                        }
-                    }
+        let mut state = self.state.load(Ordering::Relaxed);
 
-                    TOKEN_NORMAL
+        // only the last reader needs to wake up parked threads, and if `EXC_BIT` is set
+        // then the writer that holds it will wake them up once it's done
+        while state & READERS != INC || state & (PARK_BIT | EXC_BIT) != PARK_BIT {
+            if let Err(x) = self.state.compare_exchange_weak(
+                state,
+                state - INC,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                state = x;
+            } else {
+                if state & (READERS | EXC_PARK_BIT) == INC | EXC_PARK_BIT {
+                    self.unpark_exclusive();
                 }
-            };
 
-            unsafe {
-                parking_lot_core::unpark_one(key, callback);
+                return;
             }
-        } else {
-            self.state.fetch_sub(INC, Ordering::Release);
-            let key = self as *const _ as usize + 1;
-            let callback = |result: UnparkResult| {
-                if result.unparked_threads != 0 {
-                    self.state.fetch_and(!EXC_PARK_BIT, Ordering::Relaxed);
+        }
+
+        let prev_state = core::cell::Cell::new(state);
+        let key = self as *const _ as usize;
+        let callback = |result: UnparkResult| {
+            let mut state = self.state.load(Ordering::Relaxed);
+
+            loop {
+                let mut new_state = state - INC;
+
+                if !result.have_more_threads {
+                    new_state &= !PARK_BIT;
                 }
-                TOKEN_NORMAL
-            };
 
-            unsafe {
-                parking_lot_core::unpark_one(key, callback);
+                if let Err(x) = self.state.compare_exchange_weak(
+                    state,
+                    new_state,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                ) {
+                    state = x;
+                } else {
+                    break;
+                }
             }
+
+            prev_state.set(state);
+            TOKEN_NORMAL
+        };
+
+        unsafe {
+            parking_lot_core::unpark_one(key, callback);
+        }
+
+        // a writer may have started waiting for the readers to leave
+        // while we were waking up the parked thread
+        let state = prev_state.get();
+        if state & (READERS | EXC_PARK_BIT) == INC | EXC_PARK_BIT {
+            self.unpark_exclusive();
         }
     }
 
@@ -543,11 +723,7 @@ impl AdaptiveLock {
                 }
 
                 // Timeout expired
-                ParkResult::TimedOut => {
-                    self.unpark_shared();
-
-                    return false;
-                }
+                ParkResult::TimedOut => return false,
             }
         }
 
@@ -557,8 +733,16 @@ impl AdaptiveLock {
     #[cold]
     #[inline(never)]
     fn exc_lock_slow(&self, timeout: Option<Instant>) -> bool {
+        // writers only need `EXC_BIT` to keep new readers out, but if readers are
+        // preferred then writers also wait for all readers to leave before taking it
+        let blocked = if P::WRITER_PREFERRED {
+            EXC_BIT
+        } else {
+            EXC_BIT | READERS
+        };
+
         let try_lock = |state: &mut usize| loop {
-            if *state & EXC_BIT != 0 {
+            if *state & blocked != 0 {
                 return false;
             }
 
@@ -575,29 +759,26 @@ impl AdaptiveLock {
         };
 
         let exclusive = || true;
-        let shared = || {
-            if self.state.fetch_sub(INC, Ordering::Relaxed) & PARK_BIT != 0 {
-                let success = self.wait_for_shared(0, timeout);
-
-                if !success {
-                    self.state
-                        .fetch_and(!(EXC_BIT | EXC_PARK_BIT), Ordering::Relaxed);
-                }
-
-                success
-            } else {
-                true
-            }
-        };
+        let shared = || unreachable!("readers never hand off the lock directly");
 
-        self.lock_slow(
+        if !self.lock_slow(
             TOKEN_EXCLUSIVE,
             timeout,
-            EXC_BIT,
+            blocked,
             try_lock,
             exclusive,
             shared,
-        )
+        ) {
+            return false;
+        }
+
+        // we hold `EXC_BIT`, but readers may still be inside
+        if self.wait_for_shared(0, timeout) {
+            true
+        } else {
+            self.abort_exclusive();
+            false
+        }
     }
 
     #[cold]
@@ -849,4 +1030,247 @@ mod tests {
 
         t.join().unwrap();
     }
+
+    #[test]
+    fn try_upgrade() {
+        use crate::share_lock::ShareGuard;
+
+        let lock = AdaptiveLock::rwlock(0);
+
+        let a = lock.read();
+        let b = lock.read();
+
+        // there is another reader, so upgrading must fail and give back the read guard
+        let a = match ShareGuard::try_upgrade(a) {
+            Ok(_) => panic!("upgraded while there was another reader"),
+            Err(a) => a,
+        };
+        drop(b);
+
+        if *a == 0 {
+            let mut a = ShareGuard::try_upgrade(a).ok().unwrap();
+            *a += 1;
+        }
+
+        assert_eq!(*lock.read(), 1);
+    }
+
+    fn stress<P: Preference>() {
+        let lock = RwLock::<_, P>::new(0);
+
+        crossbeam_utils::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|_| {
+                    for _ in 0..500 {
+                        let mut guard = lock.write();
+                        let value = *guard;
+                        std::thread::yield_now();
+                        *guard = value + 1;
+                    }
+                });
+
+                s.spawn(|_| {
+                    for _ in 0..500 {
+                        let guard = lock.read();
+                        let value = *guard;
+                        std::thread::yield_now();
+                        assert_eq!(
+                            *guard, value,
+                            "a writer entered while a reader held the lock"
+                        );
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        assert_eq!(*lock.read(), 2000);
+    }
+
+    #[test]
+    fn stress_reader_preferred() {
+        stress::<ReaderPreferred>()
+    }
+
+    #[test]
+    fn stress_writer_preferred() {
+        stress::<WriterPreferred>()
+    }
+
+    #[test]
+    fn stress_fair() {
+        stress::<Fair>()
+    }
+
+    fn wait_for_bit<P: Preference>(lock: &RwLock<i32, P>, bit: usize) {
+        while lock.raw().inner().state.load(Ordering::Relaxed) & bit == 0 {
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn preference() {
+        let lock = RwLock::<_, ReaderPreferred>::new(0);
+        let guard = lock.read();
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| *lock.write() += 1);
+
+            // the writer waits for all readers to leave, so new readers can still get in
+            wait_for_bit(&lock, PARK_BIT);
+            assert!(lock.try_read().is_some());
+            drop(guard);
+        })
+        .unwrap();
+
+        let lock = RwLock::<_, WriterPreferred>::new(0);
+        let guard = lock.read();
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| *lock.write() += 1);
+
+            // new readers wait for the writer, but existing readers can still re-enter
+            wait_for_bit(&lock, EXC_BIT);
+            assert!(lock.try_read().is_none());
+            assert!(lock.try_read_recursive().is_some());
+            assert!(!lock.is_locked_exclusive());
+            drop(guard);
+        })
+        .unwrap();
+
+        assert_eq!(*lock.read(), 1);
+    }
+
+    #[test]
+    fn write_timeout() {
+        let lock = RwLock::<_, WriterPreferred>::new(0);
+        let guard = lock.read();
+
+        // the writer gives up, and lets new readers back in
+        assert!(lock
+            .try_write_for(std::time::Duration::from_millis(10))
+            .is_none());
+        assert!(lock.try_read().is_some());
+        drop(guard);
+
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn read_timeout() {
+        use std::time::{Duration, Instant};
+
+        let lock = AdaptiveLock::rwlock(0);
+        let guard = lock.write();
+
+        assert!(lock.try_read_for(Duration::from_millis(10)).is_none());
+        assert!(lock
+            .try_read_until(Instant::now() + Duration::from_millis(10))
+            .is_none());
+        drop(guard);
+
+        assert!(lock.try_read_for(Duration::from_millis(10)).is_some());
+    }
+
+    #[test]
+    fn timed_raw_guards() {
+        use crate::exclusive_lock::RawExclusiveGuard;
+        use crate::share_lock::RawShareGuard;
+        use std::time::{Duration, Instant};
+
+        let lock = AdaptiveLock::new();
+        let guard = RawExclusiveGuard::new(&lock);
+
+        assert!(RawShareGuard::try_new_for(&lock, Duration::from_millis(10)).is_none());
+        assert!(RawExclusiveGuard::try_new_until(
+            &lock,
+            Instant::now() + Duration::from_millis(10)
+        )
+        .is_none());
+        drop(guard);
+
+        let guard = RawShareGuard::try_new_until(&lock, Instant::now() + Duration::from_millis(10));
+        assert!(guard.is_some());
+        assert!(RawExclusiveGuard::try_new_for(&lock, Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn fair_read() {
+        use crate::share_lock::ShareGuard;
+
+        let lock = AdaptiveLock::rwlock(0);
+        let mut guard = lock.read();
+
+        ShareGuard::bump_fair(&mut guard);
+        ShareGuard::unlocked_fair(&mut guard, || assert!(lock.try_write().is_some()));
+        assert!(lock.try_write().is_none());
+
+        ShareGuard::unlock_fair(guard);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn downgrade_guard() {
+        use crate::exclusive_lock::ExclusiveGuard;
+
+        let lock = AdaptiveLock::rwlock((0, 1));
+
+        let mut guard = lock.write();
+        guard.0 += 2;
+        let guard = ExclusiveGuard::downgrade(guard);
+        assert_eq!(*guard, (2, 1));
+        assert!(lock.try_write().is_none());
+        assert!(lock.try_read().is_some());
+        drop(guard);
+
+        let mut guard = ExclusiveGuard::map(lock.write(), |(_, b)| b);
+        *guard += 2;
+        let guard = unsafe { ExclusiveGuard::downgrade_mapped(guard) };
+        assert_eq!(*guard, 3);
+        assert!(lock.try_write().is_none());
+        assert_eq!(*lock.try_read().unwrap(), (2, 3));
+        drop(guard);
+
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn fair_mapped() {
+        use crate::exclusive_lock::ExclusiveGuard;
+        use crate::share_lock::ShareGuard;
+
+        let lock = AdaptiveLock::rwlock((0, 1));
+
+        let mut guard = ExclusiveGuard::map(lock.write(), |(a, _)| a);
+        *guard += 2;
+        ExclusiveGuard::unlock_fair(guard);
+
+        let guard = ShareGuard::map(lock.read(), |(_, b)| b);
+        assert_eq!(*guard, 1);
+        assert!(lock.try_write().is_none());
+        ShareGuard::unlock_fair(guard);
+
+        assert_eq!(*lock.try_write().unwrap(), (2, 1));
+    }
+
+    #[test]
+    fn arc_guards() {
+        use std::sync::Arc;
+
+        let lock = Arc::new(AdaptiveLock::rwlock(0));
+
+        let mut guard = lock.write_arc();
+        assert!(lock.try_read_arc().is_none());
+
+        let thread = std::thread::spawn(move || *guard += 1);
+        thread.join().unwrap();
+
+        let a = lock.read_arc();
+        let b = lock.try_read_arc().unwrap();
+        assert!(lock.try_write_arc().is_none());
+
+        let thread = std::thread::spawn(move || *a + *b);
+        assert_eq!(thread.join().unwrap(), 2);
+        assert!(lock.try_write_arc().is_some());
+    }
 }
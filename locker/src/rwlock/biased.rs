@@ -0,0 +1,444 @@
+//! a reader biased rwlock, based on [BRAVO](https://arxiv.org/abs/1810.01553)
+//!
+//! While the lock is biased towards readers, a *shr lock* is acquired by publishing
+//! the address of the lock in a global table of visible readers, which is a single
+//! uncontended write in the common case. Writers revoke the bias, and wait for all
+//! visible readers of the lock to leave before entering. To avoid slowing down
+//! write heavy workloads, the bias is only restored after some time has passed,
+//! proportional to how long it took to revoke it.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade, RawExclusiveLockFair};
+use crate::mutex::RawMutex;
+use crate::rwlock::RawRwLock;
+use crate::share_lock::{RawShareLock, RawShareLockFair, RawShareLockRecursive};
+use crate::spin_wait::SpinWait;
+use crate::{Init, RawLockInfo};
+
+// the number of slots in the visible readers table, must be a power of 2
+const SLOTS: usize = 1024;
+
+// how much longer than the revocation to wait before restoring the reader bias
+const INHIBIT_MULTIPLIER: u64 = 9;
+
+static VISIBLE_READERS: [AtomicUsize; SLOTS] = [const { AtomicUsize::new(0) }; SLOTS];
+
+std::thread_local! {
+    // the number of *shr locks* the current thread holds through each slot of the visible readers table
+    static HELD: [Cell<u32>; SLOTS] = const { [const { Cell::new(0) }; SLOTS] };
+}
+
+fn now() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+
+    START.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// A reader biased rwlock, which wraps another rwlock
+///
+/// *shr locks* which are acquired through the visible readers table are tied to the
+/// thread which acquired them, so the share guards of this lock are not `Send` or `Sync`.
+pub struct Biased<L> {
+    rbias: AtomicBool,
+    inhibit_until: AtomicU64,
+    lock: L,
+}
+
+impl<L> Biased<L> {
+    /// Wrap the given lock
+    #[inline]
+    pub const fn from_raw(lock: L) -> Self {
+        Self {
+            rbias: AtomicBool::new(false),
+            inhibit_until: AtomicU64::new(0),
+            lock,
+        }
+    }
+
+    /// Get the underlying lock
+    #[inline]
+    pub fn inner(&self) -> &L {
+        &self.lock
+    }
+
+    #[inline]
+    fn addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    #[inline]
+    fn with_slot<R>(&self, f: impl FnOnce(&AtomicUsize, &Cell<u32>) -> R) -> R {
+        HELD.with(|held| {
+            let thread = held.as_ptr() as u64;
+            let hash = (self.addr() as u64 ^ thread.rotate_left(32))
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                >> (64 - SLOTS.trailing_zeros());
+            let index = hash as usize;
+
+            f(&VISIBLE_READERS[index], &held[index])
+        })
+    }
+
+    /// Try to acquire a *shr lock* through the visible readers table
+    #[inline]
+    fn shr_try_lock_fast(&self) -> bool {
+        let addr = self.addr();
+
+        self.with_slot(|slot, held| {
+            if held.get() != 0 {
+                // we already hold this slot, so if it's for this lock, just reuse it
+                let is_ours = slot.load(Ordering::Relaxed) == addr;
+
+                if is_ours {
+                    held.set(held.get() + 1);
+                }
+
+                return is_ours;
+            }
+
+            if !self.rbias.load(Ordering::Relaxed)
+                || slot
+                    .compare_exchange(0, addr, Ordering::SeqCst, Ordering::Relaxed)
+                    .is_err()
+            {
+                return false;
+            }
+
+            // if a writer revoked the bias, then it may not have seen our slot
+            if self.rbias.load(Ordering::SeqCst) {
+                held.set(1);
+                true
+            } else {
+                slot.store(0, Ordering::Relaxed);
+                false
+            }
+        })
+    }
+
+    /// Try to release a *shr lock* acquired through the visible readers table
+    ///
+    /// returns false if the current thread doesn't have a *shr lock* in the visible readers table
+    #[inline]
+    fn shr_unlock_fast(&self) -> bool {
+        let addr = self.addr();
+
+        self.with_slot(|slot, held| {
+            let count = held.get();
+
+            if count == 0 || slot.load(Ordering::Relaxed) != addr {
+                return false;
+            }
+
+            held.set(count - 1);
+
+            if count == 1 {
+                slot.store(0, Ordering::Release);
+            }
+
+            true
+        })
+    }
+
+    /// Restore the reader bias if it has been inhibited for long enough
+    ///
+    /// This must be called while holding a *shr lock* on the underlying lock
+    #[inline]
+    fn try_restore_bias(&self) {
+        if !self.rbias.load(Ordering::Relaxed)
+            && now() >= self.inhibit_until.load(Ordering::Relaxed)
+        {
+            self.rbias.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn has_visible_readers(&self) -> bool {
+        let addr = self.addr();
+
+        VISIBLE_READERS
+            .iter()
+            .any(|slot| slot.load(Ordering::SeqCst) == addr)
+    }
+
+    /// Revoke the reader bias, and wait for all visible readers to leave
+    ///
+    /// This must be called while holding an *exc lock* on the underlying lock
+    #[cold]
+    #[inline(never)]
+    fn revoke(&self) {
+        self.rbias.store(false, Ordering::SeqCst);
+
+        let start = now();
+        let addr = self.addr();
+
+        for slot in VISIBLE_READERS.iter() {
+            let mut spin = SpinWait::new();
+
+            while slot.load(Ordering::SeqCst) == addr {
+                spin.spin();
+            }
+        }
+
+        let end = now();
+        self.inhibit_until
+            .store(end + (end - start) * INHIBIT_MULTIPLIER, Ordering::Relaxed);
+    }
+}
+
+impl<L: Init> Init for Biased<L> {
+    const INIT: Self = Self::from_raw(Init::INIT);
+}
+
+impl<L: Init> Default for Biased<L> {
+    #[inline]
+    fn default() -> Self {
+        Init::INIT
+    }
+}
+
+unsafe impl<L: RawMutex> RawMutex for Biased<L> {}
+unsafe impl<L: RawRwLock> RawRwLock for Biased<L> {}
+unsafe impl<L: RawLockInfo> RawLockInfo for Biased<L> {
+    type ExclusiveGuardTraits = L::ExclusiveGuardTraits;
+    type ShareGuardTraits = (L::ShareGuardTraits, (crate::NoSend, crate::NoSync));
+}
+
+unsafe impl<L: RawExclusiveLock> RawExclusiveLock for Biased<L> {
+    #[inline]
+    fn exc_lock(&self) {
+        self.lock.exc_lock();
+
+        if self.rbias.load(Ordering::Relaxed) {
+            self.revoke();
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        if !self.lock.exc_try_lock() {
+            return false;
+        }
+
+        if self.rbias.load(Ordering::Relaxed) {
+            self.rbias.store(false, Ordering::SeqCst);
+
+            // don't wait for the visible readers, new readers will use the underlying lock
+            if self.has_visible_readers() {
+                unsafe { self.lock.exc_unlock() }
+                return false;
+            }
+        }
+
+        true
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.lock.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        self.lock.exc_bump();
+
+        // readers may have restored the bias while the lock was released
+        if self.rbias.load(Ordering::Relaxed) {
+            self.revoke();
+        }
+    }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.lock.exc_lock_addr()
+    }
+}
+
+unsafe impl<L: RawExclusiveLockFair> RawExclusiveLockFair for Biased<L> {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        self.lock.exc_unlock_fair()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.lock.exc_bump_fair();
+
+        // readers may have restored the bias while the lock was released
+        if self.rbias.load(Ordering::Relaxed) {
+            self.revoke();
+        }
+    }
+}
+
+unsafe impl<L: RawExclusiveLockDowngrade> RawExclusiveLockDowngrade for Biased<L> {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        self.lock.downgrade()
+    }
+}
+
+unsafe impl<L: RawShareLock> RawShareLock for Biased<L> {
+    #[inline]
+    fn shr_lock(&self) {
+        if !self.shr_try_lock_fast() {
+            self.lock.shr_lock();
+            self.try_restore_bias();
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        if self.shr_try_lock_fast() {
+            return true;
+        }
+
+        let locked = self.lock.shr_try_lock();
+
+        if locked {
+            self.try_restore_bias();
+        }
+
+        locked
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        if !self.shr_try_lock_fast() {
+            self.lock.shr_split()
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        if !self.shr_unlock_fast() {
+            self.lock.shr_unlock()
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        if self.shr_unlock_fast() {
+            self.shr_lock()
+        } else {
+            self.lock.shr_bump()
+        }
+    }
+}
+
+unsafe impl<L: RawShareLockRecursive> RawShareLockRecursive for Biased<L> {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        if !self.shr_try_lock_fast() {
+            self.lock.shr_lock_recursive();
+            self.try_restore_bias();
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        if self.shr_try_lock_fast() {
+            return true;
+        }
+
+        let locked = self.lock.shr_try_lock_recursive();
+
+        if locked {
+            self.try_restore_bias();
+        }
+
+        locked
+    }
+}
+
+unsafe impl<L: RawShareLockFair> RawShareLockFair for Biased<L> {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        if !self.shr_unlock_fast() {
+            self.lock.shr_unlock_fair()
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) {
+        if self.shr_unlock_fast() {
+            self.shr_lock()
+        } else {
+            self.lock.shr_bump_fair()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Biased;
+    use crate::rwlock::spin::SpinLock;
+    use crate::rwlock::RwLock;
+
+    #[test]
+    fn readers_and_writers() {
+        let lock = RwLock::<Biased<SpinLock>, _>::new(0);
+
+        crossbeam_utils::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|_| {
+                    for _ in 0..1000 {
+                        let a = lock.read();
+                        let b = a.clone();
+                        assert_eq!(*a, *b);
+                    }
+                });
+            }
+
+            for _ in 0..2 {
+                s.spawn(|_| {
+                    for _ in 0..1000 {
+                        *lock.write() += 1;
+                    }
+                });
+            }
+        })
+        .unwrap();
+
+        assert_eq!(*lock.read(), 2000);
+    }
+
+    #[test]
+    fn bump_revokes_bias() {
+        use crate::exclusive_lock::ExclusiveGuard;
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        let lock = RwLock::<Biased<crate::rwlock::default::DefaultLock>, _>::new(());
+
+        for i in 0..20 {
+            let fair = i % 2 == 0;
+            let read = AtomicBool::new(false);
+
+            crossbeam_utils::thread::scope(|s| {
+                let mut guard = lock.write();
+
+                // the reader restores the bias while the writer is bumping,
+                // bumping lets the reader acquire the lock
+                s.spawn(|_| {
+                    drop(lock.read());
+                    read.store(true, Ordering::SeqCst);
+                });
+
+                while !read.load(Ordering::SeqCst) {
+                    if fair {
+                        ExclusiveGuard::bump_fair(&mut guard);
+                    } else {
+                        ExclusiveGuard::bump(&mut guard);
+                    }
+                }
+
+                s.spawn(|_| assert!(lock.try_read().is_none()))
+                    .join()
+                    .unwrap();
+            })
+            .unwrap();
+        }
+    }
+}
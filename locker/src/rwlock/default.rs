@@ -70,6 +70,20 @@ unsafe impl RawLockInfo for DefaultLock {
     type ShareGuardTraits = <Lock as RawLockInfo>::ShareGuardTraits;
 }
 
+impl crate::RawLockQuery for DefaultLock {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.0.is_locked()
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.0.is_locked_exclusive()
+    }
+}
+
+unsafe impl crate::combinators::Elidable for DefaultLock {}
+
 unsafe impl RawExclusiveLock for DefaultLock {
     #[inline]
     fn exc_lock(&self) {
@@ -132,6 +146,25 @@ unsafe impl RawShareLock for DefaultLock {
     }
 }
 
+unsafe impl crate::share_lock::RawShareLockCount for DefaultLock {
+    #[inline]
+    fn shr_count(&self) -> usize {
+        self.0.shr_count()
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockRecursive for DefaultLock {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        self.0.shr_lock_recursive()
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        self.0.shr_try_lock_recursive()
+    }
+}
+
 unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for DefaultLock {
     #[inline]
     unsafe fn downgrade(&self) {
@@ -149,6 +182,36 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for DefaultLock {
     unsafe fn try_upgrade(&self) -> bool {
         self.0.try_upgrade()
     }
+
+    #[inline]
+    fn shr_lock_upgradable(&self) {
+        self.0.shr_lock_upgradable()
+    }
+
+    #[inline]
+    fn shr_try_lock_upgradable(&self) -> bool {
+        self.0.shr_try_lock_upgradable()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock_upgradable(&self) {
+        self.0.shr_unlock_upgradable()
+    }
+
+    #[inline]
+    unsafe fn upgrade_upgradable(&self) {
+        self.0.upgrade_upgradable()
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_upgradable(&self) -> bool {
+        self.0.try_upgrade_upgradable()
+    }
+
+    #[inline]
+    unsafe fn downgrade_to_upgradable(&self) {
+        self.0.downgrade_to_upgradable()
+    }
 }
 
 #[cfg(feature = "parking_lot_core")]
@@ -165,6 +165,11 @@ unsafe impl RawExclusiveLock for GlobalLock {
     unsafe fn exc_bump(&self) {
         self.get().exc_bump()
     }
+
+    #[inline]
+    fn exc_lock_addr(&self) -> usize {
+        self.get().exc_lock_addr()
+    }
 }
 
 #[cfg(feature = "parking_lot_core")]
@@ -253,6 +258,30 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for GlobalLock {
     unsafe fn try_upgrade(&self) -> bool {
         self.get().try_upgrade()
     }
+
+    fn shr_lock_upgradable(&self) {
+        self.get().shr_lock_upgradable()
+    }
+
+    fn shr_try_lock_upgradable(&self) -> bool {
+        self.get().shr_try_lock_upgradable()
+    }
+
+    unsafe fn shr_unlock_upgradable(&self) {
+        self.get().shr_unlock_upgradable()
+    }
+
+    unsafe fn upgrade_upgradable(&self) {
+        self.get().upgrade_upgradable()
+    }
+
+    unsafe fn try_upgrade_upgradable(&self) -> bool {
+        self.get().try_upgrade_upgradable()
+    }
+
+    unsafe fn downgrade_to_upgradable(&self) {
+        self.get().downgrade_to_upgradable()
+    }
 }
 
 #[cfg(test)]
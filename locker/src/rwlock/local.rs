@@ -2,7 +2,8 @@
 
 use core::cell::Cell;
 
-const EXC_LOCK: usize = !0;
+const EXC_BIT: usize = 0b01;
+const INC: usize = 0b10;
 
 /// a local (single-threaded) raw mutex
 pub type RawMutex = crate::mutex::raw::Mutex<LocalLock>;
@@ -14,6 +15,9 @@ pub type RawRwLock = crate::rwlock::raw::RwLock<LocalLock>;
 pub type RwLock<T> = crate::rwlock::RwLock<LocalLock, T>;
 
 /// a local (single-threaded) rwlock lock
+///
+/// This can be used as a drop-in replacement for a `RefCell`, because locking
+/// a locked `LocalLock` panics instead of deadlocking.
 pub struct LocalLock {
     state: Cell<usize>,
 }
@@ -68,7 +72,7 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalLock {
     #[inline]
     fn exc_try_lock(&self) -> bool {
         if self.state.get() == 0 {
-            self.state.set(EXC_LOCK);
+            self.state.set(EXC_BIT | INC);
             true
         } else {
             false
@@ -77,13 +81,43 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for LocalLock {
 
     #[inline]
     unsafe fn exc_unlock(&self) {
-        self.state.set(0);
+        let state = self.state.get();
+        debug_assert_ne!(state & EXC_BIT, 0, "Can't unlock an unlocked local lock");
+
+        if state == EXC_BIT | INC {
+            self.state.set(0);
+        } else {
+            self.state.set(state - INC);
+        }
     }
 
     #[inline]
     unsafe fn exc_bump(&self) {}
 }
 
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for LocalLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        crate::exclusive_lock::RawExclusiveLock::exc_unlock(self)
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {}
+}
+
+unsafe impl crate::exclusive_lock::SplittableExclusiveLock for LocalLock {
+    #[inline]
+    unsafe fn exc_split(&self) {
+        let state = self
+            .state
+            .get()
+            .checked_add(INC)
+            .expect("tried to split the exclusive lock too many times");
+
+        self.state.set(state);
+    }
+}
+
 unsafe impl crate::share_lock::RawShareLock for LocalLock {
     #[inline]
     fn shr_lock(&self) {
@@ -95,7 +129,13 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
 
     #[inline]
     fn shr_try_lock(&self) -> bool {
-        if let Some(new_state) = self.state.get().checked_add(1) {
+        let state = self.state.get();
+
+        if state & EXC_BIT != 0 {
+            return false;
+        }
+
+        if let Some(new_state) = state.checked_add(INC) {
             self.state.set(new_state);
             true
         } else {
@@ -113,7 +153,7 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
 
     #[inline]
     unsafe fn shr_unlock(&self) {
-        let (state, ovf) = self.state.get().overflowing_sub(1);
+        let (state, ovf) = self.state.get().overflowing_sub(INC);
         debug_assert!(!ovf, "Can't unlock an unlocked local lock");
         self.state.set(state);
     }
@@ -122,15 +162,25 @@ unsafe impl crate::share_lock::RawShareLock for LocalLock {
     unsafe fn shr_bump(&self) {}
 }
 
+unsafe impl crate::share_lock::RawShareLockFair for LocalLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        crate::share_lock::RawShareLock::shr_unlock(self)
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) {}
+}
+
 unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for LocalLock {
     unsafe fn downgrade(&self) {
         debug_assert_eq!(
             self.state.get(),
-            EXC_LOCK,
-            "cannot downgrade a shared lock!"
+            EXC_BIT | INC,
+            "cannot downgrade a shared or split exclusive lock!"
         );
 
-        self.state.set(1);
+        self.state.set(INC);
     }
 }
 
@@ -145,8 +195,8 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for LocalLock {
     unsafe fn try_upgrade(&self) -> bool {
         let state = self.state.get();
 
-        if state == 1 {
-            self.state.set(EXC_LOCK);
+        if state == INC {
+            self.state.set(EXC_BIT | INC);
 
             true
         } else {
@@ -154,3 +204,23 @@ unsafe impl crate::share_lock::RawShareLockUpgrade for LocalLock {
         }
     }
 }
+
+#[test]
+fn test_split_exclusive() {
+    let lock = LocalLock::rwlock((0, 0));
+
+    let guard = lock.write();
+    let (mut a, mut b) = crate::exclusive_lock::ExclusiveGuard::split_map(guard, |(a, b)| (a, b));
+    *a += 1;
+    *b += 1;
+    drop(a);
+    assert!(lock.try_read().is_none());
+    drop(b);
+
+    let a = lock.read();
+    let b = lock.read();
+    assert!(lock.try_write().is_none());
+    drop((a, b));
+
+    assert_eq!(*lock.write(), (1, 1));
+}
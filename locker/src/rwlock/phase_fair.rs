@@ -0,0 +1,316 @@
+//! a phase-fair spin rwlock
+//!
+//! This is the ticket based phase-fair rwlock (PF-T) from
+//! [Reader-Writer Synchronization for Shared-Memory Multiprocessor Real-Time Systems](https://www.cs.unc.edu/~anderson/papers/ecrts09b.pdf).
+//!
+//! Readers and writers alternate in phases, when a writer arrives it waits for the
+//! current readers to leave, and any readers which arrive after it wait for it
+//! to unlock. When a writer unlocks, all waiting readers enter at once, before the
+//! next writer. So a reader waits for at most one writer, and a writer waits for at
+//! most one reader phase per writer ahead of it, which gives bounded latency for both
+//! readers and writers.
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::share_lock::RawShareLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// the phase id of the current writer
+const PHASE_ID: usize = 0b01;
+// set while there is a writer waiting for readers to leave or holding the lock
+const PRESENT: usize = 0b10;
+const WRITER_BITS: usize = PHASE_ID | PRESENT;
+// readers are counted in the rest of the bits
+const ONE_READER: usize = 0b100;
+
+/// a raw mutex backed by a phase-fair rwlock
+pub type RawMutex = crate::mutex::raw::Mutex<PhaseFairLock>;
+
+/// a mutex backed by a phase-fair rwlock
+pub type Mutex<T> = crate::mutex::Mutex<PhaseFairLock, T>;
+
+/// a raw rwlock backed by a phase-fair rwlock
+///
+/// Readers and writers alternate, so both reader and writer latency is bounded
+pub type RawRwLock = crate::rwlock::raw::RwLock<PhaseFairLock>;
+
+/// a rwlock backed by a phase-fair rwlock
+///
+/// Readers and writers alternate, so both reader and writer latency is bounded
+pub type RwLock<T> = crate::rwlock::RwLock<PhaseFairLock, T>;
+
+/// A phase-fair spin rwlock
+///
+/// Writers are served in FIFO order, and alternate with phases of readers.
+///
+/// Like [`TicketLock`](crate::rwlock::ticket::TicketLock), waiting threads never park,
+/// so this lock is best suited to short critical sections.
+pub struct PhaseFairLock {
+    read_in: AtomicUsize,
+    read_out: AtomicUsize,
+    write_in: AtomicUsize,
+    write_out: AtomicUsize,
+}
+
+impl PhaseFairLock {
+    /// create a new phase-fair rwlock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            read_in: AtomicUsize::new(0),
+            read_out: AtomicUsize::new(0),
+            write_in: AtomicUsize::new(0),
+            write_out: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new phase-fair rwlock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new phase-fair rwlock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new phase-fair rwlock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new phase-fair rwlock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    /// start a writer phase, and return the number of readers that must leave before the writer can enter
+    #[inline]
+    fn start_write_phase(&self, ticket: usize) -> usize {
+        self.read_in
+            .fetch_add(PRESENT | (ticket & PHASE_ID), Ordering::Acquire)
+            & !WRITER_BITS
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait_for_writers(&self, ticket: usize) {
+        let mut spin = SpinWait::new();
+
+        while self.write_out.load(Ordering::Acquire) != ticket {
+            spin.spin();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait_for_readers(&self, readers: usize) {
+        let mut spin = SpinWait::new();
+
+        while self.read_out.load(Ordering::Acquire) != readers {
+            spin.spin();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn shr_lock_slow(&self, phase: usize) {
+        let mut spin = SpinWait::new();
+
+        // wait until the writer phase ends
+        while self.read_in.load(Ordering::Acquire) & WRITER_BITS == phase {
+            spin.spin();
+        }
+    }
+}
+
+impl Default for PhaseFairLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for PhaseFairLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for PhaseFairLock {}
+unsafe impl crate::rwlock::RawRwLock for PhaseFairLock {}
+unsafe impl crate::RawLockInfo for PhaseFairLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+unsafe impl RawExclusiveLock for PhaseFairLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let ticket = self.write_in.fetch_add(1, Ordering::Relaxed);
+
+        if self.write_out.load(Ordering::Acquire) != ticket {
+            self.wait_for_writers(ticket);
+        }
+
+        let readers = self.start_write_phase(ticket);
+
+        if self.read_out.load(Ordering::Acquire) != readers {
+            self.wait_for_readers(readers);
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let ticket = self.write_out.load(Ordering::Relaxed);
+
+        if self.read_in.load(Ordering::Relaxed) != self.read_out.load(Ordering::Relaxed)
+            || self
+                .write_in
+                .compare_exchange(
+                    ticket,
+                    ticket.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return false;
+        }
+
+        if self.read_out.load(Ordering::Acquire) == self.start_write_phase(ticket) {
+            true
+        } else {
+            // a reader entered before we started the writer phase, so back out
+            unsafe { self.exc_unlock() }
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // end the writer phase, this lets in all waiting readers
+        self.read_in.fetch_and(!WRITER_BITS, Ordering::Release);
+        self.write_out.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        let write_out = self.write_out.load(Ordering::Relaxed);
+        let read_in = self.read_in.load(Ordering::Relaxed) & !WRITER_BITS;
+
+        // if there are readers or writers waiting, let them run
+        if self.write_in.load(Ordering::Relaxed) != write_out.wrapping_add(1)
+            || read_in != self.read_out.load(Ordering::Relaxed)
+        {
+            self.exc_unlock();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for PhaseFairLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // a phase-fair lock always hands the lock over to the waiting readers
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.exc_bump()
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for PhaseFairLock {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        // count ourselves as a reader before ending the writer phase
+        self.read_in.fetch_add(ONE_READER, Ordering::Relaxed);
+        self.exc_unlock();
+    }
+}
+
+unsafe impl RawShareLock for PhaseFairLock {
+    #[inline]
+    fn shr_lock(&self) {
+        let phase = self.read_in.fetch_add(ONE_READER, Ordering::Acquire) & WRITER_BITS;
+
+        if phase != 0 && self.read_in.load(Ordering::Acquire) & WRITER_BITS == phase {
+            self.shr_lock_slow(phase);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        let read_in = self.read_in.load(Ordering::Relaxed);
+
+        read_in & WRITER_BITS == 0
+            && self
+                .read_in
+                .compare_exchange(
+                    read_in,
+                    read_in.wrapping_add(ONE_READER),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        // a waiting writer waits for `read_out` to reach the number of readers
+        // when it arrived, so it will need to wait for one more unlock
+        self.read_out.fetch_sub(ONE_READER, Ordering::Relaxed);
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.read_out.fetch_add(ONE_READER, Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        // if a writer is waiting, let it run
+        if self.read_in.load(Ordering::Relaxed) & WRITER_BITS != 0 {
+            self.shr_unlock();
+            self.shr_lock();
+        }
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for PhaseFairLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        // a phase-fair lock always hands the lock over to the waiting writer
+        self.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) {
+        self.shr_bump()
+    }
+}
+
+#[test]
+fn test_phases() {
+    let lock = PhaseFairLock::rwlock(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *lock.write() += 1;
+                    let a = lock.read();
+                    let b = a.clone();
+                    assert_eq!(*a, *b);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let guard = lock.read();
+    assert_eq!(*guard, 4000);
+    assert!(lock.try_write().is_none());
+    drop(guard);
+    assert!(lock.try_write().is_some());
+}
@@ -0,0 +1,176 @@
+//! a lock backed by a `pthread_rwlock_t`
+//!
+//! pthread locks can't be moved once they have been used, so they
+//! are lazily allocated and initialized the first time they are locked.
+
+use std::boxed::Box;
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::mutex::pthread::LazyBox;
+use crate::share_lock::RawShareLock;
+
+/// a raw mutex backed by a `pthread_rwlock_t`
+pub type RawMutex = crate::mutex::raw::Mutex<PthreadLock>;
+
+/// a mutex backed by a `pthread_rwlock_t`
+pub type Mutex<T> = crate::mutex::Mutex<PthreadLock, T>;
+
+/// a raw rwlock backed by a `pthread_rwlock_t`
+pub type RawRwLock = crate::rwlock::raw::RwLock<PthreadLock>;
+
+/// a rwlock backed by a `pthread_rwlock_t`
+pub type RwLock<T> = crate::rwlock::RwLock<PthreadLock, T>;
+
+/// A lock backed by a `pthread_rwlock_t`
+///
+/// A `pthread_rwlock_t` must be unlocked on the same thread that locked it,
+/// so the guards of this lock are not `Send`.
+///
+/// Some platforms prefer writers, so cloning a share guard may deadlock
+/// if another thread is waiting to acquire an exclusive lock.
+pub struct PthreadLock {
+    lock: LazyBox<libc::pthread_rwlock_t>,
+}
+
+// SAFETY: the rwlock is boxed, so it never moves, and it is synchronized by the OS
+unsafe impl Send for PthreadLock {}
+unsafe impl Sync for PthreadLock {}
+
+impl PthreadLock {
+    /// create a new pthread lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: LazyBox::new(),
+        }
+    }
+
+    /// create a new pthread lock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new pthread lock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new pthread lock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new pthread lock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[inline]
+    fn raw(&self) -> *mut libc::pthread_rwlock_t {
+        unsafe { self.lock.get_or_init(Self::init, Self::destroy) }
+    }
+
+    fn init() -> Box<libc::pthread_rwlock_t> {
+        Box::new(libc::PTHREAD_RWLOCK_INITIALIZER)
+    }
+
+    unsafe fn destroy(rwlock: *mut libc::pthread_rwlock_t) {
+        libc::pthread_rwlock_destroy(rwlock);
+    }
+}
+
+impl Drop for PthreadLock {
+    fn drop(&mut self) {
+        if let Some(mut rwlock) = self.lock.take() {
+            unsafe {
+                // destroying a locked rwlock is undefined behavior,
+                // so if a guard was leaked, then leak the rwlock too
+                if libc::pthread_rwlock_trywrlock(&mut *rwlock) == 0 {
+                    libc::pthread_rwlock_unlock(&mut *rwlock);
+                    Self::destroy(&mut *rwlock);
+                } else {
+                    Box::leak(rwlock);
+                }
+            }
+        }
+    }
+}
+
+impl Default for PthreadLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for PthreadLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for PthreadLock {}
+unsafe impl crate::rwlock::RawRwLock for PthreadLock {}
+unsafe impl crate::RawLockInfo for PthreadLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = crate::NoSend;
+}
+
+unsafe impl RawExclusiveLock for PthreadLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let r = unsafe { libc::pthread_rwlock_wrlock(self.raw()) };
+        // relocking on the same thread may fail with `EDEADLK` instead of deadlocking,
+        // so the lock must not be treated as acquired if this fails
+        if r == libc::EDEADLK {
+            panic!("rwlock write lock would result in deadlock")
+        }
+        assert_eq!(r, 0, "failed to acquire an rwlock write lock");
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        unsafe { libc::pthread_rwlock_trywrlock(self.raw()) == 0 }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        let r = libc::pthread_rwlock_unlock(self.raw());
+        debug_assert_eq!(r, 0);
+    }
+}
+
+unsafe impl RawShareLock for PthreadLock {
+    #[inline]
+    fn shr_lock(&self) {
+        let r = unsafe { libc::pthread_rwlock_rdlock(self.raw()) };
+        assert_ne!(r, libc::EAGAIN, "Tried to create too many shared locks!");
+        if r == libc::EDEADLK {
+            panic!("rwlock read lock would result in deadlock")
+        }
+        assert_eq!(r, 0, "failed to acquire an rwlock read lock");
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        unsafe { libc::pthread_rwlock_tryrdlock(self.raw()) == 0 }
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        self.shr_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let r = libc::pthread_rwlock_unlock(self.raw());
+        debug_assert_eq!(r, 0);
+    }
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn test_relock_panics() {
+    let lock = PthreadLock::rwlock(7);
+
+    let _write = lock.write();
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock.read())).is_err());
+}
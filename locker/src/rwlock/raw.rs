@@ -2,7 +2,10 @@
 
 use super::RawRwLock;
 use crate::exclusive_lock::{RawExclusiveGuard, RawExclusiveLockTimed};
-use crate::share_lock::{RawShareGuard, RawShareLockTimed};
+use crate::share_lock::{
+    RawShareGuard, RawShareLockCount, RawShareLockRecursive, RawShareLockTimed,
+    RawShareLockUpgrade, RawUpgradableGuard,
+};
 
 /// A read-write syncronization primitive useful for protecting shared data
 ///
@@ -159,6 +162,105 @@ where
     }
 }
 
+impl<L: RawRwLock + RawShareLockRecursive + ?Sized> RwLock<L>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// Unlike [`read`](Self::read), this will acquire the lock even if there are writers waiting
+    /// for the lock, so it is safe to use when the current thread already holds a read lock.
+    /// This may starve writers.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    #[inline]
+    pub fn read_recursive(&self) -> RawShareGuard<'_, L> {
+        unsafe {
+            self.lock.shr_lock_recursive();
+            self.read_unchecked()
+        }
+    }
+
+    /// Attempts to acquire this RwLock with shared read access, even if there are writers waiting
+    /// for the lock.
+    ///
+    /// If the access could not be granted at this time, then None is returned.
+    /// Otherwise, an RAII guard is returned which will release the shared access when it is dropped.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_read_recursive(&self) -> Option<RawShareGuard<'_, L>> {
+        unsafe {
+            if self.lock.shr_try_lock_recursive() {
+                Some(self.read_unchecked())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl<L: RawRwLock + crate::RawLockQuery + ?Sized> RwLock<L> {
+    /// Returns true if there are any readers or writers which currently hold this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_locked()
+    }
+
+    /// Returns true if there is a writer which currently holds this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for assertions, debug logging and heuristics.
+    #[inline]
+    pub fn is_locked_exclusive(&self) -> bool {
+        self.lock.is_locked_exclusive()
+    }
+}
+
+impl<L: RawRwLock + RawShareLockCount + ?Sized> RwLock<L> {
+    /// Returns the number of readers which currently hold this `RwLock`
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for diagnostics, like metrics or debugging writer starvation.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.lock.shr_count()
+    }
+}
+
+impl<L: RawRwLock + RawShareLockUpgrade + ?Sized> RwLock<L>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Locks this `RwLock` with upgradable read access, blocking the current thread until it can be acquired.
+    ///
+    /// The calling thread will be blocked until there are no more writers or other upgradable reads
+    /// which hold the lock. There may be other readers currently inside the lock when this method returns.
+    ///
+    /// Returns an RAII guard which will release this thread's upgradable access once it is dropped,
+    /// or which can be upgraded to write access without deadlocking.
+    #[inline]
+    pub fn upgradable_read(&self) -> RawUpgradableGuard<'_, L> {
+        RawUpgradableGuard::new(&self.lock)
+    }
+
+    /// Attempts to acquire this RwLock with upgradable read access.
+    ///
+    /// If the access could not be granted at this time, then None is returned.
+    /// Otherwise, an RAII guard is returned which will release the upgradable access when it is dropped.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_upgradable_read(&self) -> Option<RawUpgradableGuard<'_, L>> {
+        RawUpgradableGuard::try_new(&self.lock)
+    }
+}
+
 impl<L: RawRwLock + RawExclusiveLockTimed + RawShareLockTimed + ?Sized> RwLock<L>
 where
     L::ExclusiveGuardTraits: crate::Inhabitted,
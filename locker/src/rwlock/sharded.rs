@@ -3,10 +3,13 @@
 use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockFair};
 use crate::mutex::RawMutex;
 use crate::remutex::ThreadInfo;
-use crate::rwlock::RawRwLock;
 use crate::share_lock::{RawShareLock, RawShareLockFair};
 use crate::RawLockInfo;
 
+use crate::exclusive_lock::RawExclusiveLockDowngrade;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
 pub struct Sharded<I, S: ?Sized> {
     thread_info: I,
     shards: S,
@@ -25,7 +28,9 @@ impl<I, S> Sharded<I, S> {
 }
 
 #[cfg(feature = "nightly")]
-impl<I: ThreadInfo + crate::Init, S: RawRwLock + crate::Init, const N: usize> Sharded<I, [S; N]> {
+impl<I: ThreadInfo + crate::Init, S: crate::rwlock::RawRwLock + crate::Init, const N: usize>
+    Sharded<I, [S; N]>
+{
     pub fn new() -> Self {
         unsafe {
             use core::mem::MaybeUninit;
@@ -46,7 +51,10 @@ impl<I: ThreadInfo + crate::Init, S: RawRwLock + crate::Init, const N: usize> Sh
 }
 
 unsafe impl<I: ThreadInfo, S: RawMutex> RawMutex for Sharded<I, [S]> {}
-unsafe impl<I: ThreadInfo, S: RawRwLock> RawRwLock for Sharded<I, [S]> {}
+unsafe impl<I: ThreadInfo, S: crate::rwlock::RawRwLock> crate::rwlock::RawRwLock
+    for Sharded<I, [S]>
+{
+}
 
 impl<I: ThreadInfo, S> Sharded<I, [S]> {
     pub fn get(&self) -> &S {
@@ -171,3 +179,265 @@ unsafe impl<I: ThreadInfo, S: RawExclusiveLockFair> RawExclusiveLockFair for Sha
         self.shards.iter().for_each(|shard| shard.exc_unlock_fair())
     }
 }
+
+/// The default number of shards in a [`ShardedLock`]
+pub const DEFAULT_SHARDS: usize = 8;
+
+/// a raw rwlock with sharded reader counts
+pub type RawRwLock<const N: usize = DEFAULT_SHARDS> = crate::rwlock::raw::RwLock<ShardedLock<N>>;
+
+/// a rwlock with sharded reader counts
+pub type RwLock<T, const N: usize = DEFAULT_SHARDS> = crate::rwlock::RwLock<ShardedLock<N>, T>;
+
+/// the raw lock which backs [`RwLock`]
+pub type RawLock<const N: usize = DEFAULT_SHARDS> = ShardedLock<N>;
+
+#[repr(align(64))]
+struct Shard {
+    readers: AtomicUsize,
+}
+
+impl Shard {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NEW: Self = Self {
+        readers: AtomicUsize::new(0),
+    };
+}
+
+fn shard_hint() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    std::thread_local! {
+        static HINT: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    HINT.with(|&hint| hint)
+}
+
+/// A rwlock which spreads it's reader count over `N` shards, each in their own cache line
+///
+/// Each thread only touches a single shard to acquire a *shr lock*, so readers on
+/// different threads don't contend with each other. Writers set a flag which
+/// blocks new readers, and then wait for every shard to drain.
+///
+/// The *shr lock* must be released on the thread which acquired it,
+/// so the share guards of this lock are not `Send`.
+pub struct ShardedLock<const N: usize = DEFAULT_SHARDS> {
+    writer: AtomicBool,
+    shards: [Shard; N],
+}
+
+impl<const N: usize> ShardedLock<N> {
+    /// create a new sharded lock
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(
+            N != 0,
+            "You cannot use an empty shard list in a `ShardedLock`"
+        );
+
+        Self {
+            writer: AtomicBool::new(false),
+            shards: [Shard::NEW; N],
+        }
+    }
+
+    /// create a new sharded lock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock<N> {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new sharded lock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T, N> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[inline]
+    fn shard(&self) -> &AtomicUsize {
+        &self.shards[shard_hint() % N].readers
+    }
+
+    // the shards must be loaded with `SeqCst`, so that either the writer sees the reader's
+    // increment, or the reader sees the writer flag in `shr_try_lock_in`
+    #[inline]
+    fn has_readers(&self) -> bool {
+        self.shards
+            .iter()
+            .any(|shard| shard.readers.load(Ordering::SeqCst) != 0)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn exc_lock_slow(&self) {
+        let mut spin = SpinWait::new();
+
+        while self
+            .writer
+            .compare_exchange_weak(false, true, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            spin.spin();
+        }
+
+        // new readers will see the writer flag, so wait for the current readers to leave
+        for shard in self.shards.iter() {
+            let mut spin = SpinWait::new();
+
+            while shard.readers.load(Ordering::SeqCst) != 0 {
+                spin.spin();
+            }
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn shr_lock_slow(&self, shard: &AtomicUsize) {
+        let mut spin = SpinWait::new();
+
+        loop {
+            while self.writer.load(Ordering::Relaxed) {
+                spin.spin();
+            }
+
+            if self.shr_try_lock_in(shard) {
+                return;
+            }
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_in(&self, shard: &AtomicUsize) -> bool {
+        shard.fetch_add(1, Ordering::SeqCst);
+
+        if self.writer.load(Ordering::SeqCst) {
+            shard.fetch_sub(1, Ordering::Release);
+            false
+        } else {
+            true
+        }
+    }
+}
+
+impl<const N: usize> Default for ShardedLock<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> crate::Init for ShardedLock<N> {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl<const N: usize> RawMutex for ShardedLock<N> {}
+unsafe impl<const N: usize> crate::rwlock::RawRwLock for ShardedLock<N> {}
+unsafe impl<const N: usize> RawLockInfo for ShardedLock<N> {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = crate::NoSend;
+}
+
+unsafe impl<const N: usize> RawExclusiveLock for ShardedLock<N> {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.exc_lock_slow()
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        if self
+            .writer
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+
+        if self.has_readers() {
+            self.writer.store(false, Ordering::Release);
+            false
+        } else {
+            true
+        }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        self.writer.store(false, Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        // there are never any parked threads in a sharded lock
+    }
+}
+
+unsafe impl<const N: usize> RawExclusiveLockDowngrade for ShardedLock<N> {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        self.shard().fetch_add(1, Ordering::Relaxed);
+        self.writer.store(false, Ordering::Release);
+    }
+}
+
+unsafe impl<const N: usize> RawShareLock for ShardedLock<N> {
+    #[inline]
+    fn shr_lock(&self) {
+        let shard = self.shard();
+
+        if !self.shr_try_lock_in(shard) {
+            self.shr_lock_slow(shard)
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        !self.writer.load(Ordering::Relaxed) && self.shr_try_lock_in(self.shard())
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        // we already hold a *shr lock*, so no writer can get in
+        self.shard().fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let readers = self.shard().fetch_sub(1, Ordering::Release);
+        debug_assert_ne!(readers, 0, "Can't unlock an unlocked sharded lock");
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        // there are never any parked threads in a sharded lock
+    }
+}
+
+#[test]
+fn test_sharded_lock() {
+    let lock = ShardedLock::<4>::rwlock(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    let a = lock.read();
+                    let b = a.clone();
+                    assert_eq!(*a, *b);
+                }
+            });
+        }
+
+        for _ in 0..2 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *lock.write() += 1;
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*lock.read(), 2000);
+}
@@ -1,9 +1,16 @@
 //! a spin lock
 
-use crate::spin_wait::SpinWait;
+use crate::relax::Backoff;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-const EXC_LOCK: usize = !0;
+// set while there is an *exc lock*
+const EXC_BIT: usize = 0b01;
+// set while a thread is waiting for an *exc lock*, this blocks new *shr locks*
+const PENDING_BIT: usize = 0b10;
+// set while there is an *upg lock*, which is also counted as a *shr lock*
+const UPG_BIT: usize = 0b100;
+// the rest of the state is the number of *shr locks*
+const ONE_READER: usize = 0b1000;
 
 /// a raw mutex backed by a spin lock
 ///
@@ -53,6 +60,9 @@ pub type RwLock<T> = crate::rwlock::RwLock<SpinLock, T>;
 /// `parking_lot_core`, then you will automatically get adaptive strategys,
 /// which are more efficient in the general case. All this without sacrificing
 /// platforms that can't support adaptive strategys.
+///
+/// This lock prefers writers, once a thread starts waiting for an *exc lock*
+/// no new *shr locks* can be acquired (but existing *shr locks* can still be split).
 pub struct SpinLock {
     state: AtomicUsize,
 }
@@ -88,55 +98,118 @@ impl SpinLock {
 
     #[cold]
     fn exc_lock_slow(&self) {
-        let mut spin = SpinWait::new();
+        let mut backoff = Backoff::new();
+        let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            if self
-                .state
-                .compare_exchange_weak(0, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-                .is_ok()
-            {
-                break;
+            // there are no readers or writers, so try to take the lock
+            if state & !PENDING_BIT == 0 {
+                match self.state.compare_exchange_weak(
+                    state,
+                    EXC_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(x) => state = x,
+                }
+
+                // another thread changed the state, so back off before trying again
+                backoff.spin();
+                continue;
             }
 
-            spin.spin();
+            // block any new readers until we get the lock
+            if state & PENDING_BIT == 0 {
+                self.state.fetch_or(PENDING_BIT, Ordering::Relaxed);
+            }
+
+            backoff.snooze();
+            state = self.state.load(Ordering::Relaxed);
         }
     }
 
-    #[cold]
-    fn shr_lock_slow(&self) {
-        let mut spin = SpinWait::new();
+    // `blocked` is the set of bits which prevent new *shr locks*,
+    // and `inc` is added to the state to acquire the lock
+    #[inline]
+    fn shr_try_lock_inner(&self, blocked: usize, inc: usize) -> bool {
         let state = self.state.load(Ordering::Relaxed);
 
+        if state & blocked != 0 {
+            return false;
+        }
+
+        if let Some(new_state) = state.checked_add(inc) {
+            self.state
+                .compare_exchange(state, new_state, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        } else {
+            false
+        }
+    }
+
+    #[cold]
+    fn shr_lock_slow(&self, blocked: usize, inc: usize) {
+        let mut backoff = Backoff::new();
+        let mut state = self.state.load(Ordering::Relaxed);
+
         loop {
-            if let Some(new_state) = state.checked_add(1) {
-                if self
-                    .state
-                    .compare_exchange_weak(state, new_state, Ordering::Acquire, Ordering::Relaxed)
-                    .is_ok()
-                {
-                    break;
+            if state & blocked == 0 {
+                let new_state = state
+                    .checked_add(inc)
+                    .expect("Tried to create too many shared locks!");
+
+                match self.state.compare_exchange_weak(
+                    state,
+                    new_state,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(x) => state = x,
                 }
+
+                // another thread changed the state, so back off before trying again
+                backoff.spin();
+                continue;
             }
 
-            spin.spin();
+            backoff.snooze();
+            state = self.state.load(Ordering::Relaxed);
         }
     }
 
+    // `owned` is the part of the state which is owned by the upgrading thread
     #[cold]
-    fn upgrade_slow(&self) {
-        let mut spin = SpinWait::new();
+    fn upgrade_slow(&self, owned: usize) {
+        let mut backoff = Backoff::new();
+        let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            if self
-                .state
-                .compare_exchange_weak(1, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-                .is_ok()
-            {
-                break;
+            // we are the only reader, so try to take the lock
+            if state & !PENDING_BIT == owned {
+                match self.state.compare_exchange_weak(
+                    state,
+                    EXC_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(x) => state = x,
+                }
+
+                // another thread changed the state, so back off before trying again
+                backoff.spin();
+                continue;
             }
 
-            spin.spin();
+            // block any new readers until we get the lock
+            if state & PENDING_BIT == 0 {
+                self.state.fetch_or(PENDING_BIT, Ordering::Relaxed);
+            }
+
+            backoff.snooze();
+            state = self.state.load(Ordering::Relaxed);
         }
     }
 }
@@ -148,10 +221,24 @@ impl crate::Init for SpinLock {
 unsafe impl crate::mutex::RawMutex for SpinLock {}
 unsafe impl crate::rwlock::RawRwLock for SpinLock {}
 unsafe impl crate::RawLockInfo for SpinLock {
-    type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
-    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+    type ExclusiveGuardTraits = (crate::SendGuard, crate::NoSync);
+    type ShareGuardTraits = (crate::SendGuard, crate::NoSync);
 }
 
+impl crate::RawLockQuery for SpinLock {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & !PENDING_BIT != 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & EXC_BIT != 0
+    }
+}
+
+unsafe impl crate::combinators::Elidable for SpinLock {}
+
 unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
     #[inline]
     fn exc_lock(&self) {
@@ -162,14 +249,20 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
 
     #[inline]
     fn exc_try_lock(&self) -> bool {
-        self.state
-            .compare_exchange(0, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+        let state = self.state.load(Ordering::Relaxed);
+
+        state & !PENDING_BIT == 0
+            && self
+                .state
+                .compare_exchange(state, EXC_BIT, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
     }
 
     #[inline]
     unsafe fn exc_unlock(&self) {
-        self.state.store(0, Ordering::Release);
+        // keep the pending bit, so that waiting writers still get priority over new readers
+        let state = self.state.fetch_and(!EXC_BIT, Ordering::Release);
+        debug_assert_ne!(state & EXC_BIT, 0, "Can't unlock an unlocked spin lock");
     }
 
     #[inline]
@@ -181,7 +274,9 @@ unsafe impl crate::exclusive_lock::RawExclusiveLock for SpinLock {
 unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for SpinLock {
     #[inline]
     unsafe fn downgrade(&self) {
-        self.state.store(1, Ordering::Relaxed);
+        // EXC_BIT + (ONE_READER - EXC_BIT) = ONE_READER, this doesn't touch the pending bit
+        self.state
+            .fetch_add(ONE_READER - EXC_BIT, Ordering::Release);
     }
 }
 
@@ -189,21 +284,13 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
     #[inline]
     fn shr_lock(&self) {
         if !self.shr_try_lock() {
-            self.shr_lock_slow();
+            self.shr_lock_slow(EXC_BIT | PENDING_BIT, ONE_READER);
         }
     }
 
     #[inline]
     fn shr_try_lock(&self) -> bool {
-        let state = self.state.load(Ordering::Acquire);
-
-        if let Some(new_state) = state.checked_add(1) {
-            self.state
-                .compare_exchange(state, new_state, Ordering::Acquire, Ordering::Relaxed)
-                .is_ok()
-        } else {
-            false
-        }
+        self.shr_try_lock_inner(EXC_BIT | PENDING_BIT, ONE_READER)
     }
 
     #[inline]
@@ -211,7 +298,7 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
         let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
-            if let Some(new_state) = state.checked_add(1) {
+            if let Some(new_state) = state.checked_add(ONE_READER) {
                 if let Err(x) = self.state.compare_exchange(
                     state,
                     new_state,
@@ -230,8 +317,8 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
 
     #[inline]
     unsafe fn shr_unlock(&self) {
-        let state = self.state.fetch_sub(1, Ordering::Release);
-        debug_assert_ne!(state, 0, "Can't unlock an unlocked local lock");
+        let state = self.state.fetch_sub(ONE_READER, Ordering::Release);
+        debug_assert!(state >= ONE_READER, "Can't unlock an unlocked spin lock");
     }
 
     #[inline]
@@ -240,16 +327,404 @@ unsafe impl crate::share_lock::RawShareLock for SpinLock {
     }
 }
 
+unsafe impl crate::share_lock::RawShareLockCount for SpinLock {
+    #[inline]
+    fn shr_count(&self) -> usize {
+        self.state.load(Ordering::Relaxed) / ONE_READER
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockRecursive for SpinLock {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        if !self.shr_try_lock_recursive() {
+            self.shr_lock_slow(EXC_BIT, ONE_READER);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        // ignore waiting writers, only an *exc lock* blocks recursive *shr locks*
+        self.shr_try_lock_inner(EXC_BIT, ONE_READER)
+    }
+}
+
 unsafe impl crate::share_lock::RawShareLockUpgrade for SpinLock {
     unsafe fn upgrade(&self) {
         if !self.try_upgrade() {
-            self.upgrade_slow();
+            self.upgrade_slow(ONE_READER);
         }
     }
 
     unsafe fn try_upgrade(&self) -> bool {
+        self.try_upgrade_inner(ONE_READER)
+    }
+
+    #[inline]
+    fn shr_lock_upgradable(&self) {
+        if !self.shr_try_lock_upgradable() {
+            self.shr_lock_slow(EXC_BIT | PENDING_BIT | UPG_BIT, UPG_BIT | ONE_READER);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_upgradable(&self) -> bool {
+        self.shr_try_lock_inner(EXC_BIT | PENDING_BIT | UPG_BIT, UPG_BIT | ONE_READER)
+    }
+
+    #[inline]
+    unsafe fn shr_unlock_upgradable(&self) {
+        let state = self
+            .state
+            .fetch_sub(UPG_BIT | ONE_READER, Ordering::Release);
+        debug_assert_ne!(state & UPG_BIT, 0, "Can't unlock an unlocked spin lock");
+    }
+
+    #[inline]
+    unsafe fn upgrade_upgradable(&self) {
+        if !self.try_upgrade_upgradable() {
+            self.upgrade_slow(UPG_BIT | ONE_READER);
+        }
+    }
+
+    #[inline]
+    unsafe fn try_upgrade_upgradable(&self) -> bool {
+        self.try_upgrade_inner(UPG_BIT | ONE_READER)
+    }
+
+    #[inline]
+    unsafe fn downgrade_to_upgradable(&self) {
+        // this doesn't touch the pending bit
         self.state
-            .compare_exchange(1, EXC_LOCK, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+            .fetch_add((UPG_BIT | ONE_READER) - EXC_BIT, Ordering::Release);
+    }
+}
+
+impl SpinLock {
+    // `owned` is the part of the state which is owned by the upgrading thread
+    #[inline]
+    fn try_upgrade_inner(&self, owned: usize) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+
+        state & !PENDING_BIT == owned
+            && self
+                .state
+                .compare_exchange(state, EXC_BIT, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
     }
 }
+
+#[test]
+fn test_writer_preference() {
+    use crate::exclusive_lock::RawExclusiveLock;
+    use crate::share_lock::{RawShareLock, RawShareLockRecursive};
+
+    let lock = SpinLock::new();
+
+    lock.shr_lock();
+
+    crossbeam_utils::thread::scope(|s| {
+        let writer = s.spawn(|_| {
+            lock.exc_lock();
+            unsafe { lock.exc_unlock() }
+        });
+
+        while lock.state.load(Ordering::Relaxed) & PENDING_BIT == 0 {
+            core::hint::spin_loop();
+        }
+
+        // a writer is waiting, so new readers must wait for it
+        assert!(!lock.shr_try_lock());
+
+        // unless they already hold a *shr lock*
+        assert!(lock.shr_try_lock_recursive());
+        unsafe { lock.shr_unlock() }
+        unsafe { lock.shr_unlock() }
+
+        writer.join().unwrap();
+    })
+    .unwrap();
+
+    assert!(lock.shr_try_lock());
+}
+
+#[test]
+fn test_read_recursive() {
+    let lock = SpinLock::rwlock(0);
+
+    let a = lock.read();
+
+    crossbeam_utils::thread::scope(|s| {
+        let writer = s.spawn(|_| *lock.write() += 1);
+
+        while lock.raw().inner().state.load(Ordering::Relaxed) & PENDING_BIT == 0 {
+            core::hint::spin_loop();
+        }
+
+        // a nested read while a writer is waiting must not deadlock
+        assert!(lock.try_read().is_none());
+        let b = lock.read_recursive();
+        assert_eq!(*a, *b);
+        drop((a, b));
+
+        writer.join().unwrap();
+    })
+    .unwrap();
+
+    assert_eq!(*lock.read(), 1);
+}
+
+#[test]
+fn test_upgradable() {
+    let lock = SpinLock::rwlock(0);
+
+    let upgradable = lock.upgradable_read();
+    let reader = lock.read();
+    assert_eq!(lock.reader_count(), 2);
+
+    // only one upgradable read may exist, but it doesn't block readers
+    assert!(lock.try_upgradable_read().is_none());
+    assert!(lock.try_write().is_none());
+    assert_eq!(*upgradable, *reader);
+
+    let upgradable = match crate::share_lock::UpgradableGuard::try_upgrade(upgradable) {
+        Ok(_) => panic!("upgraded while there was a reader"),
+        Err(upgradable) => upgradable,
+    };
+    drop(reader);
+
+    let mut writer = crate::share_lock::UpgradableGuard::upgrade(upgradable);
+    assert!(lock.is_locked_exclusive());
+    *writer += 1;
+    assert!(lock.try_read().is_none());
+    drop(writer);
+
+    let upgradable = crate::exclusive_lock::ExclusiveGuard::downgrade_to_upgradable(lock.write());
+    assert!(lock.try_read().is_some());
+    assert!(lock.try_upgradable_read().is_none());
+    assert_eq!(*upgradable, 1);
+    drop(upgradable);
+
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn test_leak() {
+    use crate::share_lock::ShareGuard;
+
+    static LOCK: RwLock<u32> = SpinLock::rwlock(5);
+
+    let value: &'static u32 = ShareGuard::leak(LOCK.read());
+    assert_eq!(*value, 5);
+
+    assert!(LOCK.try_write().is_none());
+    assert_eq!(*LOCK.try_read().unwrap(), 5);
+}
+
+#[test]
+fn test_exclusive_leak() {
+    use crate::exclusive_lock::ExclusiveGuard;
+
+    let lock = SpinLock::rwlock(String::from("HELLO"));
+
+    let value = ExclusiveGuard::leak(lock.write());
+    value.push('!');
+    assert!(lock.try_read().is_none());
+    assert_eq!(value, "HELLO!");
+}
+
+#[test]
+fn test_try_map() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::share_lock::ShareGuard;
+
+    let lock = SpinLock::rwlock([1, 2, 3]);
+
+    let guard = match ShareGuard::try_map(lock.read(), |a| a.get(3)) {
+        Ok(_) => panic!("mapped to an element which doesn't exist"),
+        Err(guard) => guard,
+    };
+    let guard = ShareGuard::try_map(guard, |a| a.get(2)).ok().unwrap();
+    assert_eq!(*guard, 3);
+    drop(guard);
+
+    let mut guard = ExclusiveGuard::try_map(lock.write(), |a| a.get_mut(0))
+        .ok()
+        .unwrap();
+    *guard = 4;
+    drop(guard);
+
+    assert_eq!(*lock.read(), [4, 2, 3]);
+}
+
+#[test]
+fn test_unlocked_with() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::share_lock::ShareGuard;
+
+    let lock = SpinLock::rwlock(0);
+
+    let mut guard = lock.write();
+    let value = ExclusiveGuard::unlocked_with(&mut guard, |unlocked| {
+        assert!(!unlocked.is_relocked());
+        *lock.write() += 1;
+
+        assert!(unlocked.try_relock());
+        assert!(lock.try_read().is_none());
+        1
+    });
+    *guard += value;
+    drop(guard);
+
+    let mut guard = lock.read();
+    ShareGuard::unlocked_with(&mut guard, |unlocked| {
+        assert!(lock.try_write().is_some());
+        unlocked.relock();
+        assert!(lock.try_write().is_none());
+    });
+    assert_eq!(*guard, 2);
+}
+
+#[test]
+fn test_as_mutex() {
+    let lock = SpinLock::rwlock(0);
+
+    *lock.as_mutex().lock() += 1;
+    let guard = lock.read();
+    assert!(lock.as_mutex().try_lock().is_none());
+    drop(guard);
+
+    let mutex = lock.as_mutex();
+    let guard = mutex.as_rwlock().read();
+    assert_eq!(*guard, 1);
+    assert!(mutex.try_lock().is_none());
+}
+
+#[test]
+fn test_with() {
+    let lock = SpinLock::rwlock(0);
+
+    lock.write_with(|value| *value += 1);
+    assert_eq!(lock.read_with(|value| *value), 1);
+    assert_eq!(lock.as_mutex().with(|value| *value), 1);
+}
+
+#[test]
+fn test_force_unlock() {
+    let lock = SpinLock::rwlock(0);
+
+    core::mem::forget(lock.write());
+    assert!(lock.try_read().is_none());
+    unsafe { lock.force_unlock_write() }
+
+    core::mem::forget(lock.read());
+    assert!(lock.try_write().is_none());
+    unsafe { lock.force_unlock_read() }
+
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn test_map() {
+    use crate::exclusive_lock::ExclusiveGuard;
+    use crate::mutex::adaptive::AdaptiveLock;
+
+    let lock = SpinLock::rwlock(String::from("hello"));
+
+    let mut guard = ExclusiveGuard::map(lock.write(), String::as_mut_str);
+    guard.make_ascii_uppercase();
+    drop(guard);
+    assert_eq!(*lock.read(), "HELLO");
+
+    let mutex = AdaptiveLock::mutex(vec![1, 2, 3]);
+    let mut guard = ExclusiveGuard::map(mutex.lock(), |v| &mut v[1..]);
+    guard[0] = 4;
+    ExclusiveGuard::unlock_fair(guard);
+    assert_eq!(*mutex.lock(), [1, 4, 3]);
+}
+
+#[test]
+#[cfg(feature = "send_guard")]
+fn test_send_guard() {
+    let lock = SpinLock::rwlock(0);
+
+    let guard = lock.write();
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(move |_| drop(guard));
+    })
+    .unwrap();
+
+    assert!(lock.try_read().is_some());
+}
+
+#[test]
+fn test_clone_mapped() {
+    use crate::share_lock::ShareGuard;
+
+    let lock = SpinLock::rwlock((0, 1));
+
+    let a = ShareGuard::map(lock.read(), |(_, b)| b);
+    let b = ShareGuard::clone(&a);
+    drop(a);
+    assert_eq!(*b, 1);
+    assert!(lock.try_write().is_none());
+    drop(b);
+
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn test_guard_traits() {
+    use crate::share_lock::ShareGuard;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash<T: Hash>(value: T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let a = SpinLock::rwlock(1);
+    let b = SpinLock::rwlock(2);
+
+    let (x, y) = (a.read(), b.read());
+    assert!(x < y);
+    assert_eq!(x.cmp(&y), core::cmp::Ordering::Less);
+    assert_eq!(x, ShareGuard::clone(&x));
+    assert_eq!(format!("{} {}", x, y), "1 2");
+
+    assert_eq!(hash(&x), hash(1));
+    drop((x, y));
+
+    let mut x = a.write();
+    *x += 2;
+    assert_eq!(x.to_string(), "3");
+}
+
+#[test]
+fn test_cloned() {
+    use crate::share_lock::ShareGuard;
+
+    let lock = SpinLock::rwlock((String::from("hello"), 1));
+
+    let value = ShareGuard::cloned(ShareGuard::map(lock.read(), |(s, _)| s));
+    assert_eq!(value, "hello");
+    assert!(lock.try_write().is_some());
+
+    let value = ShareGuard::copied(ShareGuard::map(lock.read(), |(_, x)| x));
+    assert_eq!(value, 1);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serde() {
+    let lock = SpinLock::rwlock(std::vec![1, 2, 3]);
+    let _reader = lock.read();
+    let json = serde_json::to_string(&lock).unwrap();
+    assert_eq!(json, "[1,2,3]");
+
+    let lock: RwLock<std::vec::Vec<u32>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(*lock.try_write().unwrap(), [1, 2, 3]);
+}
@@ -1,6 +1,6 @@
 //! a splittable spin lock
 
-use crate::spin_wait::SpinWait;
+use crate::relax::Backoff;
 
 use core::sync::atomic::{AtomicUsize, Ordering};
 
@@ -106,7 +106,7 @@ impl SplitSpinLock {
     #[cold]
     #[inline(never)]
     fn exc_lock_slow(&self) -> bool {
-        let mut spinwait = SpinWait::new();
+        let mut backoff = Backoff::new();
         let mut state = self.state.load(Ordering::Acquire);
 
         loop {
@@ -126,14 +126,14 @@ impl SplitSpinLock {
                 state = self.state.load(Ordering::Acquire);
             }
 
-            spinwait.spin();
+            backoff.snooze();
         }
     }
 
     #[cold]
     #[inline(never)]
     fn shr_lock_slow(&self) -> bool {
-        let mut spinwait = SpinWait::new();
+        let mut backoff = Backoff::new();
         let mut state = self.state.load(Ordering::Relaxed);
 
         loop {
@@ -154,7 +154,7 @@ impl SplitSpinLock {
                 state = self.state.load(Ordering::Relaxed);
             }
 
-            spinwait.spin();
+            backoff.snooze();
         }
     }
 
@@ -210,8 +210,8 @@ impl crate::Init for SplitSpinLock {
 unsafe impl crate::mutex::RawMutex for SplitSpinLock {}
 unsafe impl crate::rwlock::RawRwLock for SplitSpinLock {}
 unsafe impl crate::RawLockInfo for SplitSpinLock {
-    type ExclusiveGuardTraits = (crate::NoSend, crate::NoSync);
-    type ShareGuardTraits = (crate::NoSend, crate::NoSync);
+    type ExclusiveGuardTraits = (crate::SendGuard, crate::NoSync);
+    type ShareGuardTraits = (crate::SendGuard, crate::NoSync);
 }
 
 unsafe impl crate::exclusive_lock::RawExclusiveLock for SplitSpinLock {
@@ -0,0 +1,144 @@
+//! a lock backed by a windows slim reader/writer lock (`SRWLOCK`)
+//!
+//! This delegates all blocking to the OS, which integrates with the OS scheduler
+//! and doesn't need any extra code for parking threads.
+
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::share_lock::RawShareLock;
+
+/// a raw mutex backed by a `SRWLOCK`
+pub type RawMutex = crate::mutex::raw::Mutex<SrwLock>;
+
+/// a mutex backed by a `SRWLOCK`
+pub type Mutex<T> = crate::mutex::Mutex<SrwLock, T>;
+
+/// a raw rwlock backed by a `SRWLOCK`
+pub type RawRwLock = crate::rwlock::raw::RwLock<SrwLock>;
+
+/// a rwlock backed by a `SRWLOCK`
+pub type RwLock<T> = crate::rwlock::RwLock<SrwLock, T>;
+
+#[repr(C)]
+#[allow(clippy::upper_case_acronyms)]
+struct SRWLOCK {
+    ptr: *mut c_void,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn AcquireSRWLockExclusive(lock: *mut SRWLOCK);
+    fn TryAcquireSRWLockExclusive(lock: *mut SRWLOCK) -> u8;
+    fn ReleaseSRWLockExclusive(lock: *mut SRWLOCK);
+    fn AcquireSRWLockShared(lock: *mut SRWLOCK);
+    fn TryAcquireSRWLockShared(lock: *mut SRWLOCK) -> u8;
+    fn ReleaseSRWLockShared(lock: *mut SRWLOCK);
+}
+
+/// A lock backed by a windows `SRWLOCK`
+///
+/// A `SRWLOCK` must be unlocked on the same thread that locked it,
+/// so the guards of this lock are not `Send`.
+///
+/// Windows doesn't support recursively acquiring shared locks, so cloning
+/// a share guard may deadlock if another thread is waiting to acquire an
+/// exclusive lock.
+pub struct SrwLock {
+    lock: UnsafeCell<SRWLOCK>,
+}
+
+// SAFETY: a `SRWLOCK` can be moved while unlocked, and it is synchronized by the OS
+unsafe impl Send for SrwLock {}
+unsafe impl Sync for SrwLock {}
+
+impl SrwLock {
+    /// create a new `SRWLOCK`
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            // SRWLOCK_INIT
+            lock: UnsafeCell::new(SRWLOCK {
+                ptr: core::ptr::null_mut(),
+            }),
+        }
+    }
+
+    /// create a new `SRWLOCK` based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new `SRWLOCK` based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new `SRWLOCK` based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new `SRWLOCK` based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+}
+
+impl Default for SrwLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for SrwLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for SrwLock {}
+unsafe impl crate::rwlock::RawRwLock for SrwLock {}
+unsafe impl crate::RawLockInfo for SrwLock {
+    type ExclusiveGuardTraits = crate::NoSend;
+    type ShareGuardTraits = crate::NoSend;
+}
+
+unsafe impl RawExclusiveLock for SrwLock {
+    #[inline]
+    fn exc_lock(&self) {
+        unsafe { AcquireSRWLockExclusive(self.lock.get()) }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        unsafe { TryAcquireSRWLockExclusive(self.lock.get()) != 0 }
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        ReleaseSRWLockExclusive(self.lock.get())
+    }
+}
+
+unsafe impl RawShareLock for SrwLock {
+    #[inline]
+    fn shr_lock(&self) {
+        unsafe { AcquireSRWLockShared(self.lock.get()) }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        unsafe { TryAcquireSRWLockShared(self.lock.get()) != 0 }
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        self.shr_lock()
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        ReleaseSRWLockShared(self.lock.get())
+    }
+}
@@ -0,0 +1,9 @@
+//! a tagged rwlock
+//!
+//! This is the share-lock counterpart of the [tagged mutex](crate::mutex::tagged),
+//! it can store up to `TAG_BITS` bits alongside the lock state, which can be read and
+//! modified with `tag`, `or_tag`, `swap_tag` and friends at any time.
+//!
+//! Currently this is the [tagged spin rwlock](crate::rwlock::tagged_spin)
+
+pub use crate::rwlock::tagged_spin::{RawLock, RawRwLock, RwLock, TaggedSpinLock};
@@ -0,0 +1,414 @@
+//! a tagged spin rwlock
+
+use crate::exclusive_lock::{RawExclusiveLock, RawExclusiveLockDowngrade};
+use crate::relax::Backoff;
+use crate::share_lock::{RawShareLock, RawShareLockRecursive};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// the tag is stored in the lower bits of the state
+const MASK: usize = 0xff;
+// set while there is an *exc lock*
+const EXC_BIT: usize = 0x100;
+// set while a thread is waiting for an *exc lock*, this blocks new *shr locks*
+const PENDING_BIT: usize = 0x200;
+// the rest of the state is the number of *shr locks*
+const ONE_READER: usize = 0x400;
+// set if there is an *exc lock* or any *shr locks*
+const LOCKED: usize = !(MASK | PENDING_BIT);
+
+/// A tagged spin raw mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
+///
+/// It is not reccomended to use this type in libraries,
+/// because spin locks are only efficient for very short critical sections.
+pub type RawMutex = crate::mutex::raw::Mutex<TaggedSpinLock>;
+
+/// A tagged spin mutex that can store up to `TAG_BITS` bits in the lower bits of the lock
+///
+/// It is not reccomended to use this type in libraries,
+/// because spin locks are only efficient for very short critical sections.
+pub type Mutex<T> = crate::mutex::Mutex<TaggedSpinLock, T>;
+
+/// A tagged spin raw rwlock that can store up to `TAG_BITS` bits in the lower bits of the lock
+///
+/// It is not reccomended to use this type in libraries,
+/// because spin locks are only efficient for very short critical sections.
+pub type RawRwLock = crate::rwlock::raw::RwLock<TaggedSpinLock>;
+
+/// A tagged spin rwlock that can store up to `TAG_BITS` bits in the lower bits of the lock
+///
+/// It is not reccomended to use this type in libraries,
+/// because spin locks are only efficient for very short critical sections.
+pub type RwLock<T> = crate::rwlock::RwLock<TaggedSpinLock, T>;
+
+/// A tagged spin rwlock that can store up to `TAG_BITS` bits in the lower bits of the lock
+pub type RawLock = TaggedSpinLock;
+
+#[inline]
+fn strongest_failure_ordering(order: Ordering) -> Ordering {
+    use Ordering::*;
+
+    match order {
+        Release => Relaxed,
+        Relaxed => Relaxed,
+        SeqCst => SeqCst,
+        Acquire => Acquire,
+        AcqRel => Acquire,
+        _ => unreachable!(),
+    }
+}
+
+/// A tagged spin rwlock that can store up to `TAG_BITS` bits in the lower bits of the lock
+///
+/// The tag can be changed at any time, regardless of whether the lock is locked.
+///
+/// Like [`SpinLock`](crate::rwlock::spin::SpinLock), this lock prefers writers,
+/// once a thread starts waiting for an *exc lock* no new *shr locks* can be acquired
+/// (but existing *shr locks* can still be split).
+pub struct TaggedSpinLock {
+    state: AtomicUsize,
+}
+
+impl TaggedSpinLock {
+    /// The number of bits that this rwlock can store
+    ///
+    /// This is guaranteed to be at least 8
+    pub const TAG_BITS: u8 = MASK.count_ones() as u8;
+
+    /// create a new tagged spin lock
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_tag(0)
+    }
+
+    /// create a new tagged spin lock with the given inital tag
+    #[inline]
+    pub const fn with_tag(tag: u8) -> Self {
+        Self {
+            state: AtomicUsize::new(tag as usize & MASK),
+        }
+    }
+
+    /// Create a new raw tagged mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// Create a new tagged mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// Create a new raw tagged rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// Create a new tagged rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    /// Get the tag with the specified load ordering
+    pub fn tag(&self, order: Ordering) -> u8 {
+        (self.state.load(order) & MASK) as u8
+    }
+
+    /// perform a bit-wise and with the given tag and the stored tag using
+    /// the specifed ordering
+    ///
+    /// returns the old tag
+    ///
+    /// this lowers to a single `fetch_and`
+    pub fn and_tag(&self, tag: u8, order: Ordering) -> u8 {
+        let tag = tag as usize | !MASK;
+
+        (self.state.fetch_and(tag, order) & MASK) as u8
+    }
+
+    /// perform a bit-wise or with the given tag and the stored tag using
+    /// the specifed ordering
+    ///
+    /// returns the old tag
+    ///
+    /// this lowers to a single `fetch_or`
+    pub fn or_tag(&self, tag: u8, order: Ordering) -> u8 {
+        (self.state.fetch_or(tag as usize, order) & MASK) as u8
+    }
+
+    /// swap the tag with the given tag using the specied ordering
+    ///
+    /// returns the old tag
+    pub fn swap_tag(&self, tag: u8, order: Ordering) -> u8 {
+        self.exchange_tag(tag, order, strongest_failure_ordering(order))
+    }
+
+    /// swap the tag with the given tag using the specied orderings
+    #[inline]
+    pub fn exchange_tag(&self, tag: u8, success: Ordering, failure: Ordering) -> u8 {
+        match self.update_tag(success, failure, move |_| Some(tag)) {
+            Ok(x) => x,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// update the tag with the given function until it returns `None` or succeeds using the specied orderings
+    pub fn update_tag(
+        &self,
+        success: Ordering,
+        failure: Ordering,
+        mut f: impl FnMut(u8) -> Option<u8>,
+    ) -> Result<u8, u8> {
+        let mut state = self.state.load(failure);
+
+        while let Some(tag) = f((state & MASK) as u8) {
+            match self.state.compare_exchange_weak(
+                state,
+                (state & !MASK) | tag as usize,
+                success,
+                failure,
+            ) {
+                Err(x) => state = x,
+                Ok(x) => return Ok((x & MASK) as u8),
+            }
+        }
+
+        Err((state & MASK) as u8)
+    }
+
+    #[cold]
+    fn exc_lock_slow(&self) {
+        let mut backoff = Backoff::new();
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            // there are no readers or writers, so try to take the lock
+            if state & LOCKED == 0 {
+                match self.state.compare_exchange_weak(
+                    state,
+                    (state & MASK) | EXC_BIT,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return,
+                    Err(x) => state = x,
+                }
+
+                // another thread changed the state, so back off before trying again
+                backoff.spin();
+                continue;
+            }
+
+            // block any new readers until we get the lock
+            if state & PENDING_BIT == 0 {
+                self.state.fetch_or(PENDING_BIT, Ordering::Relaxed);
+            }
+
+            backoff.snooze();
+            state = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    // `blocked` is the set of bits which prevent new *shr locks*
+    #[inline]
+    fn shr_try_lock_inner(&self, blocked: usize) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // retry if only the tag or the number of readers changed
+        while state & blocked == 0 {
+            let new_state = match state.checked_add(ONE_READER) {
+                Some(new_state) => new_state,
+                None => return false,
+            };
+
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        false
+    }
+
+    #[cold]
+    fn shr_lock_slow(&self, blocked: usize) {
+        let mut backoff = Backoff::new();
+
+        while !self.shr_try_lock_inner(blocked) {
+            backoff.snooze();
+        }
+    }
+}
+
+impl Default for TaggedSpinLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for TaggedSpinLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for TaggedSpinLock {}
+unsafe impl crate::rwlock::RawRwLock for TaggedSpinLock {}
+unsafe impl crate::RawLockInfo for TaggedSpinLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+impl crate::RawLockQuery for TaggedSpinLock {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & LOCKED != 0
+    }
+
+    #[inline]
+    fn is_locked_exclusive(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & EXC_BIT != 0
+    }
+}
+
+unsafe impl RawExclusiveLock for TaggedSpinLock {
+    #[inline]
+    fn exc_lock(&self) {
+        if !self.exc_try_lock() {
+            self.exc_lock_slow()
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // retry if only the tag changed
+        while state & LOCKED == 0 {
+            match self.state.compare_exchange_weak(
+                state,
+                (state & MASK) | EXC_BIT,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        false
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // keep the pending bit, so that waiting writers still get priority over new readers
+        let state = self.state.fetch_and(!EXC_BIT, Ordering::Release);
+        debug_assert_ne!(state & EXC_BIT, 0, "Can't unlock an unlocked spin lock");
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        // there are never any parked threads in a spin lock
+    }
+}
+
+unsafe impl RawExclusiveLockDowngrade for TaggedSpinLock {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        // EXC_BIT + (ONE_READER - EXC_BIT) = ONE_READER, this doesn't touch the tag or the pending bit
+        self.state
+            .fetch_add(ONE_READER - EXC_BIT, Ordering::Release);
+    }
+}
+
+unsafe impl RawShareLock for TaggedSpinLock {
+    #[inline]
+    fn shr_lock(&self) {
+        if !self.shr_try_lock() {
+            self.shr_lock_slow(EXC_BIT | PENDING_BIT);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.shr_try_lock_inner(EXC_BIT | PENDING_BIT)
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        loop {
+            let new_state = state
+                .checked_add(ONE_READER)
+                .expect("Tried to create too many shared locks!");
+
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        let state = self.state.fetch_sub(ONE_READER, Ordering::Release);
+        debug_assert!(state >= ONE_READER, "Can't unlock an unlocked spin lock");
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        // there are never any parked threads in a spin lock
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockCount for TaggedSpinLock {
+    #[inline]
+    fn shr_count(&self) -> usize {
+        self.state.load(Ordering::Relaxed) / ONE_READER
+    }
+}
+
+unsafe impl RawShareLockRecursive for TaggedSpinLock {
+    #[inline]
+    fn shr_lock_recursive(&self) {
+        if !self.shr_try_lock_recursive() {
+            self.shr_lock_slow(EXC_BIT);
+        }
+    }
+
+    #[inline]
+    fn shr_try_lock_recursive(&self) -> bool {
+        // ignore waiting writers, only an *exc lock* blocks recursive *shr locks*
+        self.shr_try_lock_inner(EXC_BIT)
+    }
+}
+
+#[test]
+fn test_tag() {
+    let lock = TaggedSpinLock::rwlock(0);
+
+    assert_eq!(lock.raw().inner().or_tag(0b101, Ordering::Relaxed), 0);
+
+    let a = lock.read();
+    let b = a.clone();
+    assert_eq!(lock.raw().inner().swap_tag(0xff, Ordering::Relaxed), 0b101);
+    assert!(lock.try_write().is_none());
+    drop((a, b));
+
+    let guard = lock.write();
+    assert_eq!(lock.raw().inner().and_tag(0b11, Ordering::Relaxed), 0xff);
+    assert!(lock.try_read().is_none());
+    drop(guard);
+
+    assert_eq!(lock.raw().inner().tag(Ordering::Relaxed), 0b11);
+    assert!(lock.try_write().is_some());
+}
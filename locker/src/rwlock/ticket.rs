@@ -0,0 +1,262 @@
+//! a fair ticket spin rwlock
+
+use crate::exclusive_lock::RawExclusiveLock;
+use crate::share_lock::RawShareLock;
+use crate::spin_wait::SpinWait;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// a raw mutex backed by a ticket rwlock
+pub type RawMutex = crate::mutex::raw::Mutex<TicketLock>;
+
+/// a mutex backed by a ticket rwlock
+pub type Mutex<T> = crate::mutex::Mutex<TicketLock, T>;
+
+/// a raw rwlock backed by a ticket rwlock
+///
+/// Readers and writers acquire the lock in the order that they first tried to lock it,
+/// so neither readers nor writers can be starved.
+pub type RawRwLock = crate::rwlock::raw::RwLock<TicketLock>;
+
+/// a rwlock backed by a ticket rwlock
+///
+/// Readers and writers acquire the lock in the order that they first tried to lock it,
+/// so neither readers nor writers can be starved.
+pub type RwLock<T> = crate::rwlock::RwLock<TicketLock, T>;
+
+/// A fair (FIFO) spin rwlock
+///
+/// Each thread that wants to lock takes a ticket, then waits until its ticket
+/// is served. Consecutive readers are served together, as soon as a reader enters
+/// it serves the next ticket, so that a batch of readers can enter at the same time.
+/// A writer is served once every thread with an earlier ticket has unlocked.
+///
+/// Like [`TicketLock`](crate::mutex::ticket::TicketLock), waiting threads never park,
+/// so this lock is best suited to short critical sections.
+pub struct TicketLock {
+    next: AtomicUsize,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+impl TicketLock {
+    /// create a new ticket rwlock
+    #[inline]
+    pub const fn new() -> Self {
+        TicketLock {
+            next: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            write: AtomicUsize::new(0),
+        }
+    }
+
+    /// create a new ticket rwlock based raw mutex
+    pub const fn raw_mutex() -> RawMutex {
+        unsafe { RawMutex::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket rwlock based mutex
+    pub const fn mutex<T>(value: T) -> Mutex<T> {
+        Mutex::from_raw_parts(Self::raw_mutex(), value)
+    }
+
+    /// create a new ticket rwlock based raw rwlock
+    pub const fn raw_rwlock() -> RawRwLock {
+        unsafe { RawRwLock::from_raw(Self::new()) }
+    }
+
+    /// create a new ticket rwlock based rwlock
+    pub const fn rwlock<T>(value: T) -> RwLock<T> {
+        RwLock::from_raw_parts(Self::raw_rwlock(), value)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn wait(counter: &AtomicUsize, ticket: usize) {
+        let mut spin = SpinWait::new();
+
+        while counter.load(Ordering::Acquire) != ticket {
+            spin.spin();
+        }
+    }
+}
+
+impl Default for TicketLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::Init for TicketLock {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl crate::mutex::RawMutex for TicketLock {}
+unsafe impl crate::rwlock::RawRwLock for TicketLock {}
+unsafe impl crate::RawLockInfo for TicketLock {
+    type ExclusiveGuardTraits = ();
+    type ShareGuardTraits = ();
+}
+
+unsafe impl RawExclusiveLock for TicketLock {
+    #[inline]
+    fn exc_lock(&self) {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+
+        if self.write.load(Ordering::Acquire) != ticket {
+            Self::wait(&self.write, ticket);
+        }
+    }
+
+    #[inline]
+    fn exc_try_lock(&self) -> bool {
+        let write = self.write.load(Ordering::Relaxed);
+
+        // only take a ticket if it would be served immediately
+        self.next
+            .compare_exchange(
+                write,
+                write.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+
+    #[inline]
+    unsafe fn exc_unlock(&self) {
+        // serve the next ticket, regardless of whether it's a reader or a writer
+        self.read.fetch_add(1, Ordering::Release);
+        self.write.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn exc_bump(&self) {
+        let write = self.write.load(Ordering::Relaxed);
+
+        // if another thread has taken a ticket, let it run
+        if self.next.load(Ordering::Relaxed) != write.wrapping_add(1) {
+            self.exc_unlock();
+            self.exc_lock();
+        }
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockFair for TicketLock {
+    #[inline]
+    unsafe fn exc_unlock_fair(&self) {
+        // a ticket lock always hands the lock over to the next waiting thread
+        self.exc_unlock()
+    }
+
+    #[inline]
+    unsafe fn exc_bump_fair(&self) {
+        self.exc_bump()
+    }
+}
+
+unsafe impl crate::exclusive_lock::RawExclusiveLockDowngrade for TicketLock {
+    #[inline]
+    unsafe fn downgrade(&self) {
+        // let the next batch of readers in, and the `write` counter will be
+        // incremented when the *shr lock* is released
+        self.read.fetch_add(1, Ordering::Release);
+    }
+}
+
+unsafe impl RawShareLock for TicketLock {
+    #[inline]
+    fn shr_lock(&self) {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+
+        if self.read.load(Ordering::Acquire) != ticket {
+            Self::wait(&self.read, ticket);
+        }
+
+        // let the next reader in
+        self.read.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        let read = self.read.load(Ordering::Relaxed);
+
+        // only take a ticket if it would be served immediately
+        let locked = self
+            .next
+            .compare_exchange(
+                read,
+                read.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok();
+
+        if locked {
+            self.read.fetch_add(1, Ordering::Relaxed);
+        }
+
+        locked
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        // the `write` counter is incremented once per unlock, so the next writer
+        // will need to wait for one more unlock
+        self.write.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.write.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    unsafe fn shr_bump(&self) {
+        // if a writer is waiting, let it run
+        if self.next.load(Ordering::Relaxed) != self.read.load(Ordering::Relaxed) {
+            self.shr_unlock();
+            self.shr_lock();
+        }
+    }
+}
+
+unsafe impl crate::share_lock::RawShareLockFair for TicketLock {
+    #[inline]
+    unsafe fn shr_unlock_fair(&self) {
+        // a ticket lock always hands the lock over to the next waiting thread
+        self.shr_unlock()
+    }
+
+    #[inline]
+    unsafe fn shr_bump_fair(&self) {
+        self.shr_bump()
+    }
+}
+
+#[test]
+fn test_fifo() {
+    let lock = TicketLock::rwlock(0);
+
+    let a = lock.read();
+    let b = a.clone();
+    assert!(lock.try_read().is_some());
+    assert!(lock.try_write().is_none());
+    drop((a, b));
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..1000 {
+                    *lock.write() += 1;
+                    let a = lock.read();
+                    let b = a.clone();
+                    assert_eq!(*a, *b);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    assert_eq!(*lock.read(), 4000);
+}
@@ -0,0 +1,283 @@
+//! A counting semaphore
+//!
+//! The [`Semaphore`] implements [`RawShareLock`], where each *shr lock* is a single permit,
+//! so `n` permits allows `n` concurrent *shr lock*s. So it can be used with [`RawShareGuard`],
+//! or to build a [`ShareGuard`](crate::share_lock::ShareGuard) over some data. It also has a
+//! conventional API, which can acquire and release many permits at once with [`SemaphorePermit`].
+//!
+//! Threads waiting for permits are parked with an [`EventCount`], keyed on the address of the semaphore.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::event_count::EventCount;
+use crate::share_lock::{RawShareGuard, RawShareLock, RawShareLockTimed};
+
+/// A counting semaphore
+pub struct Semaphore {
+    permits: AtomicUsize,
+    event: EventCount,
+}
+
+/// An RAII guard which owns some permits of a [`Semaphore`],
+/// and releases them when dropped
+///
+/// This is created by [`Semaphore::acquire`], [`Semaphore::acquire_many`], and [`Semaphore::try_acquire`]
+#[must_use = "if unused the `SemaphorePermit` will immediately release its permits"]
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+impl Semaphore {
+    /// Create a new semaphore with the given number of permits
+    #[inline]
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: AtomicUsize::new(permits),
+            event: EventCount::new(),
+        }
+    }
+
+    /// The number of permits which are currently available
+    #[inline]
+    pub fn available_permits(&self) -> usize {
+        self.permits.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn try_acquire_raw(&self, n: usize) -> bool {
+        let mut permits = self.permits.load(Ordering::Relaxed);
+
+        loop {
+            if permits < n {
+                return false;
+            }
+
+            match self.permits.compare_exchange_weak(
+                permits,
+                permits - n,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => permits = x,
+            }
+        }
+    }
+
+    #[inline]
+    fn acquire_raw(&self, n: usize, timeout: Option<Instant>) -> bool {
+        self.try_acquire_raw(n) || self.acquire_slow(n, timeout)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn acquire_slow(&self, n: usize, timeout: Option<Instant>) -> bool {
+        loop {
+            let key = self.event.prepare_wait();
+
+            if self.try_acquire_raw(n) {
+                self.event.cancel_wait(key);
+                return true;
+            }
+
+            match timeout {
+                None => self.event.commit_wait(key),
+                Some(timeout) => {
+                    if !self.event.commit_wait_until(key, timeout) {
+                        return self.try_acquire_raw(n);
+                    }
+                }
+            }
+
+            if self.try_acquire_raw(n) {
+                return true;
+            }
+        }
+    }
+
+    /// Acquire a single permit, blocking until it is available
+    #[inline]
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        self.acquire_many(1)
+    }
+
+    /// Acquire `n` permits at once, blocking until they are available
+    ///
+    /// If `n` is larger than the number of permits the semaphore will ever have,
+    /// then this blocks forever
+    #[inline]
+    pub fn acquire_many(&self, n: usize) -> SemaphorePermit<'_> {
+        self.acquire_raw(n, None);
+
+        SemaphorePermit {
+            semaphore: self,
+            permits: n,
+        }
+    }
+
+    /// Try to acquire `n` permits at once, without blocking
+    #[inline]
+    pub fn try_acquire(&self, n: usize) -> Option<SemaphorePermit<'_>> {
+        if self.try_acquire_raw(n) {
+            Some(SemaphorePermit {
+                semaphore: self,
+                permits: n,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Try to acquire `n` permits at once, blocking until they are available, or the `duration` has passed
+    #[inline]
+    pub fn try_acquire_for(&self, n: usize, duration: Duration) -> Option<SemaphorePermit<'_>> {
+        if self.acquire_raw(n, Instant::now().checked_add(duration)) {
+            Some(SemaphorePermit {
+                semaphore: self,
+                permits: n,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Add `n` permits to the semaphore, waking up any threads which are waiting for permits
+    ///
+    /// This may be used to release permits which were [forgotten](SemaphorePermit::forget),
+    /// or to increase the number of permits
+    #[inline]
+    pub fn release(&self, n: usize) {
+        self.permits.fetch_add(n, Ordering::Release);
+        // waiting threads may need different numbers of permits,
+        // so wake all of them to check
+        self.event.notify_all();
+    }
+
+    /// Acquire a single permit as a *shr lock*, blocking until it is available
+    #[inline]
+    pub fn acquire_guard(&self) -> RawShareGuard<'_, Self> {
+        RawShareGuard::new(self)
+    }
+}
+
+impl SemaphorePermit<'_> {
+    /// The number of permits owned by this guard
+    #[inline]
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Forget the guard without releasing its permits
+    ///
+    /// The permits can be released later with [`Semaphore::release`]
+    #[inline]
+    pub fn forget(self) {
+        core::mem::forget(self)
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits)
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Semaphore")
+            .field("available_permits", &self.available_permits())
+            .finish()
+    }
+}
+
+impl fmt::Debug for SemaphorePermit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemaphorePermit")
+            .field("permits", &self.permits)
+            .finish()
+    }
+}
+
+unsafe impl crate::RawLockInfo for Semaphore {
+    type ExclusiveGuardTraits = core::convert::Infallible;
+    type ShareGuardTraits = ();
+}
+
+unsafe impl RawShareLock for Semaphore {
+    #[inline]
+    fn shr_lock(&self) {
+        self.acquire_raw(1, None);
+    }
+
+    #[inline]
+    fn shr_try_lock(&self) -> bool {
+        self.try_acquire_raw(1)
+    }
+
+    #[inline]
+    unsafe fn shr_split(&self) {
+        // each *shr lock* is a separate permit, so this has to acquire another one
+        self.acquire_raw(1, None);
+    }
+
+    #[inline]
+    unsafe fn shr_unlock(&self) {
+        self.release(1)
+    }
+}
+
+impl crate::RawTimedLock for Semaphore {
+    type Instant = Instant;
+    type Duration = Duration;
+}
+
+unsafe impl RawShareLockTimed for Semaphore {
+    #[inline]
+    fn shr_try_lock_until(&self, instant: Self::Instant) -> bool {
+        self.acquire_raw(1, Some(instant))
+    }
+
+    #[inline]
+    fn shr_try_lock_for(&self, duration: Self::Duration) -> bool {
+        self.acquire_raw(1, Instant::now().checked_add(duration))
+    }
+}
+
+#[test]
+fn test_semaphore() {
+    use core::sync::atomic::AtomicUsize;
+
+    let semaphore = Semaphore::new(2);
+    let active = AtomicUsize::new(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|_| {
+                for _ in 0..100 {
+                    let _permit = semaphore.acquire();
+                    assert!(active.fetch_add(1, Ordering::Relaxed) < 2);
+                    active.fetch_sub(1, Ordering::Relaxed);
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    let a = semaphore.acquire_guard();
+    let b = a.clone();
+    assert!(semaphore.try_acquire(1).is_none());
+    assert!(semaphore
+        .try_acquire_for(1, Duration::from_millis(1))
+        .is_none());
+    drop((a, b));
+
+    let permit = semaphore.try_acquire(2).unwrap();
+    assert_eq!(permit.num_permits(), 2);
+    permit.forget();
+    assert_eq!(semaphore.available_permits(), 0);
+    semaphore.release(3);
+    assert_eq!(semaphore.acquire_many(3).num_permits(), 3);
+}
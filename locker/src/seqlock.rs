@@ -0,0 +1,262 @@
+//! A sequence lock
+//!
+//! A sequence lock allows readers to optimistically read the protected value
+//! without ever blocking writers. Instead readers detect when they raced with
+//! a writer, and retry the read. This works well for small values that are
+//! read very often, and written to rarely, like statistics.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use crate::exclusive_lock::RawExclusiveGuard;
+use crate::mutex::{raw, RawMutex};
+use crate::spin_wait::SpinWait;
+
+/// A sequence lock
+///
+/// Writers are serialized by the raw mutex `L`, while readers never take the lock.
+/// Readers instead check a sequence number before and after reading the value,
+/// and retry if a writer changed the value in the meantime.
+///
+/// Because readers may observe a partially written value (which is discarded),
+/// the value must be `Copy`.
+pub struct SeqLock<L, T> {
+    seq: AtomicUsize,
+    raw: raw::Mutex<L>,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<L: Send + RawMutex, T: Send> Send for SeqLock<L, T> {}
+unsafe impl<L: Sync + RawMutex, T: Send> Sync for SeqLock<L, T> {}
+
+/// A RAII guard that allows writing to the value protected by a [`SeqLock`]
+///
+/// Readers will retry until this guard is dropped.
+pub struct SeqLockWriteGuard<'a, L: RawMutex, T> {
+    _raw: RawExclusiveGuard<'a, L>,
+    lock: &'a SeqLock<L, T>,
+}
+
+impl<L: RawMutex + crate::Init, T: Copy + Default> Default for SeqLock<L, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<L, T> SeqLock<L, T> {
+    /// Create a new sequence lock with the given raw mutex
+    #[inline]
+    pub const fn from_raw_parts(raw: raw::Mutex<L>, value: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            raw,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Decomposes the sequence lock into a raw mutex and it's value
+    #[inline]
+    pub fn into_raw_parts(self) -> (raw::Mutex<L>, T) {
+        (self.raw, self.value.into_inner())
+    }
+
+    /// Consumes this sequence lock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// the underlying raw mutex
+    #[inline]
+    pub const fn raw(&self) -> &raw::Mutex<L> {
+        &self.raw
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `SeqLock` mutably, no actual locking needs to take place
+    /// ---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<L: RawMutex + crate::Init, T: Copy> SeqLock<L, T> {
+    /// Creates a new sequence lock in an unlocked state ready for use.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self::from_raw_parts(crate::Init::INIT, value)
+    }
+}
+
+impl<L: RawMutex, T: Copy> SeqLock<L, T> {
+    /// Attempts to read the value without retrying
+    ///
+    /// If a writer is currently writing to the value, or wrote to the
+    /// value while it was being read, then `None` is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_read(&self) -> Option<T> {
+        let seq = self.seq.load(Ordering::Acquire);
+
+        // a writer is currently writing to the value
+        if seq & 1 != 0 {
+            return None;
+        }
+
+        // SAFETY: a writer may be concurrently writing to the value, so it is read
+        // as a `MaybeUninit<T>`, which is only assumed to be initialized once
+        // we have checked that no writer could have changed it
+        let value = unsafe { core::ptr::read_volatile(self.value.get() as *const MaybeUninit<T>) };
+
+        fence(Ordering::Acquire);
+
+        if self.seq.load(Ordering::Relaxed) == seq {
+            // SAFETY: no writer changed the value while we were reading it
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    /// Reads the value, retrying until there are no conflicting writes
+    ///
+    /// This function never blocks writers, but it may spin
+    /// while writers are writing to the value.
+    #[inline]
+    pub fn read(&self) -> T {
+        match self.try_read() {
+            Some(value) => value,
+            None => self.read_slow(),
+        }
+    }
+
+    #[cold]
+    fn read_slow(&self) -> T {
+        let mut spin = SpinWait::new();
+
+        loop {
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+
+            spin.spin();
+        }
+    }
+}
+
+impl<L: RawMutex, T: Copy> SeqLock<L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn wrap<'s>(&'s self, raw: RawExclusiveGuard<'s, L>) -> SeqLockWriteGuard<'s, L, T> {
+        // only the lock owner may write to `seq`
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+
+        // make sure that readers see the odd sequence number before any writes to the value
+        fence(Ordering::Release);
+
+        SeqLockWriteGuard {
+            _raw: raw,
+            lock: self,
+        }
+    }
+
+    /// Acquires the write lock, blocking the current thread until it is able to do so.
+    ///
+    /// Readers will retry until the returned guard is dropped.
+    #[inline]
+    pub fn write(&self) -> SeqLockWriteGuard<'_, L, T> {
+        self.wrap(self.raw.lock())
+    }
+
+    /// Attempts to acquire the write lock.
+    ///
+    /// If the lock could not be acquired at this time, then None is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_write(&self) -> Option<SeqLockWriteGuard<'_, L, T>> {
+        Some(self.wrap(self.raw.try_lock()?))
+    }
+
+    /// Overwrites the value, blocking the current thread until the write lock is available.
+    #[inline]
+    pub fn set(&self, value: T) {
+        *self.write() = value;
+    }
+}
+
+impl<L: RawMutex, T> Deref for SeqLockWriteGuard<'_, L, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<L: RawMutex, T> DerefMut for SeqLockWriteGuard<'_, L, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<L: RawMutex, T> Drop for SeqLockWriteGuard<'_, L, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // publish the new value, the raw guard will unlock the lock after this
+        let seq = self.lock.seq.load(Ordering::Relaxed);
+        self.lock.seq.store(seq.wrapping_add(1), Ordering::Release);
+    }
+}
+
+#[cfg(all(test, feature = "extra"))]
+mod tests {
+    use super::SeqLock;
+    use crate::mutex::spin::SpinLock;
+
+    #[test]
+    fn read_write() {
+        let lock = SeqLock::<SpinLock, _>::new((0, 0));
+
+        assert_eq!(lock.read(), (0, 0));
+
+        let mut guard = lock.write();
+        *guard = (1, 1);
+        assert_eq!(lock.try_read(), None);
+        assert!(lock.try_write().is_none());
+        drop(guard);
+
+        assert_eq!(lock.read(), (1, 1));
+        lock.set((2, 2));
+        assert_eq!(lock.try_read(), Some((2, 2)));
+    }
+
+    #[test]
+    fn no_torn_reads() {
+        let lock = SeqLock::<SpinLock, _>::new([0_u64; 8]);
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                for i in 1..10_000 {
+                    lock.set([i; 8]);
+                }
+            });
+
+            for _ in 0..10_000 {
+                let value = lock.read();
+                assert!(value.iter().all(|&x| x == value[0]));
+            }
+        })
+        .unwrap();
+    }
+}
@@ -2,11 +2,17 @@
 //!
 //! See [`RawShareLock`] for details
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod arc;
 mod guard;
 mod raw;
 
-pub use guard::{MappedShareGuard, ShareGuard};
-pub use raw::{RawShareGuard, _RawShareGuard};
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use arc::ArcShareGuard;
+pub use guard::{MappedShareGuard, ShareGuard, UpgradableGuard};
+pub use raw::{
+    _RawShareGuard, _RawUpgradableGuard, RawShareGuard, RawUpgradableGuard, ShareUnlocked,
+};
 
 #[cfg(doc)]
 use crate::RawLockInfo;
@@ -14,7 +20,7 @@ use crate::RawLockInfo;
 /// A raw sharable lock, this implementation is for any lock that can be locked multiple times
 /// for some times slice.
 ///
-/// Some examples include `RwLock`'s reader locks and `RefCell`'s `Ref`, and `ReentrantMutex`'s
+/// Some examples include `RwLock`'s reader locks and `RefCell`'s `Ref`, and [`ReentrantMutex`](crate::remutex::ReentrantMutex)'s
 /// locks (which can be shared in a single thread).
 ///
 /// # *shr lock*
@@ -195,6 +201,88 @@ pub unsafe trait RawShareLockUpgrade:
     /// * the caller must own a *shr lock*
     /// * the lock must not have been moved since it was locked
     unsafe fn try_upgrade(&self) -> bool;
+
+    /// acquire an *upg lock*
+    ///
+    /// An *upg lock* acts like a *shr lock* which can always be upgraded to an *exc lock*
+    /// without deadlocking. Only one *upg lock* can exist at a time, but it can coexist with
+    /// any number of *shr locks*.
+    ///
+    /// By default this acquires an *exc lock*, which is correct but blocks all readers.
+    /// Locks which override this must override all of the *upg lock* methods.
+    ///
+    /// blocks until lock is acquired
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is cannot be acquired
+    #[inline]
+    fn shr_lock_upgradable(&self) {
+        self.exc_lock()
+    }
+
+    /// attempts to acquire an *upg lock*
+    ///
+    /// This function is non-blocking and may not panic
+    ///
+    /// returns true on success
+    #[inline]
+    fn shr_try_lock_upgradable(&self) -> bool {
+        self.exc_try_lock()
+    }
+
+    /// Unlock an *upg lock*
+    ///
+    /// This releases an *upg lock*
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own an *upg lock*
+    /// * the lock must not have been moved since it was locked
+    #[inline]
+    unsafe fn shr_unlock_upgradable(&self) {
+        self.exc_unlock()
+    }
+
+    /// Atomically upgrade an *upg lock* to a *exc lock*
+    ///
+    /// Blocks until all *shr locks* are released.
+    ///
+    /// This releases an *upg lock* and acquires a *exc lock*
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own an *upg lock*
+    /// * the lock must not have been moved since it was locked
+    #[inline]
+    unsafe fn upgrade_upgradable(&self) {}
+
+    /// Attempts to atomically upgrade an *upg lock* to a *exc lock*
+    ///
+    /// If the *exc lock* was acquired, then the *upg lock* is released
+    /// and this function returns true. Otherwise, the *upg lock* is maintained
+    /// and this function returns false.
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own an *upg lock*
+    /// * the lock must not have been moved since it was locked
+    #[inline]
+    unsafe fn try_upgrade_upgradable(&self) -> bool {
+        true
+    }
+
+    /// Atomically downgrade a *exc lock* to an *upg lock*, without allowing
+    /// any new *exc locks* or *upg locks* in the meantime
+    ///
+    /// This releases a *exc lock* and acquires an *upg lock*
+    ///
+    /// # Safety
+    ///
+    /// * the caller must own a *exc lock*
+    /// * the lock must not have been moved since it was locked
+    #[inline]
+    unsafe fn downgrade_to_upgradable(&self) {}
 }
 
 /// Additional methods for RwLocks which support atomically downgrading an exclusive lock to a shared lock.
@@ -232,6 +320,50 @@ pub unsafe trait RawShareLockUpgradeTimed: RawShareLockUpgrade + RawShareLockTim
     unsafe fn try_upgrade_for(&self, duration: Self::Duration) -> bool;
 }
 
+/// Additional methods for RwLocks which support recursively acquiring a *shr lock*
+///
+/// Locks which prefer writers may block new *shr locks* while a thread is waiting for an
+/// *exc lock*, so if a thread which already owns a *shr lock* tries to acquire another one,
+/// it may deadlock. A recursive *shr lock* is acquired even if there are threads waiting
+/// for an *exc lock*, so this can't happen.
+///
+/// # Safety
+///
+/// [`RawShareLockRecursive::shr_lock_recursive`] must acquire a *shr lock*
+///
+/// [`RawShareLockRecursive::shr_try_lock_recursive`] must acquire a *shr lock* if it returns true
+pub unsafe trait RawShareLockRecursive: RawShareLock {
+    /// Acquire a *shr lock*, even if there are threads waiting for an *exc lock*
+    ///
+    /// blocks until the lock is acquired
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is impossible to acquire
+    fn shr_lock_recursive(&self);
+
+    /// Attempts to acquire a *shr lock*, even if there are threads waiting for an *exc lock*
+    ///
+    /// This function is non-blocking and may not panic
+    ///
+    /// returns true on success
+    fn shr_try_lock_recursive(&self) -> bool;
+}
+
+/// Additional methods for shared locks which can report the number of *shr locks*
+///
+/// # Safety
+///
+/// [`RawShareLockCount::shr_count`] must return the number of *shr locks* which were held
+/// at some point during the call
+pub unsafe trait RawShareLockCount: RawShareLock {
+    /// Returns the number of *shr locks* which are currently held
+    ///
+    /// This may be out of date as soon as it is returned, so it should only be used
+    /// for diagnostics, and not for synchronization
+    fn shr_count(&self) -> usize;
+}
+
 macro_rules! trait_impls {
     ($L:ident => $($type:ty),*) => {$(
         unsafe impl<$L: ?Sized + RawShareLock> RawShareLock for $type {
@@ -284,6 +416,46 @@ macro_rules! trait_impls {
             unsafe fn try_upgrade(&self) -> bool {
                 L::try_upgrade(self)
             }
+
+            fn shr_lock_upgradable(&self) {
+                L::shr_lock_upgradable(self)
+            }
+
+            fn shr_try_lock_upgradable(&self) -> bool {
+                L::shr_try_lock_upgradable(self)
+            }
+
+            unsafe fn shr_unlock_upgradable(&self) {
+                L::shr_unlock_upgradable(self)
+            }
+
+            unsafe fn upgrade_upgradable(&self) {
+                L::upgrade_upgradable(self)
+            }
+
+            unsafe fn try_upgrade_upgradable(&self) -> bool {
+                L::try_upgrade_upgradable(self)
+            }
+
+            unsafe fn downgrade_to_upgradable(&self) {
+                L::downgrade_to_upgradable(self)
+            }
+        }
+
+        unsafe impl<$L: ?Sized + RawShareLockCount> RawShareLockCount for $type {
+            fn shr_count(&self) -> usize {
+                L::shr_count(self)
+            }
+        }
+
+        unsafe impl<$L: ?Sized + RawShareLockRecursive> RawShareLockRecursive for $type {
+            fn shr_lock_recursive(&self) {
+                L::shr_lock_recursive(self)
+            }
+
+            fn shr_try_lock_recursive(&self) -> bool {
+                L::shr_try_lock_recursive(self)
+            }
         }
 
         unsafe impl<$L: ?Sized + RawShareLockUpgradeTimed> RawShareLockUpgradeTimed for $type {
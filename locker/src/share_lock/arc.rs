@@ -0,0 +1,80 @@
+use super::RawShareLock;
+use crate::rwlock::RwLock;
+use crate::RawLockInfo;
+use core::fmt;
+use core::ops::Deref;
+use std::sync::Arc;
+
+/// An RAII structure used to release the shared access of a lock when dropped.
+///
+/// Unlike [`ShareGuard`](crate::share_lock::ShareGuard) this guard keeps the lock
+/// alive by holding on to an `Arc`, so it has no lifetime parameter, and can be stored in
+/// structs or sent to other threads.
+///
+/// This is created by [`RwLock::read_arc`]
+#[must_use = "if unused the `ArcShareGuard` will immediately unlock"]
+pub struct ArcShareGuard<L: RawShareLock + RawLockInfo, T: ?Sized> {
+    rwlock: Arc<RwLock<L, T>>,
+    _traits: L::ShareGuardTraits,
+}
+
+unsafe impl<L: RawShareLock + RawLockInfo + Send + Sync, T: ?Sized + Send + Sync> Send
+    for ArcShareGuard<L, T>
+where
+    L::ShareGuardTraits: Send,
+{
+}
+// the rwlock can be reached through `ArcShareGuard::rwlock`, and write locked on another thread
+unsafe impl<L: RawShareLock + RawLockInfo + Sync, T: ?Sized + Send + Sync> Sync
+    for ArcShareGuard<L, T>
+where
+    L::ShareGuardTraits: Sync,
+{
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized> ArcShareGuard<L, T>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Create a new `ArcShareGuard`
+    ///
+    /// # Safety
+    ///
+    /// A *shr lock* must owned for the given `rwlock`
+    pub unsafe fn from_raw(rwlock: Arc<RwLock<L, T>>) -> Self {
+        Self {
+            rwlock,
+            _traits: crate::Inhabitted::INIT,
+        }
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized> ArcShareGuard<L, T> {
+    /// The rwlock that this guard locks
+    ///
+    /// This is an associated function that needs to be used as `ArcShareGuard::rwlock(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn rwlock(g: &Self) -> &Arc<RwLock<L, T>> {
+        &g.rwlock
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized> Drop for ArcShareGuard<L, T> {
+    fn drop(&mut self) {
+        unsafe { self.rwlock.raw().inner().shr_unlock() }
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized> Deref for ArcShareGuard<L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.rwlock.as_mut_ptr() }
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + fmt::Debug> fmt::Debug for ArcShareGuard<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -1,11 +1,15 @@
-use super::{RawShareGuard, RawShareLock, RawShareLockFair};
+use super::{
+    RawShareGuard, RawShareLock, RawShareLockFair, RawShareLockUpgrade, RawUpgradableGuard,
+    ShareUnlocked,
+};
 use crate::RawLockInfo;
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::Deref;
 
 pub use crate::guard::{Mapped, Pure, TryMapError};
 
-/// An RAII exclusive guard guard returned by `ShareGuard::map`,
+/// An RAII shared guard returned by `ShareGuard::map`,
 /// which can point to a subfield of the protected data.
 ///
 /// The main difference between `MappedShareGuard` and `ShareGuard`
@@ -14,6 +18,10 @@ pub use crate::guard::{Mapped, Pure, TryMapError};
 pub type MappedShareGuard<'a, L, T> = ShareGuard<'a, L, T, Mapped>;
 
 /// RAII structure used to release the shared access of a lock when dropped.
+///
+/// If the lock can be unlocked from any thread, then this guard is `Send`, so it can be acquired
+/// on one thread and moved to another thread to be released there. Some locks only opt into this
+/// when the `send_guard` feature is enabled, see [`SendGuard`](crate::marker::SendGuard).
 #[must_use = "if unused the `ShareGuard` will immediately unlock"]
 pub struct ShareGuard<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St = Pure> {
     raw: RawShareGuard<'a, L>,
@@ -37,6 +45,9 @@ where
 impl<L: RawShareLockFair + RawLockInfo, T: ?Sized, St> ShareGuard<'_, L, T, St> {
     /// Unlocks the guard using a fair unlocking protocol
     /// [read more](RawShareLockFair#method.shr_unlock_fair)
+    ///
+    /// This is also available on mapped guards, so projecting into the data doesn't
+    /// lose fair handoff.
     pub fn unlock_fair(g: Self) {
         g.raw.unlock_fair();
     }
@@ -55,11 +66,24 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
     pub fn unlocked<R>(g: &mut Self, f: impl FnOnce() -> R) -> R {
         g.raw.unlocked(f)
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The function gets a handle which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because &mut guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_with<R>(g: &mut Self, f: impl FnOnce(&ShareUnlocked<'_, L>) -> R) -> R {
+        g.raw.unlocked_with(f)
+    }
 }
 
 impl<'a, L: RawShareLockFair + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
     /// Temporarily yields the lock to a waiting thread if there is one.
     /// [read more](RawShareLockFair#method.shr_bump_fair)
+    ///
+    /// This is not available on mapped guards, because another thread could invalidate
+    /// the projected data while the lock is yielded. Use [`unlock_fair`](Self::unlock_fair)
+    /// and re-acquire the lock instead.
     pub fn bump_fair(g: &mut Self) {
         g.raw.bump_fair();
     }
@@ -72,6 +96,16 @@ impl<'a, L: RawShareLockFair + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T> {
     pub fn unlocked_fair<R>(g: &mut Self, f: impl FnOnce() -> R) -> R {
         g.raw.unlocked_fair(f)
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The lock is unlocked a fair unlock protocol, and the function gets a handle
+    /// which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because `&mut` guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_fair_with<R>(g: &mut Self, f: impl FnOnce(&ShareUnlocked<'_, L>) -> R) -> R {
+        g.raw.unlocked_fair_with(f)
+    }
 }
 
 impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St> {
@@ -158,26 +192,88 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
         (g.raw, g.value)
     }
 
-    /// Make a new `MappedExclusiveGuard` for a component of the locked data.
+    /// Forget the guard, and leave the *shr lock* held forever
     ///
-    /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
+    /// This is like [`Ref::leak`](core::cell::Ref::leak), the guarded value can
+    /// be read for as long as the lock is borrowed, but the lock can never be
+    /// written to again.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::leak(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn leak(g: Self) -> &'a T {
+        let (raw, value) = Self::into_raw_parts(g);
+        raw.leak();
+        unsafe { &*value }
+    }
+
+    /// Forget the guard without releasing the *shr lock*, and return a pointer to the guarded value
     ///
-    /// This is an associated function that needs to be used as `ExclusiveGuard::map(...)`.
+    /// The guard can be reconstructed with [`ShareGuard::from_raw`] to release the lock, this
+    /// is useful for passing the guard through FFI or type-erased storage.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::into_raw(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn map<F, U: ?Sized>(g: Self, f: impl FnOnce(&T) -> &U) -> ShareGuard<'a, L, U, Mapped> {
+    pub fn into_raw(g: Self) -> *const T {
+        let (raw, value) = Self::into_raw_parts(g);
+        raw.into_inner();
+        value
+    }
+
+    /// Clone the guarded value, then release the *shr lock*
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::cloned(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn cloned(g: Self) -> T
+    where
+        T: Clone,
+    {
+        T::clone(&g)
+    }
+
+    /// Copy the guarded value, then release the *shr lock*
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::copied(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn copied(g: Self) -> T
+    where
+        T: Copy,
+    {
+        *g
+    }
+
+    /// Make a new `MappedShareGuard` for a component of the locked data.
+    ///
+    /// This operation cannot fail as the `ShareGuard` passed in already locked the data.
+    ///
+    /// This is an associated function that needs to be used as `ShareGuard::map(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn map<U: ?Sized>(g: Self, f: impl FnOnce(&T) -> &U) -> MappedShareGuard<'a, L, U> {
         let value = f(unsafe { &*g.value });
 
         unsafe { ShareGuard::from_raw_parts(g.raw, value) }
     }
 
-    /// Attempts to make a new `MappedExclusiveGuard` for a component of the locked data.
-    /// The original guard is return if the closure returns `Err` as well as the error.
+    /// Attempts to make a new `MappedShareGuard` for a component of the locked data.
+    /// The original guard is returned if the closure returns `None`.
     ///
-    /// This operation cannot fail as the `ExclusiveGuard` passed in already locked the data.
+    /// This is an associated function that needs to be used as `ShareGuard::try_map(...)`.
+    /// A method would interfere with methods of the same name on the contents of the locked data.
+    pub fn try_map<U: ?Sized>(
+        g: Self,
+        f: impl FnOnce(&T) -> Option<&U>,
+    ) -> Result<ShareGuard<'a, L, U, Mapped>, Self> {
+        match f(unsafe { &*g.value }) {
+            None => Err(g),
+            Some(value) => Ok(unsafe { ShareGuard::from_raw_parts(g.raw, value) }),
+        }
+    }
+
+    /// Attempts to make a new `MappedShareGuard` for a component of the locked data.
+    /// The original guard is return if the closure returns `Err` as well as the error.
     ///
-    /// This is an associated function that needs to be used as `ExclusiveGuard::try_map(...)`.
+    /// This is an associated function that needs to be used as `ShareGuard::try_map_result(...)`.
     /// A method would interfere with methods of the same name on the contents of the locked data.
-    pub fn try_map<E, U: ?Sized>(
+    pub fn try_map_result<E, U: ?Sized>(
         g: Self,
         f: impl FnOnce(&T) -> Result<&U, E>,
     ) -> Result<ShareGuard<'a, L, U, Mapped>, TryMapError<E, Self>> {
@@ -234,6 +330,23 @@ impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
     }
 }
 
+impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized, St> ShareGuard<'a, L, T, St>
+where
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Reconstruct a guard from a pointer returned by [`ShareGuard::into_raw`]
+    ///
+    /// # Safety
+    ///
+    /// * a *shr lock* must be owned for the given `lock`, and it will be released when
+    ///   the guard is dropped
+    /// * `value` must have been returned by `ShareGuard::into_raw` on a guard of the same
+    ///   kind (mapped or not) that locked `lock`
+    pub unsafe fn from_raw(lock: &'a L, value: *const T) -> Self {
+        Self::from_raw_parts(RawShareGuard::from_raw(lock), value)
+    }
+}
+
 impl<'a, L: crate::share_lock::RawShareLockUpgrade + RawLockInfo, T: ?Sized> ShareGuard<'a, L, T>
 where
     L::ExclusiveGuardTraits: crate::Inhabitted,
@@ -255,6 +368,9 @@ where
     /// Attempts to atomically upgrades a read lock lock into a exclusive write lock,
     /// without blocking or panicking
     ///
+    /// This usually only succeeds if this is the only read lock, so it can be used to validate
+    /// the data under a read lock, and then write to it without another thread changing it in between.
+    ///
     /// returns a exclusive guard if successful, otherwise returns the current guard
     pub fn try_upgrade(g: Self) -> Result<crate::exclusive_lock::ExclusiveGuard<'a, L, T>, Self> {
         unsafe {
@@ -279,8 +395,162 @@ impl<L: RawShareLock + RawLockInfo, T: ?Sized, St> Deref for ShareGuard<'_, L, T
     }
 }
 
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + fmt::Debug, St> fmt::Debug
+    for ShareGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + fmt::Display, St> fmt::Display
+    for ShareGuard<'_, L, T, St>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + PartialEq, St> PartialEq
+    for ShareGuard<'_, L, T, St>
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + Eq, St> Eq for ShareGuard<'_, L, T, St> {}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + PartialOrd, St> PartialOrd
+    for ShareGuard<'_, L, T, St>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + Ord, St> Ord for ShareGuard<'_, L, T, St> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
+impl<L: RawShareLock + RawLockInfo, T: ?Sized + core::hash::Hash, St> core::hash::Hash
+    for ShareGuard<'_, L, T, St>
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        T::hash(self, state)
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<'a, L: RawShareLock + RawLockInfo, T: ?Sized + core::marker::Unsize<U>, U: ?Sized, St>
+    core::ops::CoerceUnsized<ShareGuard<'a, L, U, St>> for ShareGuard<'a, L, T, St>
+{
+}
+
 impl<L: RawShareLock + RawLockInfo, T: ?Sized, St> Clone for ShareGuard<'_, L, T, St> {
     fn clone(&self) -> Self {
         unsafe { Self::from_raw_parts(self.raw.clone(), &*self.value) }
     }
 }
+
+/// RAII structure used to release the upgradable access of a lock when dropped.
+///
+/// An upgradable guard gives shared access to the protected data, and can be atomically
+/// upgraded to an [`ExclusiveGuard`](crate::exclusive_lock::ExclusiveGuard). Only one
+/// upgradable guard can exist at a time, but it can coexist with any number of `ShareGuard`s.
+#[must_use = "if unused the `UpgradableGuard` will immediately unlock"]
+pub struct UpgradableGuard<'a, L: RawShareLockUpgrade + RawLockInfo, T: ?Sized> {
+    raw: RawUpgradableGuard<'a, L>,
+    value: *const T,
+    _repr: PhantomData<&'a T>,
+}
+
+// the guard can be upgraded on the thread it was sent to, which gives `&mut T`
+unsafe impl<'a, L: RawShareLockUpgrade + RawLockInfo, T: ?Sized + Send + Sync> Send
+    for UpgradableGuard<'a, L, T>
+where
+    RawUpgradableGuard<'a, L>: Send,
+{
+}
+unsafe impl<'a, L: RawShareLockUpgrade + RawLockInfo, T: ?Sized + Sync> Sync
+    for UpgradableGuard<'a, L, T>
+where
+    RawUpgradableGuard<'a, L>: Sync,
+{
+}
+
+impl<'a, L: RawShareLockUpgrade + RawLockInfo, T: ?Sized> UpgradableGuard<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    /// Create a new guard from the given raw guard and pointer
+    ///
+    /// # Safety
+    ///
+    /// `value` must be valid for as long as this `UpgradableGuard` is alive,
+    /// and it must be valid to write to once the guard is upgraded
+    pub unsafe fn from_raw_parts(raw: RawUpgradableGuard<'a, L>, value: *const T) -> Self {
+        Self {
+            raw,
+            value,
+            _repr: PhantomData,
+        }
+    }
+
+    /// The inner `RawUpgradableGuard`
+    pub fn raw(g: &Self) -> &RawUpgradableGuard<'a, L> {
+        &g.raw
+    }
+
+    /// Decomposes the `UpgradableGuard` into it's raw parts
+    ///
+    /// Returns the [`RawUpgradableGuard`] and a pointer to the guarded value.
+    ///
+    /// It is not safe to write using this pointer until the raw guard is upgraded.
+    pub fn into_raw_parts(g: Self) -> (RawUpgradableGuard<'a, L>, *const T) {
+        (g.raw, g.value)
+    }
+
+    /// Atomically upgrades an upgradable lock into a exclusive write lock,
+    /// blocking the current thread until all readers have left.
+    pub fn upgrade(g: Self) -> crate::exclusive_lock::ExclusiveGuard<'a, L, T> {
+        let (raw, ptr) = Self::into_raw_parts(g);
+        unsafe {
+            crate::exclusive_lock::ExclusiveGuard::from_raw_parts(raw.upgrade(), ptr as *mut T)
+        }
+    }
+
+    /// Attempts to atomically upgrades an upgradable lock into a exclusive write lock,
+    /// without blocking or panicking
+    ///
+    /// returns a exclusive guard if successful, otherwise returns the current guard
+    pub fn try_upgrade(g: Self) -> Result<crate::exclusive_lock::ExclusiveGuard<'a, L, T>, Self> {
+        let (raw, ptr) = Self::into_raw_parts(g);
+
+        match raw.try_upgrade() {
+            Ok(raw) => Ok(unsafe {
+                crate::exclusive_lock::ExclusiveGuard::from_raw_parts(raw, ptr as *mut T)
+            }),
+            Err(raw) => Err(unsafe { Self::from_raw_parts(raw, ptr) }),
+        }
+    }
+}
+
+impl<L: RawShareLockUpgrade + RawLockInfo, T: ?Sized> Deref for UpgradableGuard<'_, L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.value }
+    }
+}
+
+impl<L: RawShareLockUpgrade + RawLockInfo, T: ?Sized + fmt::Debug> fmt::Debug
+    for UpgradableGuard<'_, L, T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
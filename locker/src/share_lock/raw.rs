@@ -1,5 +1,65 @@
-use super::{RawShareLock, RawShareLockFair, RawShareLockUpgrade};
+use super::{RawShareLock, RawShareLockFair, RawShareLockTimed, RawShareLockUpgrade};
+use crate::exclusive_lock::RawExclusiveGuard;
 use crate::{Inhabitted, RawLockInfo};
+use core::cell::Cell;
+
+/// A handle to a lock which was temporarily unlocked by
+/// [`RawShareGuard::unlocked_with`](crate::share_lock::RawShareGuard#method.unlocked_with)
+///
+/// The lock is re-acquired once the closure returns, unless it was already re-acquired with
+/// [`relock`](Self::relock) or [`try_relock`](Self::try_relock)
+pub struct ShareUnlocked<'a, L: RawShareLock + ?Sized> {
+    lock: &'a L,
+    locked: Cell<bool>,
+}
+
+impl<L: RawShareLock + ?Sized> Drop for ShareUnlocked<'_, L> {
+    fn drop(&mut self) {
+        if !self.locked.get() {
+            self.lock.shr_lock()
+        }
+    }
+}
+
+impl<'a, L: RawShareLock + ?Sized> ShareUnlocked<'a, L> {
+    fn new(lock: &'a L) -> Self {
+        Self {
+            lock,
+            locked: Cell::new(false),
+        }
+    }
+
+    /// The inner lock
+    pub fn inner(&self) -> &'a L {
+        self.lock
+    }
+
+    /// Returns true if the lock was already re-acquired
+    pub fn is_relocked(&self) -> bool {
+        self.locked.get()
+    }
+
+    /// Re-acquire the lock early, blocking until it is acquired
+    ///
+    /// Does nothing if the lock was already re-acquired
+    pub fn relock(&self) {
+        if !self.locked.get() {
+            self.lock.shr_lock();
+            self.locked.set(true);
+        }
+    }
+
+    /// Try to re-acquire the lock early, without blocking
+    ///
+    /// returns true if the lock is held after this call
+    pub fn try_relock(&self) -> bool {
+        if !self.locked.get() && self.lock.shr_try_lock() {
+            self.locked.set(true);
+        }
+
+        self.locked.get()
+    }
+}
 
 /// A RAII implementation of a scoped shared lock
 ///
@@ -69,6 +129,33 @@ where
     }
 }
 
+impl<'a, L: RawShareLockTimed + RawLockInfo + ?Sized> RawShareGuard<'a, L>
+where
+    L::ShareGuardTraits: Inhabitted,
+{
+    /// Try to create a new `RawShareGuard`, blocking until the lock is acquired or the `instant` is reached
+    ///
+    /// This function may not panic
+    pub fn try_new_until(lock: &'a L, instant: L::Instant) -> Option<Self> {
+        if lock.shr_try_lock_until(instant) {
+            Some(unsafe { Self::from_raw(lock) })
+        } else {
+            None
+        }
+    }
+
+    /// Try to create a new `RawShareGuard`, blocking until the lock is acquired or the `duration` has passed
+    ///
+    /// This function may not panic
+    pub fn try_new_for(lock: &'a L, duration: L::Duration) -> Option<Self> {
+        if lock.shr_try_lock_for(duration) {
+            Some(unsafe { Self::from_raw(lock) })
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, L: RawShareLock + RawLockInfo> RawShareGuard<'a, L> {
     /// Temporarily yields the lock to another thread if there is one.
     /// [read more](RawShareLock#method.shr_bump)
@@ -89,6 +176,18 @@ impl<'a, L: RawShareLock + RawLockInfo> RawShareGuard<'a, L> {
         f()
     }
 
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The function gets a handle which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because &mut guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_with<R>(&mut self, f: impl FnOnce(&ShareUnlocked<'_, L>) -> R) -> R {
+        unsafe {
+            self.lock.shr_unlock();
+        }
+        f(&ShareUnlocked::new(self.lock))
+    }
+
     /// The inner lock
     pub fn inner(&self) -> &L {
         self.lock
@@ -98,6 +197,14 @@ impl<'a, L: RawShareLock + RawLockInfo> RawShareGuard<'a, L> {
     pub fn into_inner(self) -> &'a L {
         core::mem::ManuallyDrop::new(self).lock
     }
+
+    /// Forget the guard, and leave the *shr lock* held forever
+    ///
+    /// This is like [`Ref::leak`](core::cell::Ref::leak), no *exc lock* can be acquired
+    /// after this, but other *shr lock*s can still be acquired
+    pub fn leak(self) -> &'a L {
+        self.into_inner()
+    }
 }
 
 impl<L: RawShareLockFair + RawLockInfo> RawShareGuard<'_, L> {
@@ -130,6 +237,19 @@ impl<L: RawShareLockFair + RawLockInfo> RawShareGuard<'_, L> {
         defer!(self.lock.shr_lock());
         f()
     }
+
+    /// Temporarily unlocks the lock to execute the given function.
+    ///
+    /// The lock is unlocked a fair unlock protocol, and the function gets a handle
+    /// which can re-acquire the lock before the function returns.
+    ///
+    /// This is safe because `&mut` guarantees that there exist no other references to the data protected by the lock.
+    pub fn unlocked_fair_with<R>(&mut self, f: impl FnOnce(&ShareUnlocked<'_, L>) -> R) -> R {
+        unsafe {
+            self.lock.shr_unlock_fair();
+        }
+        f(&ShareUnlocked::new(self.lock))
+    }
 }
 
 impl<'a, L: RawShareLockUpgrade + RawLockInfo> RawShareGuard<'a, L>
@@ -178,3 +298,105 @@ impl<'a, L: RawShareLock + RawLockInfo> Clone for RawShareGuard<'a, L> {
         }
     }
 }
+
+/// A RAII implementation of a scoped upgradable lock
+///
+/// This type represents an *upg lock*, and while it is alive there is an active *upg lock*
+///
+/// Once this structure is dropped, that *upg lock* will automatically be released by calling
+/// [`RawShareLockUpgrade::shr_unlock_upgradable`].
+pub type RawUpgradableGuard<'a, L> = _RawUpgradableGuard<
+    'a,
+    L,
+    (
+        <L as RawLockInfo>::ExclusiveGuardTraits,
+        <L as RawLockInfo>::ShareGuardTraits,
+    ),
+>;
+
+#[doc(hidden)]
+#[must_use = "if unused the `RawUpgradableGuard` will immediately unlock"]
+pub struct _RawUpgradableGuard<'a, L: RawShareLockUpgrade + ?Sized, Tr> {
+    lock: &'a L,
+    _traits: Tr,
+}
+
+impl<L: RawShareLockUpgrade + ?Sized, Tr> Drop for _RawUpgradableGuard<'_, L, Tr> {
+    fn drop(&mut self) {
+        unsafe { self.lock.shr_unlock_upgradable() }
+    }
+}
+
+impl<'a, L: RawShareLockUpgrade + RawLockInfo + ?Sized> RawUpgradableGuard<'a, L>
+where
+    L::ExclusiveGuardTraits: Inhabitted,
+    L::ShareGuardTraits: Inhabitted,
+{
+    /// # Safety
+    ///
+    /// An *upg lock* must owned for the given `lock`
+    pub unsafe fn from_raw(lock: &'a L) -> Self {
+        Self {
+            lock,
+            _traits: Inhabitted::INIT,
+        }
+    }
+
+    /// Create a new `RawUpgradableGuard`
+    ///
+    /// blocks until lock is acquired
+    ///
+    /// # Panic
+    ///
+    /// This function may panic if the lock is cannot be acquired
+    pub fn new(lock: &'a L) -> Self {
+        lock.shr_lock_upgradable();
+        unsafe { Self::from_raw(lock) }
+    }
+
+    /// Try to create a new `RawUpgradableGuard`
+    ///
+    /// This function is non-blocking and may not panic
+    pub fn try_new(lock: &'a L) -> Option<Self> {
+        if lock.shr_try_lock_upgradable() {
+            Some(unsafe { Self::from_raw(lock) })
+        } else {
+            None
+        }
+    }
+
+    /// The inner lock
+    pub fn inner(&self) -> &L {
+        self.lock
+    }
+
+    /// Consume the guard without releasing the lock
+    pub fn into_inner(self) -> &'a L {
+        core::mem::ManuallyDrop::new(self).lock
+    }
+
+    /// Atomically upgrades an upgradable lock into a exclusive write lock,
+    /// blocking the current thread until all readers have left.
+    pub fn upgrade(self) -> RawExclusiveGuard<'a, L> {
+        let lock = self.into_inner();
+        unsafe {
+            lock.upgrade_upgradable();
+            RawExclusiveGuard::from_raw(lock)
+        }
+    }
+
+    /// Attempts to atomically upgrades an upgradable lock into a exclusive write lock,
+    /// without blocking or panicking
+    ///
+    /// returns a exclusive guard if successful, otherwise returns the current guard
+    pub fn try_upgrade(self) -> Result<RawExclusiveGuard<'a, L>, Self> {
+        let lock = self.into_inner();
+        unsafe {
+            if lock.try_upgrade_upgradable() {
+                Ok(RawExclusiveGuard::from_raw(lock))
+            } else {
+                Err(RawUpgradableGuard::from_raw(lock))
+            }
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! A stamped lock
+//!
+//! A stamped lock is a rwlock which also supports optimistic reads, like Java's `StampedLock`.
+//! An optimistic read doesn't take the lock at all, instead it takes a [`Stamp`] of the
+//! current version of the lock, and later validates that no writer acquired the lock since
+//! the stamp was taken. If validation fails, the read must be retried (or a real read lock taken).
+//!
+//! The lock can be converted between all three modes:
+//! * an optimistic read can be converted to a read or write lock, if the stamp is still valid
+//! * a read lock can be upgraded to a write lock, or released into an optimistic read
+//! * a write lock can be downgraded to a read lock, or released into an optimistic read
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use crate::spin_wait::SpinWait;
+
+// the number of readers is stored in the lowest bits
+const ONE_READER: usize = 1;
+// set while there is a writer, this also acts as the lowest bit of the version
+const WRITER: usize = 1 << 16;
+const READERS: usize = WRITER - 1;
+// the version of the lock, which is incremented every time a writer acquires or releases the lock
+const VERSION: usize = !READERS;
+
+/// A stamp which represents a version of a [`StampedLock`]
+///
+/// Stamps can be validated with [`StampedLock::validate`] to check if
+/// a writer has acquired the lock since the stamp was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stamp(usize);
+
+/// A reader-writer lock which also supports optimistic reads
+///
+/// Both readers and writers spin while waiting for the lock,
+/// so this lock is best suited to short critical sections.
+pub struct StampedLock<T: ?Sized> {
+    state: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for StampedLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for StampedLock<T> {}
+
+/// A RAII guard that allows reading the value protected by a [`StampedLock`]
+///
+/// Writers will wait until this guard is dropped.
+#[must_use = "if unused the `StampedReadGuard` will immediately unlock"]
+pub struct StampedReadGuard<'a, T: ?Sized> {
+    lock: &'a StampedLock<T>,
+}
+
+/// A RAII guard that allows writing to the value protected by a [`StampedLock`]
+///
+/// Readers will wait, and optimistic reads will fail until this guard is dropped.
+#[must_use = "if unused the `StampedWriteGuard` will immediately unlock"]
+pub struct StampedWriteGuard<'a, T: ?Sized> {
+    lock: &'a StampedLock<T>,
+}
+
+impl<T: Default> Default for StampedLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> StampedLock<T> {
+    /// Creates a new stamped lock in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes this stamped lock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> StampedLock<T> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `StampedLock` mutably, no actual locking needs to take place
+    /// ---the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Takes a stamp for an optimistic read
+    ///
+    /// If a writer currently holds the lock, then None is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_optimistic_read(&self) -> Option<Stamp> {
+        let state = self.state.load(Ordering::Acquire);
+
+        if state & WRITER == 0 {
+            Some(Stamp(state & VERSION))
+        } else {
+            None
+        }
+    }
+
+    /// Checks if no writer has acquired the lock since the stamp was taken
+    ///
+    /// If this returns true, then all reads since the stamp was taken
+    /// did not race with any writes.
+    #[inline]
+    pub fn validate(&self, stamp: Stamp) -> bool {
+        // make sure that all reads of the value happen before we check the version
+        fence(Ordering::Acquire);
+        self.state.load(Ordering::Relaxed) & VERSION == stamp.0
+    }
+
+    /// Acquires a read lock if the stamp is still valid
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_convert_to_read(&self, stamp: Stamp) -> Option<StampedReadGuard<'_, T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // the stamp never has the writer bit set, so this also checks that there is no writer
+        while state & VERSION == stamp.0 && state & READERS != READERS {
+            match self.state.compare_exchange_weak(
+                state,
+                state + ONE_READER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(StampedReadGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+
+        None
+    }
+
+    /// Acquires a write lock if the stamp is still valid, and there are no readers
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_convert_to_write(&self, stamp: Stamp) -> Option<StampedWriteGuard<'_, T>> {
+        self.state
+            .compare_exchange(
+                stamp.0,
+                stamp.0.wrapping_add(WRITER),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .ok()
+            .map(|_| self.write_guard())
+    }
+
+    #[inline]
+    fn write_guard(&self) -> StampedWriteGuard<'_, T> {
+        // make sure that optimistic readers see the writer bit before any writes to the value
+        fence(Ordering::Release);
+
+        StampedWriteGuard { lock: self }
+    }
+
+    /// Attempts to acquire a read lock
+    ///
+    /// If a writer holds the lock, then None is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_read(&self) -> Option<StampedReadGuard<'_, T>> {
+        let state = self.state.load(Ordering::Relaxed);
+
+        if state & WRITER == 0 && state & READERS != READERS {
+            self.state
+                .compare_exchange(
+                    state,
+                    state + ONE_READER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .ok()
+                .map(|_| StampedReadGuard { lock: self })
+        } else {
+            None
+        }
+    }
+
+    /// Acquires a read lock, blocking the current thread until it is able to do so.
+    #[inline]
+    pub fn read(&self) -> StampedReadGuard<'_, T> {
+        match self.try_read() {
+            Some(guard) => guard,
+            None => self.read_slow(),
+        }
+    }
+
+    #[cold]
+    fn read_slow(&self) -> StampedReadGuard<'_, T> {
+        let mut spin = SpinWait::new();
+
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+
+            spin.spin();
+        }
+    }
+
+    /// Attempts to acquire the write lock
+    ///
+    /// If any readers or a writer holds the lock, then None is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_write(&self) -> Option<StampedWriteGuard<'_, T>> {
+        let state = self.state.load(Ordering::Relaxed);
+
+        if state & (WRITER | READERS) == 0 {
+            self.try_convert_to_write(Stamp(state))
+        } else {
+            None
+        }
+    }
+
+    /// Acquires the write lock, blocking the current thread until it is able to do so.
+    #[inline]
+    pub fn write(&self) -> StampedWriteGuard<'_, T> {
+        match self.try_write() {
+            Some(guard) => guard,
+            None => self.write_slow(),
+        }
+    }
+
+    #[cold]
+    fn write_slow(&self) -> StampedWriteGuard<'_, T> {
+        let mut spin = SpinWait::new();
+
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+
+            spin.spin();
+        }
+    }
+}
+
+impl<T: Copy> StampedLock<T> {
+    /// Reads the value optimistically, and validates the read with the given stamp
+    ///
+    /// If a writer acquired the lock since the stamp was taken, then None is returned
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn read_optimistic(&self, stamp: Stamp) -> Option<T> {
+        // SAFETY: a writer may be concurrently writing to the value, so it is read
+        // as a `MaybeUninit<T>`, which is only assumed to be initialized once
+        // we have checked that no writer could have changed it
+        let value = unsafe { core::ptr::read_volatile(self.value.get() as *const MaybeUninit<T>) };
+
+        if self.validate(stamp) {
+            // SAFETY: no writer changed the value while we were reading it
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: ?Sized> StampedReadGuard<'a, T> {
+    /// Attempts to upgrade this read lock to a write lock
+    ///
+    /// This only succeeds if this is the only read lock.
+    ///
+    /// This function does not block.
+    pub fn try_upgrade(g: Self) -> Result<StampedWriteGuard<'a, T>, Self> {
+        let lock = g.lock;
+        let state = lock.state.load(Ordering::Relaxed);
+
+        if state & READERS == ONE_READER
+            && lock
+                .state
+                .compare_exchange(
+                    state,
+                    (state - ONE_READER).wrapping_add(WRITER),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            core::mem::forget(g);
+            Ok(lock.write_guard())
+        } else {
+            Err(g)
+        }
+    }
+
+    /// Releases this read lock, and returns a stamp for an optimistic read
+    ///
+    /// The stamp is valid until the next time a writer acquires the lock.
+    pub fn into_optimistic(g: Self) -> Stamp {
+        // no writer can acquire the lock while we hold a read lock, so the version can't change
+        let stamp = Stamp(g.lock.state.load(Ordering::Relaxed) & VERSION);
+        drop(g);
+        stamp
+    }
+}
+
+impl<'a, T: ?Sized> StampedWriteGuard<'a, T> {
+    /// Atomically downgrades this write lock into a read lock
+    pub fn downgrade(g: Self) -> StampedReadGuard<'a, T> {
+        let lock = g.lock;
+        core::mem::forget(g);
+
+        // release the writer, which bumps the version, and add a reader
+        lock.state.fetch_add(WRITER + ONE_READER, Ordering::Release);
+
+        StampedReadGuard { lock }
+    }
+
+    /// Releases this write lock, and returns a stamp for an optimistic read
+    ///
+    /// The stamp is valid until the next time a writer acquires the lock.
+    pub fn into_optimistic(g: Self) -> Stamp {
+        let lock = g.lock;
+        core::mem::forget(g);
+
+        let state = lock.state.fetch_add(WRITER, Ordering::Release);
+        Stamp(state.wrapping_add(WRITER) & VERSION)
+    }
+}
+
+impl<T: ?Sized> Deref for StampedReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for StampedReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(ONE_READER, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized> Deref for StampedWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for StampedWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for StampedWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // clears the writer bit, and bumps the version
+        self.lock.state.fetch_add(WRITER, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StampedLock, StampedReadGuard, StampedWriteGuard};
+
+    #[test]
+    fn modes() {
+        let lock = StampedLock::new((0, 0));
+
+        let stamp = lock.try_optimistic_read().unwrap();
+        assert_eq!(lock.read_optimistic(stamp), Some((0, 0)));
+
+        let guard = lock.try_convert_to_read(stamp).unwrap();
+        let guard = StampedReadGuard::try_upgrade(guard).ok().unwrap();
+        assert!(lock.try_optimistic_read().is_none());
+        assert!(!lock.validate(stamp));
+
+        let guard = StampedWriteGuard::downgrade(guard);
+        assert!(lock.try_write().is_none());
+        let stamp = StampedReadGuard::into_optimistic(guard);
+
+        let mut guard = lock.try_convert_to_write(stamp).unwrap();
+        *guard = (1, 1);
+        let stamp = StampedWriteGuard::into_optimistic(guard);
+        assert_eq!(lock.read_optimistic(stamp), Some((1, 1)));
+
+        *lock.write() = (2, 2);
+        assert_eq!(lock.read_optimistic(stamp), None);
+        assert!(lock.try_convert_to_read(stamp).is_none());
+    }
+
+    #[test]
+    fn no_torn_reads() {
+        let lock = StampedLock::new([0_u64; 8]);
+
+        crossbeam_utils::thread::scope(|s| {
+            s.spawn(|_| {
+                for i in 1..10_000 {
+                    *lock.write() = [i; 8];
+                }
+            });
+
+            for _ in 0..10_000 {
+                let value = match lock.try_optimistic_read() {
+                    Some(stamp) => lock.read_optimistic(stamp),
+                    None => None,
+                };
+
+                let value = value.unwrap_or_else(|| *lock.read());
+                assert!(value.iter().all(|&x| x == value[0]));
+            }
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+//! Run a closure while holding several `RwLock`s, without deadlocking
+//!
+//! A [`transaction`] takes a tuple of [`read`] and [`write`] handles, and acquires all of them
+//! before running the closure. The locks are acquired in the order of their addresses, like
+//! [`lock_all`](crate::multi_lock::lock_all), but only the first lock is acquired by blocking,
+//! the rest are acquired with a `try_*` method. If that fails, then the transaction conflicted
+//! with another thread, so every lock is released, and the transaction is retried by first
+//! blocking on the lock that conflicted. So a transaction never blocks while holding a lock,
+//! and can't deadlock even with threads that lock the same locks in some other order.
+//!
+//! The closure gets a mutable reference to the tuple of guards, so the guards can't escape it.
+//!
+//! ```
+//! use locker::rwlock::default::DefaultLock;
+//!
+//! let a = DefaultLock::rwlock(1);
+//! let b = DefaultLock::rwlock(2);
+//! let c = DefaultLock::rwlock(0);
+//!
+//! locker::transaction!(read &a, read &b, write &c => |(a, b, c)| {
+//!     **c = **a + **b;
+//! });
+//!
+//! assert_eq!(*c.read(), 3);
+//! ```
+
+use crate::exclusive_lock::ExclusiveGuard;
+use crate::multi_lock::Lockable;
+use crate::relax::Backoff;
+use crate::rwlock::{RawRwLock, RwLock};
+use crate::share_lock::ShareGuard;
+
+/// A [`Lockable`] which can also be acquired without blocking
+pub trait TryLockable: Lockable {
+    /// Attempt to acquire the lock, without blocking
+    fn try_acquire(&self) -> Option<Self::Guard>;
+}
+
+/// Acquire a *shr lock* in a [`transaction`], like [`RwLock::read`]
+pub struct Read<'a, L, T: ?Sized>(&'a RwLock<L, T>);
+
+/// Acquire an *exc lock* in a [`transaction`], like [`RwLock::write`]
+pub struct Write<'a, L, T: ?Sized>(&'a RwLock<L, T>);
+
+/// Read from an `RwLock` in a [`transaction`]
+#[inline]
+pub fn read<L, T: ?Sized>(lock: &RwLock<L, T>) -> Read<'_, L, T> {
+    Read(lock)
+}
+
+/// Write to an `RwLock` in a [`transaction`]
+#[inline]
+pub fn write<L, T: ?Sized>(lock: &RwLock<L, T>) -> Write<'_, L, T> {
+    Write(lock)
+}
+
+impl<'a, L: RawRwLock, T: ?Sized> Lockable for Read<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    type Guard = ShareGuard<'a, L, T>;
+
+    #[inline]
+    fn address(&self) -> usize {
+        self.0.raw().inner().exc_lock_addr()
+    }
+
+    #[inline]
+    fn acquire(&self) -> Self::Guard {
+        self.0.read()
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> TryLockable for Read<'_, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn try_acquire(&self) -> Option<Self::Guard> {
+        self.0.try_read()
+    }
+}
+
+impl<'a, L: RawRwLock, T: ?Sized> Lockable for Write<'a, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    type Guard = ExclusiveGuard<'a, L, T>;
+
+    #[inline]
+    fn address(&self) -> usize {
+        self.0.raw().inner().exc_lock_addr()
+    }
+
+    #[inline]
+    fn acquire(&self) -> Self::Guard {
+        self.0.write()
+    }
+}
+
+impl<L: RawRwLock, T: ?Sized> TryLockable for Write<'_, L, T>
+where
+    L::ExclusiveGuardTraits: crate::Inhabitted,
+    L::ShareGuardTraits: crate::Inhabitted,
+{
+    #[inline]
+    fn try_acquire(&self) -> Option<Self::Guard> {
+        self.0.try_write()
+    }
+}
+
+/// A tuple of [`TryLockable`]s which can be acquired by [`transaction`]
+pub trait TransactionLocks {
+    /// A tuple of the guards of each lock
+    type Guards;
+
+    /// Acquire all of the locks, retrying on conflict
+    fn lock_transaction(&self) -> Self::Guards;
+}
+
+macro_rules! transaction_locks {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: TryLockable),+> TransactionLocks for ($($name,)+) {
+            type Guards = ($($name::Guard,)+);
+
+            fn lock_transaction(&self) -> Self::Guards {
+                let mut order = [$((self.$index.address(), $index)),+];
+                order.sort_unstable();
+
+                if order.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                    crate::multi_lock::same_lock()
+                }
+
+                let mut backoff = Backoff::new();
+                // the lock to block on, only a lock that conflicted is blocked on
+                let mut first = order[0].1;
+
+                loop {
+                    let mut guards = ($(None::<$name::Guard>,)+);
+                    let mut conflict = None;
+
+                    match first {
+                        $($index => guards.$index = Some(self.$index.acquire()),)+
+                        _ => unreachable!(),
+                    }
+
+                    for &(_, index) in order.iter() {
+                        if index == first {
+                            continue;
+                        }
+
+                        let acquired = match index {
+                            $($index => match self.$index.try_acquire() {
+                                Some(guard) => {
+                                    guards.$index = Some(guard);
+                                    true
+                                }
+                                None => false,
+                            },)+
+                            _ => unreachable!(),
+                        };
+
+                        if !acquired {
+                            conflict = Some(index);
+                            break;
+                        }
+                    }
+
+                    match conflict {
+                        None => {
+                            return ($(match guards.$index {
+                                Some(guard) => guard,
+                                None => unreachable!(),
+                            },)+)
+                        }
+                        Some(index) => {
+                            drop(guards);
+                            first = index;
+                            backoff.snooze();
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+transaction_locks!(A 0);
+transaction_locks!(A 0, B 1);
+transaction_locks!(A 0, B 1, C 2);
+transaction_locks!(A 0, B 1, C 2, D 3);
+transaction_locks!(A 0, B 1, C 2, D 3, E 4);
+transaction_locks!(A 0, B 1, C 2, D 3, E 4, F 5);
+transaction_locks!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+transaction_locks!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Acquire a tuple of [`read`] and [`write`] handles, and run `f` with their guards
+///
+/// Tuples of up to 8 locks are supported, see the [module level docs](self) for details
+///
+/// # Panic
+///
+/// This function panics if the same lock is passed in more than once,
+/// or if two of the locks share an underlying lock
+pub fn transaction<A: TransactionLocks, R, F: FnOnce(&mut A::Guards) -> R>(locks: A, f: F) -> R {
+    let mut guards = locks.lock_transaction();
+    f(&mut guards)
+}
+
+/// Run a [`transaction`] over some `RwLock`s
+///
+/// Each lock is prefixed by `read` or `write`, and the closure after the `=>` gets a mutable
+/// reference to the tuple of guards, in the same order as the locks.
+///
+/// `transaction!(read &a, write &b => f)` is the same as
+/// `transaction((read(&a), write(&b)), f)`
+#[macro_export]
+macro_rules! transaction {
+    ($($mode:ident $lock:expr),+ $(,)? => $f:expr) => {
+        $crate::transaction::transaction(($($crate::transaction::$mode($lock),)+), $f)
+    };
+}
+
+#[test]
+fn test_transaction() {
+    use crate::rwlock::default::DefaultLock;
+
+    let a = DefaultLock::rwlock(0);
+    let b = DefaultLock::rwlock(0);
+    let c = DefaultLock::rwlock(0);
+
+    crossbeam_utils::thread::scope(|s| {
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                transaction!(write &a, write &b, read &c => |(a, b, _)| {
+                    **a += 1;
+                    **b += 1;
+                });
+            }
+        });
+
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                transaction!(read &b, write &c, write &a => |(b, c, a)| {
+                    assert_eq!(**a, **b);
+                    **c += 1;
+                });
+            }
+        });
+
+        // lock in a conflicting order by hand
+        s.spawn(|_| {
+            for _ in 0..1000 {
+                let _c = c.read();
+                let _b = b.read();
+            }
+        });
+    })
+    .unwrap();
+
+    assert_eq!((*a.read(), *b.read(), *c.read()), (1000, 1000, 1000));
+
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        transaction!(read &a, write &a => |_| ())
+    }))
+    .is_err());
+
+    // there are more rwlocks than global locks, so some of them must share a lock
+    use crate::rwlock::global::GlobalLock;
+    let globals: [_; 64] = core::array::from_fn(GlobalLock::rwlock);
+    let (a, b) = (0..64)
+        .flat_map(|i| (i + 1..64).map(move |j| (i, j)))
+        .find(|&(i, j)| GlobalLock::will_rwlock_contend(&globals[i], &globals[j]))
+        .unwrap();
+
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        transaction!(write &globals[a], write &globals[b] => |_| ())
+    }))
+    .is_err());
+}